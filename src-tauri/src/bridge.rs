@@ -0,0 +1,48 @@
+//! 前端事件桥接
+//!
+//! 订阅监控事件总线与包装器事件，将事件转发为 Tauri 前端事件。
+
+use crate::monitor::event_bus::EventSubscription;
+use crate::wrapper::ProcessWrapperManager;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+/// 启动 UI 事件桥接任务
+pub fn spawn_ui_bridge(app: AppHandle, mut events: EventSubscription) {
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("UI 事件桥接已启动");
+
+        while let Some(event) = events.recv().await {
+            if let Err(e) = app.emit(event.ui_event_name(), &event) {
+                tracing::warn!("推送前端事件失败 {}: {}", event.ui_event_name(), e);
+            }
+        }
+
+        tracing::info!("UI 事件桥接已停止");
+    });
+}
+
+/// 启动包装器事件桥接任务（已附加会话的终端输出、进程退出）
+pub fn spawn_wrapper_bridge(app: AppHandle, manager: ProcessWrapperManager) {
+    let mut events = manager.subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("包装器事件积压，已丢弃 {} 条", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            if !manager.should_forward(&event).await {
+                continue;
+            }
+            if let Err(e) = app.emit(event.ui_event_name(), &event) {
+                tracing::warn!("推送前端事件失败 {}: {}", event.ui_event_name(), e);
+            }
+        }
+    });
+}
@@ -1,5 +1,14 @@
+use crate::commands::launch_terminal;
 use crate::error::AppError;
+use crate::models::SessionStatus;
+use crate::monitor::history;
 use crate::state::AppState;
+use crate::tmux;
+use crate::worktree;
+use crate::wrapper::buffer::TerminalSnapshot;
+use crate::wrapper::input;
+use crate::wrapper::permission::{PermissionDecision, PermissionPrompt};
+use crate::wrapper::StartOptions;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
@@ -10,25 +19,174 @@ pub struct SessionConnection {
     pub session_id: String,
     pub project_path: String,
     pub can_send_input: bool,
+    /// 附加时缓冲中的终端输出（仅应用启动的会话）
+    pub terminal: Option<TerminalSnapshot>,
+}
+
+/// 启动新会话
+///
+/// 在应用持有的伪终端中启动 Claude Code，会话立即出现在会话列表中。
+/// 使用项目的启动配置（`profile` 指定，否则为默认配置）补全未显式给出的选项与初始提示。
+/// `in_worktree` 为 true 时先在新分支（`branch`，为空时自动生成）上创建 git worktree，
+/// 会话在 worktree 中运行，与同一仓库中的其他会话互不干扰。
+#[tauri::command]
+pub async fn start_session(
+    project_path: String,
+    initial_prompt: Option<String>,
+    options: Option<StartOptions>,
+    profile: Option<String>,
+    in_worktree: Option<bool>,
+    branch: Option<String>,
+    state: State<'_, AppState>,
+) -> std::result::Result<SessionConnection, String> {
+    let path = std::path::Path::new(&project_path);
+    if !path.is_dir() {
+        return Err(AppError::ProjectNotFound(project_path).to_string());
+    }
+
+    let config = state
+        .storage
+        .get_project(&project_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|p| p.config)
+        .unwrap_or_default();
+    let launch_profile = config.profile(profile.as_deref());
+    if let (Some(name), None) = (&profile, launch_profile) {
+        return Err(AppError::InvalidInput(format!("启动配置不存在: {}", name)).to_string());
+    }
+
+    let mut options = options.unwrap_or_default();
+    let mut initial_prompt = initial_prompt.filter(|p| !p.trim().is_empty());
+    match launch_profile {
+        Some(launch_profile) => {
+            options =
+                options.with_defaults(StartOptions::from_profile(launch_profile, &config.env_vars));
+            // 恢复会话时不发送配置中的初始提示
+            if options.resume.is_none() {
+                initial_prompt = initial_prompt.or_else(|| launch_profile.initial_prompt.clone());
+            }
+        }
+        None => {
+            options = options.with_defaults(StartOptions {
+                env: config.env_vars.clone(),
+                ..StartOptions::default()
+            });
+        }
+    }
+
+    let worktree = if in_worktree.unwrap_or(false) {
+        // 恢复的会话记录在原项目目录下，无法换到新 worktree 中继续
+        if options.resume.is_some() {
+            return Err(
+                AppError::InvalidInput("恢复会话不能在新 worktree 中启动".to_string()).to_string(),
+            );
+        }
+        let project = path.to_path_buf();
+        let worktree =
+            tokio::task::spawn_blocking(move || worktree::create(&project, branch.as_deref()))
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| e.to_string())?;
+        Some(worktree)
+    } else {
+        None
+    };
+    let launch_path = worktree.as_ref().map(|w| w.path.as_path()).unwrap_or(path);
+
+    let session = match state
+        .process_wrapper
+        .start(launch_path, initial_prompt.as_deref(), options)
+        .await
+    {
+        Ok(session) => session,
+        Err(e) => {
+            // 启动失败时移除刚创建的 worktree 与分支
+            if let Some(worktree) = worktree {
+                let cleanup =
+                    tokio::task::spawn_blocking(move || worktree::cleanup(&worktree.path, true))
+                        .await;
+                if let Ok(Err(cleanup_error)) = cleanup {
+                    tracing::warn!("清理 worktree 失败: {}", cleanup_error);
+                }
+            }
+            return Err(e.to_string());
+        }
+    };
+
+    if let Err(e) = state
+        .storage
+        .update_project(&project_path, |project| {
+            project.last_accessed_at = chrono::Utc::now();
+            project.session_count += 1;
+        })
+        .await
+    {
+        tracing::warn!("更新项目记录失败 {}: {}", project_path, e);
+    }
+
+    let can_send_input = state
+        .process_wrapper
+        .get(&session.id)
+        .await
+        .map(|wrapper| wrapper.is_running())
+        .unwrap_or(false);
+
+    Ok(SessionConnection {
+        session_id: session.id,
+        project_path: session.project_path,
+        can_send_input,
+        terminal: None,
+    })
+}
+
+/// 恢复历史会话
+///
+/// 在项目目录中执行 `claude --resume <sessionId>`。默认在应用持有的伪终端中启动并返回连接信息；
+/// `in_terminal` 为 true 时改为在用户的终端中打开，返回 None。
+#[tauri::command]
+pub async fn resume_session(
+    project_path: String,
+    session_id: String,
+    in_terminal: Option<bool>,
+    state: State<'_, AppState>,
+) -> std::result::Result<Option<SessionConnection>, String> {
+    if !history::is_valid_session_id(&session_id) {
+        return Err(AppError::InvalidInput(format!("会话 ID 无效: {}", session_id)).to_string());
+    }
+
+    if in_terminal.unwrap_or(false) {
+        launch_terminal(&state, &project_path, Some(&session_id)).await?;
+        return Ok(None);
+    }
+
+    let options = StartOptions {
+        resume: Some(session_id),
+        ..StartOptions::default()
+    };
+    start_session(project_path, None, Some(options), None, None, None, state)
+        .await
+        .map(Some)
 }
 
 /// 附加到会话（打开对话弹窗）
 ///
 /// 建立与活跃会话的连接，获取进程信息以便后续交互。
-/// 注意：直接向 Claude Code 进程发送输入需要 PTY 支持，当前仅返回连接信息。
+/// 仅应用启动的会话可发送输入，外部终端中的会话只读。
+/// 应用启动的会话附加后开始推送终端输出，并返回缓冲中的输出供回放。
 #[tauri::command]
 pub async fn attach_to_session(
     session_id: String,
     state: State<'_, AppState>,
 ) -> std::result::Result<SessionConnection, String> {
-    let monitor = state.monitor.read().await;
-
     // 获取会话
-    let session = monitor
+    let session = state
+        .monitor
         .get_session(&session_id)
-        .await
         .ok_or_else(|| AppError::SessionNotFound(session_id.clone()).to_string())?;
 
+    let managed = state.process_wrapper.get(&session.id).await.is_some();
+
     // 验证会话状态
     match session.status {
         crate::models::SessionStatus::Running => {
@@ -44,55 +202,275 @@ pub async fn attach_to_session(
             return Err("会话已完成，无法附加".to_string());
         }
         crate::models::SessionStatus::Blocked => {
-            return Err("会话被阻塞，无法附加".to_string());
+            // 应用启动的会话可在终端视图中处理阻塞
+            if !managed {
+                return Err("会话被阻塞，无法附加".to_string());
+            }
         }
         crate::models::SessionStatus::Unknown => {
             // 未知状态，谨慎处理
         }
     }
 
+    // 应用启动的会话写入伪终端，tmux 中的会话经 send-keys 写入
+    let can_send_input =
+        state.process_wrapper.can_send_input(&session.id).await || session.tmux_pane.is_some();
+    let terminal = if managed {
+        Some(
+            state
+                .process_wrapper
+                .attach(&session.id)
+                .await
+                .map_err(|e| e.to_string())?,
+        )
+    } else {
+        None
+    };
+
     Ok(SessionConnection {
         session_id: session.id.clone(),
         project_path: session.project_path.clone(),
-        can_send_input: false, // 暂时不支持直接发送输入
+        can_send_input,
+        terminal,
     })
 }
 
 /// 发送消息
 ///
-/// 向会话发送消息。
-/// 注意：由于 Claude Code 进程的 stdin 不直接可用，此功能暂时未实现。
+/// 向会话写入消息，并在日志中出现对应的用户消息后返回。
+/// 应用启动的会话写入伪终端；运行在 tmux 中的会话通过 send-keys 写入所在窗格。
 #[tauri::command]
 pub async fn send_message(
-    _session_id: String,
-    _content: String,
-    _state: State<'_, AppState>,
+    session_id: String,
+    content: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    deliver_message(&state, &session_id, &content).await
+}
+
+/// 向会话写入消息并等待日志确认
+pub async fn deliver_message(
+    state: &AppState,
+    session_id: &str,
+    content: &str,
 ) -> std::result::Result<(), String> {
-    // 暂时不实现：直接向 Claude Code 进程发送 stdin 不可行
-    // 如需此功能，建议：
-    // 1. 通过项目文件进行交互（让 Claude Code 监听文件变化）
-    // 2. 使用 VS Code MCP 协议
-    // 3. 使用 pty 重新启动 Claude Code 进程
-    Err("此功能暂时未实现。可以通过打开终端在项目目录中与 Claude Code 交互。".to_string())
+    if state.process_wrapper.can_send_input(session_id).await {
+        return state
+            .process_wrapper
+            .send_message(session_id, content)
+            .await
+            .map_err(|e| e.to_string());
+    }
+
+    let session = state
+        .monitor
+        .get_session(session_id)
+        .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()).to_string())?;
+    let Some(pane) = session.tmux_pane else {
+        return Err("该会话不是由应用启动，也不在 tmux 中运行，无法直接发送消息。可以通过打开终端在项目目录中与 Claude Code 交互。".to_string());
+    };
+
+    let content = content.trim();
+    if content.is_empty() {
+        return Err(AppError::InvalidInput("消息内容为空".to_string()).to_string());
+    }
+
+    let baseline = input::log_baseline(&state.monitor, &session.project_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    tmux::send_keys(&pane, content).map_err(|e| e.to_string())?;
+    input::confirm_delivery(
+        &state.monitor,
+        &session.project_path,
+        baseline,
+        content,
+        || true,
+    )
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// 广播投递状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DeliveryStatus {
+    /// 已在日志中确认送达
+    Delivered,
+    /// 未发送（状态不符或无法写入）
+    Skipped,
+    /// 发送失败
+    Failed,
+}
+
+/// 单个会话的广播结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastDelivery {
+    pub session_id: String,
+    pub status: DeliveryStatus,
+    /// 跳过或失败的原因
+    pub message: Option<String>,
+}
+
+impl BroadcastDelivery {
+    fn new(session_id: &str, status: DeliveryStatus, message: Option<String>) -> Self {
+        Self {
+            session_id: session_id.to_string(),
+            status,
+            message,
+        }
+    }
+}
+
+/// 向多个会话广播同一条消息
+///
+/// 目标为 `session_ids` 与 `project_path` 下所有会话的并集。
+/// 默认跳过未处于等待输入状态的会话，`force` 为 true 时照常发送。
+/// 各会话并行发送，按目标顺序返回每个会话的投递结果。
+#[tauri::command]
+pub async fn broadcast_message(
+    session_ids: Vec<String>,
+    content: String,
+    project_path: Option<String>,
+    force: Option<bool>,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<BroadcastDelivery>, String> {
+    let content = content.trim().to_string();
+    if content.is_empty() {
+        return Err(AppError::InvalidInput("消息内容为空".to_string()).to_string());
+    }
+    let force = force.unwrap_or(false);
+
+    let mut targets = session_ids;
+    if let Some(project_path) = project_path {
+        let root = std::path::Path::new(&project_path);
+        targets.extend(
+            state
+                .monitor
+                .sessions()
+                .iter()
+                .filter(|s| std::path::Path::new(&s.project_path).starts_with(root))
+                .map(|s| s.id.clone()),
+        );
+    }
+    let mut seen = std::collections::HashSet::new();
+    targets.retain(|id| seen.insert(id.clone()));
+
+    let mut results: Vec<Option<BroadcastDelivery>> = vec![None; targets.len()];
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, session_id) in targets.iter().enumerate() {
+        let Some(session) = state.monitor.get_session(session_id) else {
+            results[index] = Some(BroadcastDelivery::new(
+                session_id,
+                DeliveryStatus::Failed,
+                Some(AppError::SessionNotFound(session_id.clone()).to_string()),
+            ));
+            continue;
+        };
+        if session.status != SessionStatus::WaitingInput && !force {
+            results[index] = Some(BroadcastDelivery::new(
+                session_id,
+                DeliveryStatus::Skipped,
+                Some(format!(
+                    "会话未在等待输入（当前状态: {}）",
+                    session.status.display_name()
+                )),
+            ));
+            continue;
+        }
+        if !state.process_wrapper.can_send_input(session_id).await && session.tmux_pane.is_none() {
+            results[index] = Some(BroadcastDelivery::new(
+                session_id,
+                DeliveryStatus::Skipped,
+                Some("会话不是由应用启动，也不在 tmux 中运行".to_string()),
+            ));
+            continue;
+        }
+
+        let state = state.inner().clone();
+        let session_id = session_id.clone();
+        let content = content.clone();
+        tasks.spawn(async move {
+            let result = deliver_message(&state, &session_id, &content).await;
+            (index, session_id, result)
+        });
+    }
+
+    while let Some(joined) = tasks.join_next().await {
+        let (index, session_id, result) =
+            joined.map_err(|e| AppError::Internal(format!("广播任务失败: {}", e)).to_string())?;
+        results[index] = Some(match result {
+            Ok(()) => BroadcastDelivery::new(&session_id, DeliveryStatus::Delivered, None),
+            Err(e) => BroadcastDelivery::new(&session_id, DeliveryStatus::Failed, Some(e)),
+        });
+    }
+
+    let results: Vec<BroadcastDelivery> = results.into_iter().flatten().collect();
+    let delivered = results
+        .iter()
+        .filter(|r| r.status == DeliveryStatus::Delivered)
+        .count();
+    tracing::info!("广播消息已送达 {}/{} 个会话", delivered, results.len());
+    Ok(results)
 }
 
 /// 脱离会话（关闭对话弹窗）
 ///
-/// 断开会话连接，清理相关状态。
+/// 断开会话连接；应用启动的会话在最后一个视图脱离后停止推送终端输出。
 #[tauri::command]
 pub async fn detach_from_session(
     session_id: String,
     state: State<'_, AppState>,
 ) -> std::result::Result<(), String> {
-    // 验证会话是否存在
-    let monitor = state.monitor.read().await;
-    let _session = monitor
-        .get_session(&session_id)
-        .await
-        .ok_or_else(|| AppError::SessionNotFound(session_id.clone()).to_string())?;
-
-    // 当前不需要清理额外状态，未来可以根据需要扩展
+    // 进程可能已先于视图退出，脱离总是成功
+    state.process_wrapper.detach(&session_id).await;
     tracing::info!("已脱离会话: {}", session_id);
 
     Ok(())
 }
+
+/// 调整会话终端大小
+///
+/// 终端视图尺寸变化时调用，仅对应用启动的会话有效。
+#[tauri::command]
+pub async fn resize_terminal(
+    session_id: String,
+    rows: u16,
+    cols: u16,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    state
+        .process_wrapper
+        .resize(&session_id, rows, cols)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取会话当前的权限提示
+///
+/// 仅应用启动的会话可识别；没有停在权限确认界面时返回 None。
+#[tauri::command]
+pub async fn get_permission_prompt(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<Option<PermissionPrompt>, String> {
+    state
+        .process_wrapper
+        .permission_prompt(&session_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 应答权限提示（批准、始终批准或拒绝）
+#[tauri::command]
+pub async fn respond_permission(
+    session_id: String,
+    decision: PermissionDecision,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    state
+        .process_wrapper
+        .respond_permission(&session_id, decision)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1,7 +1,21 @@
 mod session;
 mod chat;
+mod project;
+mod queue;
+mod retention;
+mod search;
 mod system;
+mod webhook;
+mod workflow;
+mod worktree;
 
 pub use session::*;
 pub use chat::*;
+pub use project::*;
+pub use queue::*;
+pub use retention::*;
+pub use search::*;
 pub use system::*;
+pub use webhook::*;
+pub use workflow::*;
+pub use worktree::*;
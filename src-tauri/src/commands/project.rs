@@ -0,0 +1,110 @@
+use crate::error::AppError;
+use crate::models::{LaunchProfile, ProjectConfig};
+use crate::state::AppState;
+use tauri::State;
+
+/// 获取项目配置（未保存过时返回默认配置）
+#[tauri::command]
+pub async fn get_project_config(
+    project_path: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<ProjectConfig, String> {
+    let project = state
+        .storage
+        .get_project(&project_path)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(project.and_then(|p| p.config).unwrap_or_default())
+}
+
+/// 新建或更新项目的启动配置（按名称匹配）
+#[tauri::command]
+pub async fn save_launch_profile(
+    project_path: String,
+    profile: LaunchProfile,
+    state: State<'_, AppState>,
+) -> std::result::Result<ProjectConfig, String> {
+    if profile.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("启动配置名称为空".to_string()).to_string());
+    }
+
+    update_project_config(&state, &project_path, |config| {
+        match config
+            .launch_profiles
+            .iter_mut()
+            .find(|p| p.name == profile.name)
+        {
+            Some(existing) => *existing = profile,
+            None => config.launch_profiles.push(profile),
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// 删除项目的启动配置
+#[tauri::command]
+pub async fn delete_launch_profile(
+    project_path: String,
+    name: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<ProjectConfig, String> {
+    update_project_config(&state, &project_path, |config| {
+        let before = config.launch_profiles.len();
+        config.launch_profiles.retain(|p| p.name != name);
+        if config.launch_profiles.len() == before {
+            return Err(format!("启动配置不存在: {}", name));
+        }
+        if config.default_profile.as_deref() == Some(name.as_str()) {
+            config.default_profile = None;
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// 设置项目的默认启动配置（为空时取消默认）
+#[tauri::command]
+pub async fn set_default_launch_profile(
+    project_path: String,
+    name: Option<String>,
+    state: State<'_, AppState>,
+) -> std::result::Result<ProjectConfig, String> {
+    update_project_config(&state, &project_path, |config| {
+        if let Some(name) = &name {
+            if !config.launch_profiles.iter().any(|p| &p.name == name) {
+                return Err(format!("启动配置不存在: {}", name));
+            }
+        }
+        config.default_profile = name;
+        Ok(())
+    })
+    .await
+}
+
+/// 修改项目配置并保存
+async fn update_project_config<F>(
+    state: &AppState,
+    project_path: &str,
+    f: F,
+) -> std::result::Result<ProjectConfig, String>
+where
+    F: FnOnce(&mut ProjectConfig) -> std::result::Result<(), String>,
+{
+    let mut config = state
+        .storage
+        .get_project(project_path)
+        .await
+        .map_err(|e| e.to_string())?
+        .and_then(|p| p.config)
+        .unwrap_or_default();
+    f(&mut config)?;
+
+    let saved = config.clone();
+    state
+        .storage
+        .update_project(project_path, move |project| project.config = Some(saved))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(config)
+}
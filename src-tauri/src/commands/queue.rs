@@ -0,0 +1,77 @@
+use crate::queue::PromptQueue;
+use crate::state::AppState;
+use tauri::State;
+
+/// 获取会话的提示队列
+#[tauri::command]
+pub async fn get_prompt_queue(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<PromptQueue, String> {
+    Ok(state.prompt_queue.get(&session_id).await)
+}
+
+/// 追加提示到会话队列
+///
+/// 会话由应用启动且正在等待输入时立即发送队首提示。
+#[tauri::command]
+pub async fn enqueue_prompt(
+    session_id: String,
+    content: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<PromptQueue, String> {
+    state
+        .prompt_queue
+        .update(&session_id, |queue| queue.enqueue(&content))
+        .await
+        .map(|(_, queue)| queue)
+        .map_err(|e| e.to_string())
+}
+
+/// 从会话队列中移除提示
+#[tauri::command]
+pub async fn remove_queued_prompt(
+    session_id: String,
+    prompt_id: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<PromptQueue, String> {
+    state
+        .prompt_queue
+        .update(&session_id, |queue| queue.remove(&prompt_id))
+        .await
+        .map(|(_, queue)| queue)
+        .map_err(|e| e.to_string())
+}
+
+/// 按给定的提示 ID 顺序重排会话队列
+#[tauri::command]
+pub async fn reorder_prompt_queue(
+    session_id: String,
+    prompt_ids: Vec<String>,
+    state: State<'_, AppState>,
+) -> std::result::Result<PromptQueue, String> {
+    state
+        .prompt_queue
+        .update(&session_id, |queue| queue.reorder(&prompt_ids))
+        .await
+        .map(|(_, queue)| queue)
+        .map_err(|e| e.to_string())
+}
+
+/// 暂停或恢复会话队列的自动发送
+#[tauri::command]
+pub async fn set_queue_paused(
+    session_id: String,
+    paused: bool,
+    state: State<'_, AppState>,
+) -> std::result::Result<PromptQueue, String> {
+    state
+        .prompt_queue
+        .update(&session_id, |queue| {
+            queue.paused = paused;
+            Ok(())
+        })
+        .await
+        .map(|(_, queue)| queue)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,19 @@
+use crate::retention::{self, RetentionReport};
+use crate::state::AppState;
+use tauri::State;
+
+/// 按当前配置立即执行一次保留策略
+#[tauri::command]
+pub async fn run_retention(
+    state: State<'_, AppState>,
+) -> std::result::Result<RetentionReport, String> {
+    retention::run_now(&state).await.map_err(|e| e.to_string())
+}
+
+/// 获取保留策略执行报告（最新的在前）
+#[tauri::command]
+pub async fn get_retention_reports(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<RetentionReport>, String> {
+    Ok(state.retention.reports())
+}
@@ -0,0 +1,22 @@
+use crate::search::{SearchFilters, SessionSearchResult};
+use crate::state::AppState;
+use tauri::State;
+
+/// 全文搜索历史会话
+///
+/// 在所有根目录日志的用户与助手文本、工具名和文件路径中搜索，
+/// 返回命中的会话及带高亮的摘要和消息 uuid。
+#[tauri::command]
+pub async fn search_sessions(
+    query: String,
+    filters: Option<SearchFilters>,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<SessionSearchResult>, String> {
+    let index = state.search.clone();
+    let filters = filters.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || index.search(&query, &filters))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
@@ -1,53 +1,202 @@
-use crate::models::{Message, Session, SessionDetail, SessionStatus};
+use crate::models::{Message, Session, SessionDetail, SessionStatus, StatusTransition};
+use crate::monitor::history::{self, ProjectHistory};
 use crate::monitor::status_detector::StatusDetector;
+use crate::monitor::discovery::SessionDiscovery;
+use crate::process::{self, TerminateOutcome};
 use crate::state::AppState;
-use std::path::PathBuf;
+use nix::sys::signal::Signal;
+use std::time::Duration;
 use tauri::State;
 
+/// 终止会话时等待 SIGTERM 生效的默认秒数
+const DEFAULT_TERMINATE_TIMEOUT_SECS: u64 = 5;
+
+/// 等待锁文件释放的时间
+const LOCK_RELEASE_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// 获取所有活跃会话
 #[tauri::command]
 pub async fn get_all_sessions(state: State<'_, AppState>) -> std::result::Result<Vec<Session>, String> {
     tracing::info!("[get_all_sessions] 命令被调用");
 
-    // 使用 write lock 以支持即时刷新
-    tracing::info!("[get_all_sessions] 尝试获取 monitor 锁...");
-    let mut monitor = state.monitor.write().await;
-    tracing::info!("[get_all_sessions] 获取到 monitor 锁, 调用 instant_refresh...");
-
-    // 调用 instant_refresh 区分新增/存量会话
-    if let Err(e) = monitor.instant_refresh().await {
-        tracing::error!("[get_all_sessions] instant_refresh 错误: {}", e);
-        return Err(e.to_string());
-    }
-
-    let sessions = monitor.get_active_sessions().await.map_err(|e| {
-        tracing::error!("[get_all_sessions] get_active_sessions 错误: {}", e);
-        e.to_string()
-    })?;
+    // 读取监控任务发布的最新快照，不阻塞其他命令
+    let sessions = state.monitor.sessions();
 
     tracing::info!("[get_all_sessions] 获取到 {} 个会话", sessions.len());
 
-    // 只过滤掉 Unknown 状态的会话（Initializing 也展示，显示为运行中）
+    // 合并存储中用户的修改（归档、标记完成、重命名），读取失败时按监控结果展示
+    let stored = state.storage().load_session_index().await.unwrap_or_else(|e| {
+        tracing::warn!("[get_all_sessions] 读取会话索引失败: {}", e);
+        Vec::new()
+    });
+    let overrides: std::collections::HashMap<&str, &Session> =
+        stored.iter().map(|s| (s.id.as_str(), s)).collect();
+
+    // 过滤掉 Unknown 状态与已归档的会话（Initializing 也展示，显示为运行中）
     let filtered_sessions: Vec<Session> = sessions
-        .into_iter()
+        .iter()
         .filter(|s| s.status != SessionStatus::Unknown)
+        .map(|s| {
+            let mut session = s.clone();
+            if let Some(stored) = overrides.get(s.id.as_str()) {
+                session.apply_overrides(stored);
+            }
+            session
+        })
+        .filter(|s| !s.is_archived)
         .collect();
     tracing::info!("[get_all_sessions] 过滤后剩余 {} 个会话", filtered_sessions.len());
 
-    // 如果没有会话，尝试从 storage 加载
-    if filtered_sessions.is_empty() {
-        let storage = state.storage();
-        let storage_sessions = storage.get_active_sessions().await.map_err(|e| e.to_string())?;
-        tracing::info!("[get_all_sessions] 从 storage 获取到 {} 个会话", storage_sessions.len());
-        Ok(storage_sessions)
-    } else {
-        // 打印第一个会话的详细信息用于调试
-        if let Some(first) = filtered_sessions.first() {
-            tracing::info!("[get_all_sessions] 第一个会话: id={}, title={}, status={:?}, created_at={:?}",
-                first.id, first.title, first.status, first.created_at);
+    // 打印第一个会话的详细信息用于调试
+    if let Some(first) = filtered_sessions.first() {
+        tracing::info!("[get_all_sessions] 第一个会话: id={}, title={}, status={:?}, created_at={:?}",
+            first.id, first.title, first.status, first.created_at);
+    }
+    Ok(filtered_sessions)
+}
+
+/// 获取存储中的历史会话（未归档，包括已结束的会话）
+///
+/// 活跃会话列表只包含监控中的会话，历史记录通过此命令单独读取。
+#[tauri::command]
+pub async fn get_session_history(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<Session>, String> {
+    state
+        .storage()
+        .get_active_sessions()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 中断会话
+///
+/// 应用启动的会话向终端发送 Esc；其他会话核对 PID 后发送 SIGINT。
+#[tauri::command]
+pub async fn interrupt_session(id: String, state: State<'_, AppState>) -> std::result::Result<(), String> {
+    if let Some(wrapper) = state.process_wrapper.get(&id).await.filter(|w| w.is_running()) {
+        return wrapper.interrupt().map_err(|e| e.to_string());
+    }
+
+    let pid = verified_agent_pid(&state, &id).await?;
+    process::send_signal(pid, Signal::SIGINT).map_err(|e| e.to_string())?;
+    tracing::info!("已中断会话 {} (pid={})", id, pid);
+    Ok(())
+}
+
+/// 终止会话
+///
+/// 核对 PID 后发送 SIGTERM，`timeout_secs`（默认 5 秒）内未退出则发送 SIGKILL；
+/// 随后等待锁文件释放，并发布会话结束事件。
+#[tauri::command]
+pub async fn terminate_session(
+    id: String,
+    timeout_secs: Option<u64>,
+    state: State<'_, AppState>,
+) -> std::result::Result<TerminateOutcome, String> {
+    let grace = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TERMINATE_TIMEOUT_SECS));
+
+    let outcome = match state.process_wrapper.get(&id).await.filter(|w| w.is_running()) {
+        // 应用持有子进程，PID 在回收前不会被复用
+        Some(wrapper) => {
+            let outcome = process::terminate(wrapper.pid(), grace)
+                .await
+                .map_err(|e| e.to_string())?;
+            process::wait_until(LOCK_RELEASE_TIMEOUT, || !wrapper.is_running()).await;
+            outcome
+        }
+        None => {
+            let pid = verified_agent_pid(&state, &id).await?;
+            let outcome = process::terminate(pid, grace).await.map_err(|e| e.to_string())?;
+            wait_for_lock_release(&state, pid).await;
+            outcome
+        }
+    };
+
+    state.monitor.unregister(&id).await.map_err(|e| e.to_string())?;
+    tracing::info!("已终止会话 {}: {:?}", id, outcome);
+    Ok(outcome)
+}
+
+/// 获取会话 PID，并核对它仍属于 Claude Code 进程且持有锁文件
+async fn verified_agent_pid(state: &AppState, id: &str) -> std::result::Result<u32, String> {
+    let session = state
+        .monitor
+        .get_session(id)
+        .ok_or_else(|| crate::error::AppError::SessionNotFound(id.to_string()).to_string())?;
+    let pid = session
+        .pid
+        .ok_or_else(|| "会话进程 PID 未知，无法发送信号".to_string())?;
+
+    let discoveries = state.discoveries().await.map_err(|e| e.to_string())?;
+    if !lock_held(&discoveries, pid).await || !process::is_agent_process(pid) {
+        return Err(format!("进程 {} 已不属于该会话", pid));
+    }
+    Ok(pid)
+}
+
+/// 等待进程的锁文件被删除；被强制结束的进程不会清理锁文件，超时后放弃
+async fn wait_for_lock_release(state: &AppState, pid: u32) {
+    let Ok(discoveries) = state.discoveries().await else {
+        return;
+    };
+
+    let deadline = tokio::time::Instant::now() + LOCK_RELEASE_TIMEOUT;
+    loop {
+        if !lock_held(&discoveries, pid).await {
+            return;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            tracing::warn!("进程 {} 的锁文件未释放", pid);
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
+
+/// 任一根目录下是否有锁文件指向该 PID
+async fn lock_held(discoveries: &[SessionDiscovery], pid: u32) -> bool {
+    for discovery in discoveries {
+        if discovery.has_lock_for_pid(pid).await {
+            return true;
         }
-        Ok(filtered_sessions)
     }
+    false
+}
+
+/// 聚焦会话所在的 tmux 窗格
+///
+/// 切换到会话所在的 tmux 窗口并选中窗格；会话不在 tmux 中运行时返回错误。
+#[tauri::command]
+pub async fn focus_session(id: String, state: State<'_, AppState>) -> std::result::Result<(), String> {
+    let session = state
+        .monitor
+        .get_session(&id)
+        .ok_or_else(|| crate::error::AppError::SessionNotFound(id.clone()).to_string())?;
+    let pane = session
+        .tmux_pane
+        .ok_or_else(|| "会话不在 tmux 中运行".to_string())?;
+
+    crate::tmux::focus_pane(&pane).map_err(|e| e.to_string())
+}
+
+/// 列出可恢复的历史会话
+///
+/// 扫描磁盘上的日志，按项目分组返回，包含首条提示与最近活动时间。
+/// 指定 `project_path` 时只返回该项目的会话。
+#[tauri::command]
+pub async fn list_resumable_sessions(
+    project_path: Option<String>,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<ProjectHistory>, String> {
+    let projects_dirs = state.claude_projects_dirs().await.map_err(|e| e.to_string())?;
+
+    // 日志可能很大，放到阻塞线程中读取
+    tokio::task::spawn_blocking(move || {
+        history::list_resumable(&projects_dirs, project_path.as_deref().map(std::path::Path::new))
+    })
+    .await
+    .map_err(|e| e.to_string())
 }
 
 /// 获取会话详情
@@ -60,12 +209,14 @@ pub async fn get_session_detail(
     // 默认消息限制
     let limit = message_limit.unwrap_or(20);
 
-    // 首先尝试从 monitor 获取会话
-    let monitor = state.monitor.read().await;
-
-    if let Some(session) = monitor.get_session(&id).await {
-        // 获取日志文件路径
-        let log_path = find_session_log_path(&session.project_path).await;
+    // 首先尝试从 monitor 快照获取会话
+    if let Some(session) = state.monitor.get_session(&id) {
+        // 在监控配置的根目录下查找日志文件
+        let log_path = state
+            .monitor
+            .find_latest_log(&session.project_path)
+            .await
+            .map_err(|e| e.to_string())?;
 
         // 提取消息
         let messages: Vec<Message> = if let Some(ref path) = log_path {
@@ -93,37 +244,21 @@ pub async fn get_session_detail(
     }
 
     // 如果 monitor 中没有，尝试从 storage 加载
-    drop(monitor);
     let storage = state.storage();
     storage.load_session_detail(&id).await.map_err(|e| e.to_string())
 }
 
-/// 查找会话的日志文件路径
-async fn find_session_log_path(project_path: &str) -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
-    let project_path = PathBuf::from(project_path);
-    let encoded = project_path.to_string_lossy().replace(['/', '\\'], "-");
-    let log_dir = home.join(".claude").join("projects").join(encoded);
-
-    // 查找最新的 jsonl 文件
-    std::fs::read_dir(&log_dir)
-        .ok()?
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| {
-            entry
-                .path()
-                .extension()
-                .map(|ext| ext == "jsonl")
-                .unwrap_or(false)
-        })
-        .max_by_key(|entry| {
-            entry
-                .metadata()
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-        })
-        .map(|entry| entry.path())
+/// 获取会话的状态变化记录（按时间顺序）
+#[tauri::command]
+pub async fn get_session_transitions(
+    id: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<StatusTransition>, String> {
+    state
+        .storage()
+        .load_transitions(&id)
+        .await
+        .map_err(|e| e.to_string())
 }
 
 /// 从会话提取进程信息
@@ -171,37 +306,86 @@ fn calculate_stats(
 }
 
 /// 标记会话完成
+///
+/// 标记在会话重新活跃（状态变为非完成）前保持有效。
 #[tauri::command]
 pub async fn mark_session_completed(id: String, state: State<'_, AppState>) -> std::result::Result<(), String> {
-    let storage = state.storage();
-
-    let mut session = storage.get_session(&id).await.map_err(|e| e.to_string())?;
-    session.status = SessionStatus::Completed;
-    session.last_active_at = chrono::Utc::now();
+    update_stored_session(&state, &id, |session| {
+        let now = chrono::Utc::now();
+        session.status = SessionStatus::Completed;
+        session.marked_completed_at = Some(now);
+        session.last_active_at = now;
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
 
-    storage.update_session(&session).await.map_err(|e| e.to_string())
+/// 重命名会话（标题为空时恢复自动生成的标题）
+#[tauri::command]
+pub async fn rename_session(
+    id: String,
+    title: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    let title = title.trim().to_string();
+    let live_title = state.monitor.get_session(&id).map(|s| s.title);
+    update_stored_session(&state, &id, move |session| {
+        if title.is_empty() {
+            session.custom_title = None;
+            if let Some(live_title) = live_title {
+                session.title = live_title;
+            }
+        } else {
+            session.title = title.clone();
+            session.custom_title = Some(title);
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())
 }
 
 /// 归档会话
 #[tauri::command]
 pub async fn archive_session(id: String, state: State<'_, AppState>) -> std::result::Result<(), String> {
-    let storage = state.storage();
-
-    let mut session = storage.get_session(&id).await.map_err(|e| e.to_string())?;
-    session.is_archived = true;
-    session.last_active_at = chrono::Utc::now();
-
-    storage.update_session(&session).await.map_err(|e| e.to_string())
+    set_session_archived(&state, &id, true).await.map_err(|e| e.to_string())
 }
 
 /// 取消归档
 #[tauri::command]
 pub async fn unarchive_session(id: String, state: State<'_, AppState>) -> std::result::Result<(), String> {
-    let storage = state.storage();
+    set_session_archived(&state, &id, false).await.map_err(|e| e.to_string())
+}
 
-    let mut session = storage.get_session(&id).await.map_err(|e| e.to_string())?;
-    session.is_archived = false;
-    session.last_active_at = chrono::Utc::now();
+/// 设置会话归档状态
+pub async fn set_session_archived(state: &AppState, id: &str, archived: bool) -> crate::error::Result<()> {
+    update_stored_session(state, id, move |session| {
+        session.is_archived = archived;
+        session.last_active_at = chrono::Utc::now();
+    })
+    .await
+}
 
-    storage.update_session(&session).await.map_err(|e| e.to_string())
+/// 修改存储中的会话
+///
+/// 存储中没有的会话（历史记录任务尚未写入）会从监控快照写入存储。
+async fn update_stored_session<F>(state: &AppState, id: &str, f: F) -> crate::error::Result<()>
+where
+    F: FnOnce(&mut Session) + Send + 'static,
+{
+    let fallback = state.monitor.get_session(id);
+    state
+        .storage()
+        .modify_session_or(id, fallback, f)
+        .await
+        .map(|_| ())
+}
+
+/// 暂停会话提醒直到指定时间（早于当前时间则取消暂停），跨重启保留
+#[tauri::command]
+pub async fn snooze_session(
+    id: String,
+    until: chrono::DateTime<chrono::Utc>,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    state.snooze_session(&id, until).await.map_err(|e| e.to_string())
 }
@@ -1,44 +1,28 @@
 use crate::state::AppState;
-use std::path::PathBuf;
-use std::process::Command;
+use crate::terminal;
 use tauri::State;
 
 /// 打开系统终端
 ///
-/// 使用 AppleScript 打开 macOS Terminal.app 并切换到指定项目目录。
+/// 在用户的终端中打开项目目录；指定 `resume_session_id` 时执行 `claude --resume <id>`。
+/// 终端程序按配置的命令模板或平台自动检测选择。
 #[tauri::command]
 pub async fn open_terminal(
     project_path: String,
-    _state: State<'_, AppState>,
+    resume_session_id: Option<String>,
+    state: State<'_, AppState>,
 ) -> std::result::Result<(), String> {
-    let path = PathBuf::from(&project_path);
-
-    // 验证路径是否存在
-    if !path.exists() {
-        return Err(format!("项目路径不存在: {}", project_path));
-    }
-
-    if !path.is_dir() {
-        return Err(format!("项目路径不是目录: {}", project_path));
-    }
-
-    // 使用 AppleScript 打开 Terminal 并执行命令
-    // 先 cd 到项目目录，然后显示提示符
-    let path_str = project_path.replace('"', "\\\"");
-    let script = format!(
-        r#"osascript -e 'tell app "Terminal" to do script "cd \"{}\" && clear'" 2>&1"#,
-        path_str
-    );
-
-    let output = Command::new("sh")
-        .args(["-c", &script])
-        .output()
-        .map_err(|e| format!("执行命令失败: {}", e))?;
+    launch_terminal(&state, &project_path, resume_session_id.as_deref()).await
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("打开终端失败: {}", stderr));
-    }
+/// 打开终端并切换到项目目录（供命令与托盘共用）
+pub async fn launch_terminal(
+    state: &AppState,
+    project_path: &str,
+    resume: Option<&str>,
+) -> std::result::Result<(), String> {
+    let template = state.config.read().await.terminal.command_template.clone();
+    terminal::launch(project_path, resume, template.as_deref()).map_err(|e| e.to_string())?;
 
     tracing::info!("已打开终端并切换到: {}", project_path);
     Ok(())
@@ -47,10 +31,9 @@ pub async fn open_terminal(
 /// 手动刷新状态
 #[tauri::command]
 pub async fn refresh_status(state: State<'_, AppState>) -> std::result::Result<(), String> {
-    // TODO: 触发状态刷新
-    // 刷新所有会话状态
-    let monitor = state.monitor.read().await;
-    monitor.refresh_all().await.map_err(|e| e.to_string())
+    // 先完整对账一次，再重新检测所有会话状态
+    state.monitor.refresh().await.map_err(|e| e.to_string())?;
+    state.monitor.refresh_all().await.map_err(|e| e.to_string())
 }
 
 /// 获取应用配置
@@ -76,5 +59,16 @@ pub async fn update_config(
     }
 
     // 保存到文件
-    state.save_config().await.map_err(|e| e.to_string())
+    state.save_config().await.map_err(|e| e.to_string())?;
+
+    // 无需重启应用，立即应用到运行中的监控
+    state.apply_config().await.map_err(|e| e.to_string())
+}
+
+/// 重启会话监控
+///
+/// 拆除并重新建立文件监控，已发现的会话保留。
+#[tauri::command]
+pub async fn restart_monitor(state: State<'_, AppState>) -> std::result::Result<(), String> {
+    state.monitor.restart().await.map_err(|e| e.to_string())
 }
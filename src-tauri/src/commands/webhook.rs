@@ -0,0 +1,39 @@
+use crate::models::SessionStatus;
+use crate::monitor::MonitorEvent;
+use crate::state::AppState;
+use crate::webhooks::WebhookDelivery;
+use tauri::State;
+
+/// 获取 Webhook 投递日志（最新的在前）
+#[tauri::command]
+pub async fn get_webhook_deliveries(
+    webhook_id: Option<String>,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<WebhookDelivery>, String> {
+    Ok(state.webhooks.deliveries(webhook_id.as_deref()))
+}
+
+/// 向指定 Webhook 发送一条测试事件
+#[tauri::command]
+pub async fn test_webhook(
+    webhook_id: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<WebhookDelivery, String> {
+    let webhook = {
+        let config = state.config.read().await;
+        config
+            .webhooks
+            .iter()
+            .find(|w| w.id == webhook_id)
+            .cloned()
+            .ok_or_else(|| format!("Webhook 不存在: {}", webhook_id))?
+    };
+
+    let event = MonitorEvent::StatusChanged {
+        session_id: "test".to_string(),
+        old_status: SessionStatus::Running,
+        new_status: SessionStatus::WaitingInput,
+    };
+
+    Ok(state.webhooks.deliver(&webhook, &event, None).await)
+}
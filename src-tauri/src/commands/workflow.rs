@@ -0,0 +1,46 @@
+use crate::state::AppState;
+use crate::workflow::{Workflow, WorkflowRun};
+use tauri::State;
+
+/// 获取全部工作流
+#[tauri::command]
+pub async fn list_workflows(
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<Workflow>, String> {
+    Ok(state.workflows.list().await)
+}
+
+/// 新建或更新工作流（ID 为空时新建）
+#[tauri::command]
+pub async fn save_workflow(
+    workflow: Workflow,
+    state: State<'_, AppState>,
+) -> std::result::Result<Workflow, String> {
+    state
+        .workflows
+        .save(workflow)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 删除工作流
+#[tauri::command]
+pub async fn delete_workflow(
+    workflow_id: String,
+    state: State<'_, AppState>,
+) -> std::result::Result<(), String> {
+    state
+        .workflows
+        .delete(&workflow_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 获取工作流运行日志（最新的在前）
+#[tauri::command]
+pub async fn get_workflow_runs(
+    workflow_id: Option<String>,
+    state: State<'_, AppState>,
+) -> std::result::Result<Vec<WorkflowRun>, String> {
+    Ok(state.workflows.runs(workflow_id.as_deref()))
+}
@@ -0,0 +1,34 @@
+use crate::error::AppError;
+use crate::monitor::discovery::SessionDiscovery;
+use crate::state::AppState;
+use crate::worktree::{self, WorktreeCleanup};
+use std::path::PathBuf;
+use tauri::State;
+
+/// 清理会话的 worktree
+///
+/// 会话结束、分支合并或会话归档后移除 worktree 并删除分支。
+/// 分支未合并时须 `force`，同时丢弃未提交的改动；worktree 中仍有运行的会话时拒绝清理。
+#[tauri::command]
+pub async fn cleanup_worktree(
+    worktree_path: String,
+    force: Option<bool>,
+    state: State<'_, AppState>,
+) -> std::result::Result<WorktreeCleanup, String> {
+    let running = state.monitor.sessions().iter().any(|s| {
+        s.project_path == worktree_path && s.pid.is_some_and(SessionDiscovery::process_exists)
+    });
+    if running {
+        return Err(AppError::InvalidInput(format!(
+            "worktree 中仍有运行的会话: {}",
+            worktree_path
+        ))
+        .to_string());
+    }
+
+    let path = PathBuf::from(worktree_path);
+    tokio::task::spawn_blocking(move || worktree::cleanup(&path, force.unwrap_or(false)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
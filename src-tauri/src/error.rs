@@ -43,6 +43,12 @@ impl From<serde_json::Error> for AppError {
     }
 }
 
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        AppError::StorageError(err.to_string())
+    }
+}
+
 impl From<notify::Error> for AppError {
     fn from(err: notify::Error) -> Self {
         AppError::MonitorError(err.to_string())
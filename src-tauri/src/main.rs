@@ -3,12 +3,25 @@
 
 use tauri::Manager;
 
+mod bridge;
 mod commands;
 mod error;
 mod models;
 mod monitor;
+mod notifications;
+mod process;
+mod queue;
+mod retention;
+mod search;
 mod state;
 mod storage;
+mod terminal;
+mod tmux;
+mod tray;
+mod webhooks;
+mod workflow;
+mod worktree;
+mod wrapper;
 
 use state::AppState;
 
@@ -19,17 +32,54 @@ fn main() {
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             commands::get_all_sessions,
+            commands::get_session_history,
             commands::get_session_detail,
+            commands::get_session_transitions,
             commands::mark_session_completed,
+            commands::rename_session,
             commands::archive_session,
             commands::unarchive_session,
             commands::send_message,
+            commands::broadcast_message,
+            commands::attach_to_session,
+            commands::detach_from_session,
+            commands::resize_terminal,
+            commands::get_permission_prompt,
+            commands::respond_permission,
             commands::open_terminal,
             commands::refresh_status,
             commands::get_config,
             commands::update_config,
+            commands::restart_monitor,
+            commands::get_webhook_deliveries,
+            commands::test_webhook,
+            commands::snooze_session,
+            commands::focus_session,
+            commands::interrupt_session,
+            commands::terminate_session,
+            commands::start_session,
+            commands::get_project_config,
+            commands::save_launch_profile,
+            commands::delete_launch_profile,
+            commands::set_default_launch_profile,
+            commands::list_resumable_sessions,
+            commands::resume_session,
+            commands::search_sessions,
+            commands::run_retention,
+            commands::get_retention_reports,
+            commands::get_prompt_queue,
+            commands::enqueue_prompt,
+            commands::remove_queued_prompt,
+            commands::reorder_prompt_queue,
+            commands::set_queue_paused,
+            commands::list_workflows,
+            commands::save_workflow,
+            commands::delete_workflow,
+            commands::get_workflow_runs,
+            commands::cleanup_worktree,
         ])
         .setup(|app| {
             tracing::info!("CodeCenter starting...");
@@ -44,28 +94,52 @@ fn main() {
                     app.manage(state.clone());
                     tracing::info!("AppState initialized successfully");
 
-                    // 启动会话监控器（用于 get_all_sessions 查询）
-                    tauri::async_runtime::spawn(async move {
-                        // 启动 monitor（用于 get_all_sessions 查询）
-                        let monitor_started = {
-                            let mut monitor = state.monitor.write().await;
-                            tracing::info!("启动会话监控...");
-                            match monitor.start().await {
-                                Ok(_) => {
-                                    tracing::info!("Session monitor started successfully");
-                                    true
-                                }
-                                Err(e) => {
-                                    tracing::error!("Failed to start monitor: {}", e);
-                                    false
-                                }
-                            }
-                        };
-
-                        if !monitor_started {
-                            tracing::error!("无法启动会话监控");
-                        }
-                    });
+                    // 会话监控已在后台任务中运行，订阅时会先回放最近快照
+                    bridge::spawn_ui_bridge(app.handle().clone(), state.monitor.subscribe());
+                    // 应用启动的会话的终端输出
+                    bridge::spawn_wrapper_bridge(
+                        app.handle().clone(),
+                        state.process_wrapper.clone(),
+                    );
+
+                    // 状态变化时弹出桌面通知，点击通知后聚焦对应会话
+                    app.manage(notifications::FocusTarget::default());
+                    notifications::spawn_notifier(
+                        app.handle().clone(),
+                        state.monitor.subscribe(),
+                        state.config.clone(),
+                        state.snoozes.clone(),
+                    );
+
+                    // 把监控发现的会话写入存储，保留会话历史
+                    storage::recorder::spawn_recorder(
+                        state.storage.clone(),
+                        state.monitor.subscribe(),
+                    );
+
+                    // 为所有日志建立全文索引并随日志变化与根目录配置变化增量更新
+                    search::spawn_indexer(state.search.clone(), state.search_dirs.subscribe());
+
+                    // 按保留策略定期标记完成、归档并清理旧会话数据
+                    retention::spawn_scheduler(state.clone());
+
+                    // 应用启动的会话进入等待输入时发送排队的提示
+                    state
+                        .prompt_queue
+                        .spawn_dispatcher(state.monitor.subscribe());
+
+                    // 按工作流定义在会话之间联动
+                    workflow::spawn_engine(app.handle().clone(), state.clone());
+
+                    // 托盘图标随会话快照更新
+                    tray::setup_tray(app, state.monitor.subscribe())?;
+
+                    // 按配置将监控事件推送到外部 Webhook
+                    webhooks::spawn_dispatcher(
+                        state.webhooks.clone(),
+                        state.monitor.subscribe(),
+                        state.config.clone(),
+                    );
                 }
                 Err(e) => {
                     tracing::error!("Failed to initialize AppState: {}", e);
@@ -78,7 +152,23 @@ fn main() {
 
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Focused(true) = event {
+                notifications::handle_window_focused(window.app_handle());
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                // 退出前停止监控任务并拆除文件监控
+                if let Some(state) = app.try_state::<AppState>() {
+                    let monitor = state.monitor.clone();
+                    if let Err(e) = tauri::async_runtime::block_on(monitor.shutdown()) {
+                        tracing::warn!("停止会话监控失败: {}", e);
+                    }
+                }
+            }
+        });
 }
 
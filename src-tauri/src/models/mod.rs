@@ -45,7 +45,7 @@ impl Default for SessionId {
 }
 
 /// 会话基础信息
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Session {
     pub id: String,
@@ -60,6 +60,37 @@ pub struct Session {
     pub last_active_at: DateTime<Utc>,
     pub summary: Option<String>,
     pub is_archived: bool,
+    /// 会话进程 PID（仅从日志发现时未知）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    /// 会话所在的 tmux 窗格（不在 tmux 中运行时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tmux_pane: Option<TmuxPane>,
+    /// 会话在 git worktree 中运行时所属的主仓库，用于在主仓库下分组展示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_repo: Option<String>,
+    /// 会话进程结束的时间（仍在运行时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    /// 用户标记完成的时间；会话之后重新活跃时清除
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marked_completed_at: Option<DateTime<Utc>>,
+    /// 用户重命名的标题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_title: Option<String>,
+}
+
+/// tmux 窗格位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TmuxPane {
+    /// 窗格 ID（如 %3）
+    pub pane_id: String,
+    pub session_name: String,
+    pub window_index: u32,
+    pub pane_index: u32,
+    /// tmux 服务器套接字路径
+    pub socket_path: Option<String>,
 }
 
 impl Session {
@@ -80,6 +111,25 @@ impl Session {
             last_active_at: now,
             summary: None,
             is_archived: false,
+            pid: None,
+            tmux_pane: None,
+            parent_repo: None,
+            ended_at: None,
+            marked_completed_at: None,
+            custom_title: None,
+        }
+    }
+
+    /// 把存储中用户的修改（归档、标记完成、重命名）合并到监控发现的会话上
+    pub fn apply_overrides(&mut self, stored: &Session) {
+        self.is_archived = stored.is_archived;
+        if let Some(title) = &stored.custom_title {
+            self.title = title.clone();
+            self.custom_title = Some(title.clone());
+        }
+        if stored.marked_completed_at.is_some() {
+            self.status = SessionStatus::Completed;
+            self.marked_completed_at = stored.marked_completed_at;
         }
     }
 }
@@ -136,6 +186,16 @@ pub struct SessionStats {
     pub duration_secs: u64,
 }
 
+/// 会话状态变化记录
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusTransition {
+    pub session_id: String,
+    pub old_status: SessionStatus,
+    pub new_status: SessionStatus,
+    pub changed_at: DateTime<Utc>,
+}
+
 /// 项目信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -147,12 +207,59 @@ pub struct Project {
     pub config: Option<ProjectConfig>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProjectConfig {
     pub auto_start: bool,
     pub default_agent: String,
+    /// 项目内所有会话共用的环境变量（启动配置中的同名变量优先）
     pub env_vars: std::collections::HashMap<String, String>,
+    /// 命名启动配置
+    #[serde(default)]
+    pub launch_profiles: Vec<LaunchProfile>,
+    /// 未指定启动配置时使用的配置名
+    #[serde(default)]
+    pub default_profile: Option<String>,
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        Self {
+            auto_start: false,
+            default_agent: "claude".to_string(),
+            env_vars: std::collections::HashMap::new(),
+            launch_profiles: Vec::new(),
+            default_profile: None,
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// 按名称查找启动配置，未指定名称时使用默认配置
+    pub fn profile(&self, name: Option<&str>) -> Option<&LaunchProfile> {
+        let name = name.or(self.default_profile.as_deref())?;
+        self.launch_profiles.iter().find(|p| p.name == name)
+    }
+}
+
+/// 新会话的启动配置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LaunchProfile {
+    pub name: String,
+    /// 模型（--model）
+    pub model: Option<String>,
+    /// 权限模式（--permission-mode）
+    pub permission_mode: Option<String>,
+    /// 允许的工具（--allowedTools）
+    pub allowed_tools: Vec<String>,
+    /// 禁用的工具（--disallowedTools）
+    pub disallowed_tools: Vec<String>,
+    /// 追加的系统提示（--append-system-prompt）
+    pub append_system_prompt: Option<String>,
+    pub env_vars: std::collections::HashMap<String, String>,
+    /// 启动时未给出提示时使用的初始提示
+    pub initial_prompt: Option<String>,
 }
 
 /// 应用配置
@@ -162,6 +269,67 @@ pub struct AppConfig {
     pub version: String,
     pub settings: Settings,
     pub ui: UiConfig,
+    /// 外发 Webhook 目标
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    /// 外部终端设置
+    #[serde(default)]
+    pub terminal: TerminalConfig,
+}
+
+/// 外部终端设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TerminalConfig {
+    /// 自定义终端命令模板，如 `kitty --directory {cwd} {command}`（占位符须单独成词）；为空时自动检测
+    pub command_template: Option<String>,
+}
+
+/// Webhook 目标配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookConfig {
+    pub id: String,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// 订阅的事件类型（如 statusChanged、sessionEnded），为空时接收除快照外的全部事件
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// JSON 请求体模板，支持 {{sessionId}}、{{newStatus}}、{{payload}} 等占位符
+    #[serde(default)]
+    pub body_template: Option<String>,
+    /// 附加请求头
+    #[serde(default)]
+    pub headers: std::collections::HashMap<String, String>,
+    /// 失败后的最大重试次数
+    #[serde(default = "default_webhook_retries")]
+    pub max_retries: u32,
+    /// 首次重试的等待毫秒数（之后指数退避）
+    #[serde(default = "default_webhook_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+impl WebhookConfig {
+    /// 是否订阅指定事件类型
+    pub fn accepts(&self, event_type: &str) -> bool {
+        if self.events.is_empty() {
+            return event_type != "snapshot";
+        }
+        self.events.iter().any(|e| e == event_type)
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_webhook_retries() -> u32 {
+    3
+}
+
+fn default_webhook_backoff_ms() -> u64 {
+    1000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -171,6 +339,86 @@ pub struct Settings {
     pub max_session_history: usize,
     pub notification_enabled: bool,
     pub message_load_limit: usize,
+    /// Claude Code 配置根目录（为空时使用 ~/.claude）
+    #[serde(default)]
+    pub claude_roots: Vec<String>,
+    /// 桌面通知细分设置（总开关为 notification_enabled）
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// 会话保留与自动归档策略
+    #[serde(default)]
+    pub retention: RetentionSettings,
+}
+
+/// 会话保留策略（会话详情按 max_session_history 保留）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetentionSettings {
+    /// 执行间隔（分钟）
+    pub interval_mins: u64,
+    /// 日志多少小时未更新后自动标记完成（为空则不自动标记，监控中的会话不受影响）
+    pub auto_complete_after_hours: Option<u64>,
+    /// 完成多少天后自动归档（为空则不自动归档，监控中的会话不受影响）
+    pub auto_archive_after_days: Option<u64>,
+    /// 多少天未更新的日志压缩备份到 ~/.codeagent/transcripts（为空则不压缩）。
+    /// 原日志保持不变，仍在运行的会话不受影响
+    pub compress_transcripts_after_days: Option<u64>,
+}
+
+impl Default for RetentionSettings {
+    fn default() -> Self {
+        Self {
+            interval_mins: 60,
+            auto_complete_after_hours: None,
+            auto_archive_after_days: None,
+            compress_transcripts_after_days: None,
+        }
+    }
+}
+
+/// 桌面通知设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationSettings {
+    /// 会话等待输入时通知
+    pub on_waiting_input: bool,
+    /// 会话执行阻塞时通知
+    pub on_blocked: bool,
+    /// 会话完成或结束时通知
+    pub on_ended: bool,
+    /// 状态需持续的最短秒数，避免短暂抖动触发通知
+    pub min_duration_secs: u64,
+    /// 免打扰时段（本地时间）
+    pub quiet_hours: Vec<QuietHours>,
+    /// 静音的项目路径
+    pub muted_projects: Vec<String>,
+    /// 仍处于等待输入/阻塞状态时，间隔多少分钟再次提醒（为空则不再提醒）
+    pub escalate_after_mins: Option<u64>,
+    /// 最多再次提醒的次数
+    pub max_escalations: u32,
+}
+
+/// 免打扰时段，格式 "HH:MM"，结束早于开始时表示跨越午夜
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            on_waiting_input: true,
+            on_blocked: true,
+            on_ended: false,
+            min_duration_secs: 3,
+            quiet_hours: Vec::new(),
+            muted_projects: Vec::new(),
+            escalate_after_mins: None,
+            max_escalations: 1,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -189,11 +437,16 @@ impl Default for AppConfig {
                 max_session_history: 100,
                 notification_enabled: true,
                 message_load_limit: 30,
+                claude_roots: Vec::new(),
+                notifications: NotificationSettings::default(),
+                retention: RetentionSettings::default(),
             },
             ui: UiConfig {
                 theme: "dark".to_string(),
                 sidebar_collapsed: false,
             },
+            webhooks: Vec::new(),
+            terminal: TerminalConfig::default(),
         }
     }
 }
@@ -41,20 +41,36 @@ pub struct DiscoveredSession {
 }
 
 impl SessionDiscovery {
-    /// 创建新的会话发现器
+    /// 创建新的会话发现器（默认根目录 ~/.claude）
     pub fn new() -> Result<Self> {
-        let home_dir = dirs::home_dir()
-            .ok_or_else(|| AppError::StorageError("无法获取用户主目录".to_string()))?;
+        Ok(Self::with_root(Self::default_root()?))
+    }
 
-        let claude_dir = home_dir.join(".claude");
+    /// 使用指定的 Claude Code 配置目录创建会话发现器
+    pub fn with_root(claude_dir: PathBuf) -> Self {
         let ide_dir = claude_dir.join("ide");
         let projects_dir = claude_dir.join("projects");
 
-        Ok(Self {
+        Self {
             claude_dir,
             ide_dir,
             projects_dir,
-        })
+        }
+    }
+
+    /// 为多个根目录创建会话发现器，列表为空时使用默认根目录
+    pub fn for_roots(roots: &[PathBuf]) -> Result<Vec<Self>> {
+        if roots.is_empty() {
+            return Ok(vec![Self::new()?]);
+        }
+        Ok(roots.iter().cloned().map(Self::with_root).collect())
+    }
+
+    /// 默认根目录 (~/.claude)
+    pub fn default_root() -> Result<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".claude"))
+            .ok_or_else(|| AppError::StorageError("无法获取用户主目录".to_string()))
     }
 
     /// 发现所有活跃会话
@@ -148,7 +164,7 @@ impl SessionDiscovery {
         let lock: LockFile = serde_json::from_str(&content)?;
 
         // 验证进程是否仍然存在
-        if !self.process_exists(lock.pid) {
+        if !Self::process_exists(lock.pid) {
             debug!("进程 {} 不存在，跳过", lock.pid);
             return Ok(None);
         }
@@ -322,7 +338,7 @@ impl SessionDiscovery {
     }
 
     /// 检查进程是否存在
-    pub fn process_exists(&self, pid: u32) -> bool {
+    pub fn process_exists(pid: u32) -> bool {
         #[cfg(unix)]
         {
             // Unix: 发送信号 0 检查进程是否存在
@@ -402,6 +418,30 @@ impl SessionDiscovery {
         }
     }
 
+    /// 是否有锁文件仍指向该 PID（Claude Code 退出时会删除自己的锁文件）
+    pub async fn has_lock_for_pid(&self, pid: u32) -> bool {
+        let Ok(mut entries) = tokio::fs::read_dir(&self.ide_dir).await else {
+            return false;
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension() != Some("lock".as_ref()) {
+                continue;
+            }
+            let Ok(content) = tokio::fs::read_to_string(&path).await else {
+                continue;
+            };
+            if let Ok(lock) = serde_json::from_str::<LockFile>(&content) {
+                if lock.pid == pid {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
     /// 检查项目是否有对应的活跃锁文件
     /// 遍历所有锁文件，检查 workspace_folders 是否包含目标项目路径
     pub async fn has_active_lock_file(&self, project_path: &Path) -> bool {
@@ -447,7 +487,7 @@ impl SessionDiscovery {
 
                             if found {
                                 // 额外检查进程是否仍然存在
-                                if self.process_exists(lock.pid) {
+                                if Self::process_exists(lock.pid) {
                                     debug!("[has_active_lock_file] 找到匹配且进程存在, PID: {}", lock.pid);
                                     return true;
                                 } else {
@@ -471,9 +511,35 @@ impl SessionDiscovery {
     }
 }
 
+/// 在所有根目录下查找项目最新的 jsonl 日志文件
+pub fn find_latest_log(discoveries: &[SessionDiscovery], project_path: &str) -> Option<PathBuf> {
+    let encoded = encode_project_path(Path::new(project_path));
+
+    discoveries
+        .iter()
+        .filter_map(|d| std::fs::read_dir(d.projects_dir.join(&encoded)).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .map(|ext| ext == "jsonl")
+                .unwrap_or(false)
+        })
+        .max_by_key(|entry| {
+            entry
+                .metadata()
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+        })
+        .map(|entry| entry.path())
+}
+
 /// 编码项目路径为文件名安全的字符串
 /// Claude Code 使用的编码方式：将 / 替换为 -
-fn encode_project_path(path: &Path) -> String {
+pub fn encode_project_path(path: &Path) -> String {
     let path_str = path.to_string_lossy();
     // 替换路径分隔符为单连字符（与 Claude Code 日志目录格式一致）
     path_str.replace('/', "-").replace('\\', "-")
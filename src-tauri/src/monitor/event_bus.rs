@@ -0,0 +1,161 @@
+//! 事件总线模块
+//!
+//! 基于 broadcast 通道的多订阅者事件分发。
+//! UI 桥接、通知、存储持久化等消费者各自订阅，互不影响；
+//! 晚到的订阅者会先收到最近一次会话快照，再接收实时事件。
+
+use crate::models::Session;
+use crate::monitor::MonitorEvent;
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// 默认通道容量
+const DEFAULT_CAPACITY: usize = 256;
+
+/// 监控事件总线
+#[derive(Clone)]
+pub struct EventBus {
+    /// 广播发送器
+    sender: broadcast::Sender<MonitorEvent>,
+    /// 最近一次会话快照（用于回放给晚到的订阅者）
+    last_snapshot: Arc<RwLock<Option<Vec<Session>>>>,
+}
+
+impl EventBus {
+    /// 创建指定容量的事件总线
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self {
+            sender,
+            last_snapshot: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// 发布事件
+    ///
+    /// 没有订阅者时事件被直接丢弃；快照事件会被记录以便回放。
+    pub fn publish(&self, event: MonitorEvent) {
+        if let MonitorEvent::Snapshot { sessions } = &event {
+            if let Ok(mut last) = self.last_snapshot.write() {
+                *last = Some(sessions.clone());
+            }
+        }
+
+        let _ = self.sender.send(event);
+    }
+
+    /// 订阅事件
+    ///
+    /// 先注册接收器再读取快照，保证快照之后的事件不会丢失。
+    pub fn subscribe(&self) -> EventSubscription {
+        let receiver = self.sender.subscribe();
+        let replay = self
+            .last_snapshot
+            .read()
+            .ok()
+            .and_then(|last| last.clone())
+            .map(|sessions| MonitorEvent::Snapshot { sessions });
+
+        EventSubscription { replay, receiver }
+    }
+
+    /// 获取最近一次会话快照
+    pub fn last_snapshot(&self) -> Option<Vec<Session>> {
+        self.last_snapshot.read().ok().and_then(|last| last.clone())
+    }
+
+    /// 当前订阅者数量
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+/// 事件订阅
+pub struct EventSubscription {
+    /// 待回放的快照
+    replay: Option<MonitorEvent>,
+    /// 广播接收器
+    receiver: broadcast::Receiver<MonitorEvent>,
+}
+
+impl EventSubscription {
+    /// 接收下一个事件
+    ///
+    /// 消费过慢导致丢失事件时记录警告并继续；总线关闭时返回 None。
+    pub async fn recv(&mut self) -> Option<MonitorEvent> {
+        if let Some(event) = self.replay.take() {
+            return Some(event);
+        }
+
+        loop {
+            match self.receiver.recv().await {
+                Ok(event) => return Some(event),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("事件订阅者处理过慢，丢失 {} 个事件", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionStatus;
+
+    fn status_changed(id: &str) -> MonitorEvent {
+        MonitorEvent::StatusChanged {
+            session_id: id.to_string(),
+            old_status: SessionStatus::Running,
+            new_status: SessionStatus::WaitingInput,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multiple_subscribers_receive_same_event() {
+        let bus = EventBus::default();
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+
+        bus.publish(status_changed("sess_1"));
+
+        for sub in [&mut first, &mut second] {
+            match sub.recv().await {
+                Some(MonitorEvent::StatusChanged { session_id, .. }) => {
+                    assert_eq!(session_id, "sess_1");
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_late_subscriber_gets_snapshot_replay() {
+        let bus = EventBus::default();
+        let session = Session::new("测试会话", "project", "/path/to/project");
+        bus.publish(MonitorEvent::Snapshot {
+            sessions: vec![session],
+        });
+
+        let mut late = bus.subscribe();
+        bus.publish(status_changed("sess_2"));
+
+        match late.recv().await {
+            Some(MonitorEvent::Snapshot { sessions }) => assert_eq!(sessions.len(), 1),
+            other => panic!("expected snapshot, got {:?}", other),
+        }
+        assert!(matches!(
+            late.recv().await,
+            Some(MonitorEvent::StatusChanged { .. })
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+//! 监控句柄模块
+//!
+//! `SessionMonitor` 在后台任务中独占会话状态，外部通过 `MonitorHandle`
+//! 与其交互：读取走快照（无锁竞争），写操作以命令形式发送到监控任务。
+
+use crate::error::{AppError, Result};
+use crate::models::Session;
+use crate::monitor::discovery::DiscoveredSession;
+use crate::monitor::event_bus::{EventBus, EventSubscription};
+use crate::monitor::MonitorConfig;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, watch};
+
+/// 会话快照（不可变，可廉价克隆）
+pub type SessionSnapshot = Arc<Vec<Session>>;
+
+/// 发送给监控任务的命令
+#[derive(Debug)]
+pub enum MonitorCommand {
+    /// 立即执行一次完整对账（扫描锁文件与日志）
+    Refresh { reply: oneshot::Sender<Result<()>> },
+    /// 重新检测所有会话状态
+    RefreshAll { reply: oneshot::Sender<Result<()>> },
+    /// 重新检测指定会话状态
+    RefreshSession {
+        session_id: String,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    /// 登记由应用启动的会话
+    Register {
+        session: DiscoveredSession,
+        reply: oneshot::Sender<Result<Session>>,
+    },
+    /// 注销已结束的会话
+    Unregister {
+        session_id: String,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    /// 查找项目最新的日志文件
+    FindLatestLog {
+        project_path: String,
+        reply: oneshot::Sender<Result<Option<PathBuf>>>,
+    },
+    /// 停止文件监控与周期对账（保留会话缓存）
+    Stop { reply: oneshot::Sender<Result<()>> },
+    /// 拆除并重新建立文件监控
+    Restart { reply: oneshot::Sender<Result<()>> },
+    /// 热更新监控配置
+    Reconfigure {
+        config: MonitorConfig,
+        reply: oneshot::Sender<Result<()>>,
+    },
+    /// 停止监控并退出监控任务
+    Shutdown { reply: oneshot::Sender<Result<()>> },
+}
+
+/// 监控句柄
+///
+/// 可在各个命令间自由克隆共享。
+#[derive(Clone)]
+pub struct MonitorHandle {
+    /// 命令发送器
+    commands: mpsc::Sender<MonitorCommand>,
+    /// 会话快照接收器
+    snapshot: watch::Receiver<SessionSnapshot>,
+    /// 运行状态接收器
+    running: watch::Receiver<bool>,
+    /// 事件总线
+    event_bus: EventBus,
+}
+
+impl MonitorHandle {
+    /// 创建句柄
+    pub(crate) fn new(
+        commands: mpsc::Sender<MonitorCommand>,
+        snapshot: watch::Receiver<SessionSnapshot>,
+        running: watch::Receiver<bool>,
+        event_bus: EventBus,
+    ) -> Self {
+        Self {
+            commands,
+            snapshot,
+            running,
+            event_bus,
+        }
+    }
+
+    /// 获取当前会话快照
+    pub fn sessions(&self) -> SessionSnapshot {
+        self.snapshot.borrow().clone()
+    }
+
+    /// 获取特定会话
+    pub fn get_session(&self, session_id: &str) -> Option<Session> {
+        self.snapshot
+            .borrow()
+            .iter()
+            .find(|s| s.id == session_id)
+            .cloned()
+    }
+
+    /// 文件监控是否正在运行
+    pub fn is_running(&self) -> bool {
+        *self.running.borrow()
+    }
+
+    /// 订阅监控事件
+    pub fn subscribe(&self) -> EventSubscription {
+        self.event_bus.subscribe()
+    }
+
+    /// 获取事件总线
+    pub fn event_bus(&self) -> EventBus {
+        self.event_bus.clone()
+    }
+
+    /// 立即执行一次完整对账，等待完成
+    pub async fn refresh(&self) -> Result<()> {
+        self.request(|reply| MonitorCommand::Refresh { reply }).await
+    }
+
+    /// 重新检测所有会话状态，等待完成
+    pub async fn refresh_all(&self) -> Result<()> {
+        self.request(|reply| MonitorCommand::RefreshAll { reply })
+            .await
+    }
+
+    /// 重新检测指定会话状态，等待完成
+    pub async fn refresh_session(&self, session_id: &str) -> Result<()> {
+        let session_id = session_id.to_string();
+        self.request(|reply| MonitorCommand::RefreshSession { session_id, reply })
+            .await
+    }
+
+    /// 登记由应用启动的会话，立即加入快照并返回会话信息
+    pub async fn register(&self, session: DiscoveredSession) -> Result<Session> {
+        self.request(|reply| MonitorCommand::Register { session, reply })
+            .await
+    }
+
+    /// 注销已结束的会话（应用启动的进程退出或会话被终止），发布会话结束事件
+    pub async fn unregister(&self, session_id: &str) -> Result<()> {
+        let session_id = session_id.to_string();
+        self.request(|reply| MonitorCommand::Unregister { session_id, reply })
+            .await
+    }
+
+    /// 在当前根目录下查找项目最新的 jsonl 日志文件
+    pub async fn find_latest_log(&self, project_path: &str) -> Result<Option<PathBuf>> {
+        let project_path = project_path.to_string();
+        self.request(|reply| MonitorCommand::FindLatestLog {
+            project_path,
+            reply,
+        })
+        .await
+    }
+
+    /// 停止文件监控与周期对账，会话缓存保留
+    pub async fn stop(&self) -> Result<()> {
+        self.request(|reply| MonitorCommand::Stop { reply }).await
+    }
+
+    /// 拆除并重新建立文件监控，会话缓存保留
+    pub async fn restart(&self) -> Result<()> {
+        self.request(|reply| MonitorCommand::Restart { reply }).await
+    }
+
+    /// 热更新监控配置（刷新间隔、根目录）
+    pub async fn reconfigure(&self, config: MonitorConfig) -> Result<()> {
+        self.request(|reply| MonitorCommand::Reconfigure { config, reply })
+            .await
+    }
+
+    /// 停止监控并退出监控任务
+    pub async fn shutdown(&self) -> Result<()> {
+        self.request(|reply| MonitorCommand::Shutdown { reply })
+            .await
+    }
+
+    /// 发送命令并等待回复
+    async fn request<T, F>(&self, build: F) -> Result<T>
+    where
+        F: FnOnce(oneshot::Sender<Result<T>>) -> MonitorCommand,
+    {
+        let (reply, rx) = oneshot::channel();
+        self.commands
+            .send(build(reply))
+            .await
+            .map_err(|_| AppError::MonitorError("监控任务未运行".to_string()))?;
+
+        rx.await
+            .map_err(|_| AppError::MonitorError("监控任务未响应".to_string()))?
+    }
+}
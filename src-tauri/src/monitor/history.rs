@@ -0,0 +1,261 @@
+//! 历史会话模块
+//!
+//! 扫描磁盘上的 jsonl 日志，列出可通过 `claude --resume` 恢复的历史会话。
+//! 与会话发现不同，这里不限制日志的更新时间。
+
+use crate::monitor::discovery::encode_project_path;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// 首条提示的最大长度（字符）
+const PROMPT_MAX_CHARS: usize = 200;
+
+/// 可恢复的历史会话
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumableSession {
+    /// Claude Code 会话 ID（用于 `--resume`）
+    pub session_id: String,
+    pub project_path: String,
+    pub first_prompt: Option<String>,
+    pub last_activity: DateTime<Utc>,
+    pub message_count: usize,
+    pub log_path: String,
+}
+
+/// 单个项目的历史会话（按最近活动倒序）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectHistory {
+    pub project_path: String,
+    pub project_name: String,
+    pub sessions: Vec<ResumableSession>,
+}
+
+/// 列出可恢复的历史会话，按项目分组，最近活动的项目在前
+///
+/// 指定 `project_path` 时只扫描该项目的日志目录。
+pub fn list_resumable(
+    projects_dirs: &[PathBuf],
+    project_path: Option<&Path>,
+) -> Vec<ProjectHistory> {
+    let project_dirs: Vec<PathBuf> = match project_path {
+        Some(path) => {
+            let encoded = encode_project_path(path);
+            projects_dirs.iter().map(|dir| dir.join(&encoded)).collect()
+        }
+        None => projects_dirs
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect(),
+    };
+
+    let mut projects: HashMap<String, Vec<ResumableSession>> = HashMap::new();
+    for dir in project_dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension() != Some("jsonl".as_ref()) {
+                continue;
+            }
+            if let Some(session) = read_log_summary(&path) {
+                projects
+                    .entry(session.project_path.clone())
+                    .or_default()
+                    .push(session);
+            }
+        }
+    }
+
+    let mut history: Vec<ProjectHistory> = projects
+        .into_iter()
+        .map(|(project_path, mut sessions)| {
+            sessions.sort_by_key(|s| Reverse(s.last_activity));
+            let project_name = Path::new(&project_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| project_path.clone());
+            ProjectHistory {
+                project_path,
+                project_name,
+                sessions,
+            }
+        })
+        .collect();
+    history.sort_by_key(|project| Reverse(project.sessions[0].last_activity));
+    history
+}
+
+/// 读取单个日志的摘要；没有用户消息的日志无法恢复，返回 None
+pub fn read_log_summary(log_path: &Path) -> Option<ResumableSession> {
+    let file = std::fs::File::open(log_path).ok()?;
+    let modified: DateTime<Utc> = file.metadata().ok()?.modified().ok()?.into();
+
+    let mut session_id = None;
+    let mut cwd = None;
+    let mut first_prompt = None;
+    let mut last_activity = None;
+    let mut message_count = 0;
+
+    for line in BufReader::new(file).lines().map_while(|l| l.ok()) {
+        let Ok(event) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+
+        if session_id.is_none() {
+            session_id = event
+                .get("sessionId")
+                .and_then(Value::as_str)
+                .map(String::from);
+        }
+        if cwd.is_none() {
+            cwd = event.get("cwd").and_then(Value::as_str).map(String::from);
+        }
+        if let Some(ts) = event
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        {
+            last_activity = Some(ts.with_timezone(&Utc));
+        }
+
+        match event.get("type").and_then(Value::as_str) {
+            Some("user") => {
+                if let Some(text) = user_prompt_text(&event) {
+                    message_count += 1;
+                    first_prompt.get_or_insert(text);
+                }
+            }
+            Some("assistant") => message_count += 1,
+            _ => {}
+        }
+    }
+
+    first_prompt.as_ref()?;
+
+    // 日志中没有工作目录时退回到由目录名解码（含连字符的路径无法还原）
+    let project_path = cwd.unwrap_or_else(|| {
+        let encoded = log_path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        encoded.replace('-', "/")
+    });
+
+    Some(ResumableSession {
+        session_id: session_id.unwrap_or_else(|| {
+            log_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        }),
+        project_path,
+        first_prompt: first_prompt.map(|p| truncate(&p, PROMPT_MAX_CHARS)),
+        last_activity: last_activity.unwrap_or(modified),
+        message_count,
+        log_path: log_path.to_string_lossy().to_string(),
+    })
+}
+
+/// 会话 ID 是否可安全地作为命令行参数（Claude Code 使用 UUID）
+pub fn is_valid_session_id(session_id: &str) -> bool {
+    !session_id.is_empty()
+        && session_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// 用户输入的文本（工具结果等非文本内容返回 None）
+pub fn user_prompt_text(event: &Value) -> Option<String> {
+    let text = match event.pointer("/message/content")? {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let mut truncated: String = text.chars().take(max_chars).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_lists_sessions_grouped_by_project() {
+        let root = TempDir::new().unwrap();
+        let project = Path::new("/work/my-app");
+        let dir = root.path().join(encode_project_path(project));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(
+            dir.join("older.jsonl"),
+            concat!(
+                r#"{"type":"user","sessionId":"aaa","cwd":"/work/my-app","timestamp":"2026-01-01T10:00:00Z","message":{"role":"user","content":"修复登录"}}"#,
+                "\n",
+                r#"{"type":"assistant","sessionId":"aaa","timestamp":"2026-01-01T10:05:00Z","message":{"role":"assistant","content":[{"type":"text","text":"好的"}]}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("newer.jsonl"),
+            concat!(
+                r#"{"type":"user","sessionId":"bbb","cwd":"/work/my-app","timestamp":"2026-01-02T09:00:00Z","message":{"role":"user","content":[{"type":"text","text":"添加测试"}]}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        // 只有摘要、没有用户消息的日志不可恢复
+        std::fs::write(dir.join("empty.jsonl"), "{\"type\":\"summary\"}\n").unwrap();
+
+        let history = list_resumable(&[root.path().to_path_buf()], None);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].project_path, "/work/my-app");
+        assert_eq!(history[0].project_name, "my-app");
+
+        let sessions = &history[0].sessions;
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].session_id, "bbb");
+        assert_eq!(sessions[0].first_prompt.as_deref(), Some("添加测试"));
+        assert_eq!(sessions[1].session_id, "aaa");
+        assert_eq!(sessions[1].message_count, 2);
+        assert_eq!(
+            sessions[1].last_activity,
+            "2026-01-01T10:05:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+
+        assert!(is_valid_session_id("3f2a-bc_01"));
+        assert!(!is_valid_session_id("x; rm -rf /"));
+
+        let filtered = list_resumable(&[root.path().to_path_buf()], Some(Path::new("/other")));
+        assert!(filtered.is_empty());
+    }
+}
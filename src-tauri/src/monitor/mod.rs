@@ -5,6 +5,8 @@
 //! # 模块结构
 //!
 //! - `discovery`: 会话发现，扫描锁文件和日志目录
+//! - `event_bus`: 事件总线，多订阅者分发监控事件
+//! - `handle`: 监控句柄，快照读取与命令通道
 //! - `status_detector`: 状态检测，解析日志推断会话状态
 //! - `watcher`: 文件监控，使用 notify 监听日志变化
 //!
@@ -13,25 +15,37 @@
 //! ```rust
 //! use crate::monitor::SessionMonitor;
 //!
-//! let monitor = SessionMonitor::new().await?;
-//! monitor.start().await?;
+//! let config = MonitorConfig::from_settings(&app_config.settings);
+//! let handle = SessionMonitor::new(config)?.spawn();
 //!
-//! // 获取活跃会话
-//! let sessions = monitor.get_active_sessions().await?;
+//! // 读取会话快照（不阻塞监控任务）
+//! let sessions = handle.sessions();
 //!
-//! // 监听状态变化
-//! while let Some(event) = monitor.next_event().await {
+//! // 订阅状态变化（可多处独立订阅）
+//! let mut events = handle.subscribe();
+//! while let Some(event) = events.recv().await {
 //!     println!("收到事件: {:?}", event);
 //! }
+//!
+//! // 运行时热更新配置、重启或关闭
+//! handle.reconfigure(new_config).await?;
+//! handle.restart().await?;
+//! handle.shutdown().await?;
 //! ```
 
 pub mod discovery;
+pub mod event_bus;
+pub mod handle;
+pub mod history;
 pub mod status_detector;
 pub mod watcher;
 
 use crate::error::{AppError, Result};
-use crate::models::{Message, Session, SessionStatus};
+use crate::models::{Message, Session, SessionStatus, Settings};
 use discovery::{DiscoveredSession, SessionDiscovery};
+use event_bus::{EventBus, EventSubscription};
+use handle::{MonitorCommand, MonitorHandle, SessionSnapshot};
+use serde::Serialize;
 use status_detector::StatusDetector;
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
@@ -39,7 +53,8 @@ use std::mem;
 use std::os::fd::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
 use tracing::{debug, error, info, warn};
 use watcher::{WatchEvent, WatchManager};
 use chrono::{DateTime, Utc};
@@ -68,11 +83,25 @@ fn generate_session_id(disc: &DiscoveredSession) -> String {
     format!("sess_{:x}", hash)
 }
 
+/// 项目路径对应的会话 ID（与监控发现的会话 ID 一致）
+pub fn session_id_for_path(project_path: &Path) -> String {
+    generate_session_id(&DiscoveredSession {
+        pid: 0,
+        project_path: project_path.to_path_buf(),
+        project_name: String::new(),
+        log_path: None,
+        start_time: None,
+    })
+}
+
 /// 监控事件
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum MonitorEvent {
+    /// 当前全部会话快照
+    Snapshot { sessions: Vec<Session> },
     /// 发现新会话
-    SessionDiscovered { session: Session },
+    SessionDiscovered { session: Box<Session> },
     /// 会话状态变更
     StatusChanged {
         session_id: String,
@@ -90,6 +119,43 @@ pub enum MonitorEvent {
     Error { message: String },
 }
 
+impl MonitorEvent {
+    /// 推送给前端的事件名
+    pub fn ui_event_name(&self) -> &'static str {
+        match self {
+            MonitorEvent::Snapshot { .. } => "session:snapshot",
+            MonitorEvent::SessionDiscovered { .. } => "session:discovered",
+            MonitorEvent::StatusChanged { .. } => "session:status-changed",
+            MonitorEvent::NewMessage { .. } => "message:received",
+            MonitorEvent::SessionEnded { .. } => "session:removed",
+            MonitorEvent::Error { .. } => "monitor:error",
+        }
+    }
+
+    /// 事件类型名（与序列化后的 `type` 字段一致）
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            MonitorEvent::Snapshot { .. } => "snapshot",
+            MonitorEvent::SessionDiscovered { .. } => "sessionDiscovered",
+            MonitorEvent::StatusChanged { .. } => "statusChanged",
+            MonitorEvent::NewMessage { .. } => "newMessage",
+            MonitorEvent::SessionEnded { .. } => "sessionEnded",
+            MonitorEvent::Error { .. } => "error",
+        }
+    }
+
+    /// 事件关联的会话 ID
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            MonitorEvent::SessionDiscovered { session } => Some(&session.id),
+            MonitorEvent::StatusChanged { session_id, .. }
+            | MonitorEvent::NewMessage { session_id, .. }
+            | MonitorEvent::SessionEnded { session_id } => Some(session_id),
+            MonitorEvent::Snapshot { .. } | MonitorEvent::Error { .. } => None,
+        }
+    }
+}
+
 /// 进程存在性检测结果
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessExistence {
@@ -101,88 +167,392 @@ pub enum ProcessExistence {
     Dead,
 }
 
+/// 监控配置
+///
+/// 可在运行时通过 `MonitorHandle::reconfigure` 热更新
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorConfig {
+    /// 周期对账间隔
+    pub refresh_interval: Duration,
+    /// Claude Code 配置根目录（为空时使用 ~/.claude）
+    pub roots: Vec<PathBuf>,
+}
+
+impl MonitorConfig {
+    /// 从应用设置构建监控配置
+    pub fn from_settings(settings: &Settings) -> Self {
+        let home = dirs::home_dir();
+        let roots = settings
+            .claude_roots
+            .iter()
+            .filter(|root| !root.trim().is_empty())
+            .map(|root| match (root.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => PathBuf::from(root),
+            })
+            .collect();
+
+        Self {
+            refresh_interval: Duration::from_millis(settings.auto_refresh_interval_ms.max(500)),
+            roots,
+        }
+    }
+}
+
 /// 会话监控器
 ///
-/// 整合发现、状态检测、文件监控，提供统一的监控接口
+/// 整合发现、状态检测、文件监控，提供统一的监控接口。
+/// 通过 `spawn` 移入后台任务后独占全部状态，外部经 `MonitorHandle` 访问。
 pub struct SessionMonitor {
-    /// 会话发现器
-    discovery: SessionDiscovery,
-    /// 文件监控管理器
-    watch_manager: WatchManager,
-    /// 事件接收器
-    event_receiver: mpsc::Receiver<MonitorEvent>,
-    /// 内部事件发送器
-    event_sender: mpsc::Sender<MonitorEvent>,
+    /// 监控配置
+    config: MonitorConfig,
+    /// 会话发现器（每个根目录一个）
+    discoveries: Vec<SessionDiscovery>,
+    /// 文件监控管理器（停止时为 None）
+    watch_manager: Option<WatchManager>,
+    /// 事件总线
+    event_bus: EventBus,
     /// 会话缓存
-    sessions: Arc<RwLock<HashMap<String, Session>>>,
+    sessions: HashMap<String, Session>,
     /// 状态缓存
-    status_cache: Arc<RwLock<HashMap<String, SessionStatus>>>,
-    /// 是否正在运行
-    running: Arc<RwLock<bool>>,
+    status_cache: HashMap<String, SessionStatus>,
+    /// 由应用启动并登记的会话（会话 ID -> PID），以进程存活为准而非锁文件
+    managed: HashMap<String, u32>,
+    /// 正在监控的会话日志（会话 ID -> 日志路径），用于把日志变更映射回会话
+    session_logs: HashMap<String, PathBuf>,
+    /// 已发布为新消息的日志读取位置（日志路径 -> 字节偏移）
+    log_offsets: HashMap<PathBuf, u64>,
+    /// 会话快照发送器
+    snapshot_tx: watch::Sender<SessionSnapshot>,
+    /// 运行状态发送器
+    running_tx: watch::Sender<bool>,
 }
 
 impl SessionMonitor {
     /// 创建新的会话监控器
-    pub async fn new() -> Result<Self> {
-        let discovery = SessionDiscovery::new()?;
-        let watch_manager = WatchManager::new().await?;
-
-        let (event_sender, event_receiver) = mpsc::channel(100);
+    pub fn new(config: MonitorConfig) -> Result<Self> {
+        let discoveries = SessionDiscovery::for_roots(&config.roots)?;
 
         Ok(Self {
-            discovery,
-            watch_manager,
-            event_receiver,
-            event_sender,
-            sessions: Arc::new(RwLock::new(HashMap::new())),
-            status_cache: Arc::new(RwLock::new(HashMap::new())),
-            running: Arc::new(RwLock::new(false)),
+            config,
+            discoveries,
+            watch_manager: None,
+            event_bus: EventBus::default(),
+            sessions: HashMap::new(),
+            status_cache: HashMap::new(),
+            managed: HashMap::new(),
+            session_logs: HashMap::new(),
+            log_offsets: HashMap::new(),
+            snapshot_tx: watch::Sender::new(Arc::new(Vec::new())),
+            running_tx: watch::Sender::new(false),
         })
     }
 
-    /// 启动监控
+    /// 将监控器移入后台任务运行，返回句柄
+    ///
+    /// 后台任务按配置的间隔周期对账，同时处理文件事件和句柄命令；
+    /// 收到 Shutdown 命令或所有句柄被丢弃后任务退出。
+    pub fn spawn(self) -> MonitorHandle {
+        let (command_tx, command_rx) = mpsc::channel(32);
+        let handle = MonitorHandle::new(
+            command_tx,
+            self.snapshot_tx.subscribe(),
+            self.running_tx.subscribe(),
+            self.event_bus.clone(),
+        );
+
+        tokio::spawn(self.run(command_rx));
+
+        handle
+    }
+
+    /// 监控任务主循环
+    async fn run(mut self, mut commands: mpsc::Receiver<MonitorCommand>) {
+        let mut watch_rx = self.start_watching().await.ok();
+        let mut ticker = Self::new_ticker(self.config.refresh_interval);
+
+        info!("监控任务已启动");
+
+        loop {
+            let running = *self.running_tx.borrow();
+
+            tokio::select! {
+                command = commands.recv() => {
+                    let Some(command) = command else { break };
+
+                    match command {
+                        MonitorCommand::Stop { reply } => {
+                            self.stop_watching().await;
+                            watch_rx = None;
+                            let _ = reply.send(Ok(()));
+                        }
+                        MonitorCommand::Restart { reply } => {
+                            self.stop_watching().await;
+                            let result = self.start_watching().await;
+                            ticker = Self::new_ticker(self.config.refresh_interval);
+                            let _ = reply.send(result.map(|rx| watch_rx = Some(rx)));
+                        }
+                        MonitorCommand::Reconfigure { config, reply } => {
+                            let result = self.reconfigure(config, &mut watch_rx, &mut ticker).await;
+                            let _ = reply.send(result);
+                        }
+                        MonitorCommand::Shutdown { reply } => {
+                            self.stop_watching().await;
+                            let _ = reply.send(Ok(()));
+                            break;
+                        }
+                        command => self.handle_command(command).await,
+                    }
+                }
+                Some(event) = next_watch_event(&mut watch_rx) => self.handle_watch_event(event).await,
+                _ = ticker.tick(), if running => {
+                    if let Err(e) = self.instant_refresh().await {
+                        warn!("周期对账失败: {}", e);
+                    }
+                }
+            }
+        }
+
+        // 句柄全部丢弃时也要拆除监控
+        self.stop_watching().await;
+        info!("监控任务已停止");
+    }
+
+    /// 处理句柄命令（生命周期命令由主循环处理）
+    async fn handle_command(&mut self, command: MonitorCommand) {
+        match command {
+            MonitorCommand::Refresh { reply } => {
+                let _ = reply.send(self.instant_refresh().await);
+            }
+            MonitorCommand::RefreshAll { reply } => {
+                let _ = reply.send(self.refresh_all().await);
+            }
+            MonitorCommand::RefreshSession { session_id, reply } => {
+                let _ = reply.send(self.refresh_session(&session_id).await);
+            }
+            MonitorCommand::Register { session, reply } => {
+                let _ = reply.send(self.register_session(session).await);
+            }
+            MonitorCommand::Unregister { session_id, reply } => {
+                self.unregister_session(&session_id).await;
+                let _ = reply.send(Ok(()));
+            }
+            MonitorCommand::FindLatestLog {
+                project_path,
+                reply,
+            } => {
+                let _ = reply.send(Ok(self.find_latest_log(&project_path)));
+            }
+            MonitorCommand::Stop { .. }
+            | MonitorCommand::Restart { .. }
+            | MonitorCommand::Reconfigure { .. }
+            | MonitorCommand::Shutdown { .. } => {}
+        }
+    }
+
+    /// 登记由应用启动的会话
+    ///
+    /// 会话立即以 Initializing 状态加入缓存并发布，之后以 PID 存活判断会话是否结束。
+    async fn register_session(&mut self, disc: DiscoveredSession) -> Result<Session> {
+        let mut session = Self::convert_discovered_to_session(&disc).await?;
+        session.status = SessionStatus::Initializing;
+        // 进程运行在应用的伪终端中，祖先链上的窗格属于应用自身
+        session.tmux_pane = None;
+        session.created_at = Utc::now();
+
+        info!("登记会话: {} (pid={})", session.id, disc.pid);
+        self.managed.insert(session.id.clone(), disc.pid);
+        self.status_cache.remove(&session.id);
+        self.sessions.insert(session.id.clone(), session.clone());
+        // 新启动的会话通常还没有日志，日志出现后由周期对账补上监控
+        self.watch_session_log(&session.id, &session.project_path).await;
+
+        self.event_bus.publish(MonitorEvent::SessionDiscovered {
+            session: Box::new(session.clone()),
+        });
+        self.publish_snapshot();
+
+        Ok(session)
+    }
+
+    /// 注销已结束的会话（应用启动的进程退出，或会话被终止）
+    async fn unregister_session(&mut self, session_id: &str) {
+        self.managed.remove(session_id);
+        self.status_cache.remove(session_id);
+        self.unwatch_session_log(session_id).await;
+        if self.sessions.remove(session_id).is_some() {
+            info!("注销会话: {}", session_id);
+            self.event_bus.publish(MonitorEvent::SessionEnded {
+                session_id: session_id.to_string(),
+            });
+            self.publish_snapshot();
+        }
+    }
+
+    /// 由应用启动的会话进程是否仍存活
+    fn managed_alive(&self, session_id: &str) -> Option<bool> {
+        self.managed
+            .get(session_id)
+            .map(|pid| SessionDiscovery::process_exists(*pid))
+    }
+
+    /// 热更新配置
+    ///
+    /// 刷新间隔变化时重建定时器；根目录变化时重建发现器并重启文件监控。
+    /// 会话缓存保持不变，不属于新根目录的会话会在下一轮对账中被清理。
+    async fn reconfigure(
+        &mut self,
+        config: MonitorConfig,
+        watch_rx: &mut Option<mpsc::Receiver<WatchEvent>>,
+        ticker: &mut tokio::time::Interval,
+    ) -> Result<()> {
+        if config == self.config {
+            return Ok(());
+        }
+
+        info!("更新监控配置: {:?}", config);
+
+        if config.roots != self.config.roots {
+            self.discoveries = SessionDiscovery::for_roots(&config.roots)?;
+
+            if *self.running_tx.borrow() {
+                self.stop_watching().await;
+                *watch_rx = None;
+                self.config = config;
+                *watch_rx = Some(self.start_watching().await?);
+                *ticker = Self::new_ticker(self.config.refresh_interval);
+                return Ok(());
+            }
+        }
+
+        if config.refresh_interval != self.config.refresh_interval {
+            *ticker = Self::new_ticker(config.refresh_interval);
+        }
+        self.config = config;
+
+        Ok(())
+    }
+
+    /// 启动文件监控并发现现有会话
     ///
     /// 1. 初始化文件监控
     /// 2. 发现现有会话
-    /// 3. 启动事件处理循环
-    pub async fn start(&mut self) -> Result<()> {
+    async fn start_watching(&mut self) -> Result<mpsc::Receiver<WatchEvent>> {
         info!("启动会话监控...");
 
-        // 设置运行标志
-        {
-            let mut running = self.running.write().await;
-            *running = true;
+        let result = self.try_start_watching().await;
+        match &result {
+            Ok(_) => {
+                self.running_tx.send_replace(true);
+                info!("会话监控已启动");
+            }
+            Err(e) => {
+                error!("启动会话监控失败: {}", e);
+                self.watch_manager = None;
+                self.event_bus.publish(MonitorEvent::Error {
+                    message: e.to_string(),
+                });
+            }
         }
 
-        // 初始化文件监控
-        self.watch_manager.initialize().await?;
+        result
+    }
+
+    async fn try_start_watching(&mut self) -> Result<mpsc::Receiver<WatchEvent>> {
+        let mut watch_manager = WatchManager::new(self.discoveries.clone()).await?;
+        watch_manager.initialize().await?;
+        let watch_rx = watch_manager
+            .take_event_stream()
+            .ok_or_else(|| AppError::MonitorError("无法获取事件流接收器".to_string()))?;
+        self.watch_manager = Some(watch_manager);
 
         // 发现现有会话
         self.discover_existing_sessions().await?;
 
-        // 启动事件处理循环
-        self.spawn_event_handler();
+        Ok(watch_rx)
+    }
 
-        info!("会话监控已启动");
-        Ok(())
+    /// 拆除文件监控
+    ///
+    /// 取消全部路径监控并丢弃 notify 监控器，事件通道随之关闭；会话缓存保留。
+    async fn stop_watching(&mut self) {
+        if let Some(mut watch_manager) = self.watch_manager.take() {
+            info!("停止会话监控...");
+            watch_manager.shutdown().await;
+        }
+        self.session_logs.clear();
+        self.log_offsets.clear();
+        self.running_tx.send_replace(false);
+    }
+
+    /// 创建周期对账定时器
+    fn new_ticker(period: Duration) -> tokio::time::Interval {
+        let mut ticker = tokio::time::interval(period);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        ticker
+    }
+
+    /// 监控会话所在项目的最新日志
+    ///
+    /// 日志尚不存在时跳过；项目换用新日志文件（如 /clear 后）时切换监控。
+    async fn watch_session_log(&mut self, session_id: &str, project_path: &str) {
+        let Some(log_path) = self.find_latest_log(project_path) else {
+            return;
+        };
+        if self.session_logs.get(session_id) == Some(&log_path) {
+            return;
+        }
+        let Some(watch_manager) = self.watch_manager.as_mut() else {
+            return;
+        };
+
+        if let Some(old_path) = self.session_logs.remove(session_id) {
+            watch_manager.unwatch_session(&old_path).await;
+        }
+        match watch_manager.watch_session(&log_path).await {
+            Ok(()) => {
+                debug!("会话 {} 开始监控日志: {:?}", session_id, log_path);
+                // 已有内容不算新消息，从当前末尾开始读取
+                let len = std::fs::metadata(&log_path).map(|m| m.len()).unwrap_or(0);
+                self.log_offsets.entry(log_path.clone()).or_insert(len);
+                self.session_logs.insert(session_id.to_string(), log_path);
+            }
+            Err(e) => warn!("监控会话日志失败 {}: {}", session_id, e),
+        }
     }
 
-    /// 停止监控
-    pub async fn stop(&mut self) {
-        info!("停止会话监控...");
+    /// 停止监控会话的日志
+    async fn unwatch_session_log(&mut self, session_id: &str) {
+        let Some(log_path) = self.session_logs.remove(session_id) else {
+            return;
+        };
+        // 同一项目目录下的日志都属于该会话
+        self.log_offsets
+            .retain(|path, _| path.parent() != log_path.parent());
+        if let Some(watch_manager) = self.watch_manager.as_mut() {
+            watch_manager.unwatch_session(&log_path).await;
+        }
+    }
 
-        let mut running = self.running.write().await;
-        *running = false;
+    /// 日志文件所属的会话
+    ///
+    /// 日志文件名是 Claude Code 的会话 UUID，与监控的会话 ID 无关，
+    /// 先查监控登记表，找不到时按日志所在的项目目录匹配。
+    fn session_for_log(&self, path: &Path) -> Option<String> {
+        if let Some((session_id, _)) = self.session_logs.iter().find(|(_, log)| *log == path) {
+            return Some(session_id.clone());
+        }
 
-        // 清理缓存
-        self.sessions.write().await.clear();
-        self.status_cache.write().await.clear();
+        let project_dir = path.parent()?.file_name()?.to_str()?;
+        self.sessions
+            .values()
+            .find(|s| discovery::encode_project_path(Path::new(&s.project_path)) == project_dir)
+            .map(|s| s.id.clone())
     }
 
     /// 获取所有活跃会话
-    pub async fn get_active_sessions(&self) -> Result<Vec<Session>> {
-        let sessions = self.sessions.read().await;
-        Ok(sessions.values().cloned().collect())
+    pub fn get_active_sessions(&self) -> Vec<Session> {
+        self.sessions.values().cloned().collect()
     }
 
     /// 刷新并获取所有活跃会话
@@ -192,9 +562,10 @@ impl SessionMonitor {
         info!("刷新并获取所有活跃会话...");
 
         // 重新发现会话
-        let discovered = self.discovery.discover_sessions().await?;
+        let discovered = self.discover_all().await?;
 
-        let mut sessions = self.sessions.write().await;
+        // 暂时取出会话缓存，以便在遍历期间调用 &self 方法
+        let mut sessions = mem::take(&mut self.sessions);
         // 直接跟踪活跃的 PID
         let mut active_pids: std::collections::HashSet<u32> = std::collections::HashSet::new();
         // 跟踪活跃的项目路径（用于 pid=0 的会话）
@@ -234,7 +605,7 @@ impl SessionMonitor {
             }
 
             // 检查进程是否存在
-            if !self.discovery.process_exists(disc.pid) {
+            if !SessionDiscovery::process_exists(disc.pid) {
                 debug!("进程 {} 不存在，跳过", disc.pid);
                 continue;
             }
@@ -332,33 +703,31 @@ impl SessionMonitor {
         let count = sessions.len();
         info!("刷新完成，当前有 {} 个活跃会话", count);
 
-        Ok(sessions.values().cloned().collect())
+        self.sessions = sessions;
+        self.publish_snapshot();
+
+        Ok(self.get_active_sessions())
     }
 
     /// 获取特定会话
-    pub async fn get_session(&self, session_id: &str) -> Option<Session> {
-        let sessions = self.sessions.read().await;
-        sessions.get(session_id).cloned()
+    pub fn get_session(&self, session_id: &str) -> Option<Session> {
+        self.sessions.get(session_id).cloned()
     }
 
     /// 获取会话状态
-    pub async fn get_session_status(&self, session_id: &str) -> Option<SessionStatus> {
+    pub fn get_session_status(&mut self, session_id: &str) -> Option<SessionStatus> {
         // 首先检查缓存
-        {
-            let cache = self.status_cache.read().await;
-            if let Some(status) = cache.get(session_id) {
-                return Some(*status);
-            }
+        if let Some(status) = self.status_cache.get(session_id) {
+            return Some(*status);
         }
 
         // 如果没有缓存，尝试检测
-        if let Some(_session) = self.get_session(session_id).await {
-            if let Some(log_path) = self.get_session_log_path(session_id).await {
+        if self.sessions.contains_key(session_id) {
+            if let Some(log_path) = self.get_session_log_path(session_id) {
                 match StatusDetector::detect(&log_path) {
                     Ok(status) => {
                         // 更新缓存
-                        let mut cache = self.status_cache.write().await;
-                        cache.insert(session_id.to_string(), status);
+                        self.status_cache.insert(session_id.to_string(), status);
                         return Some(status);
                     }
                     Err(e) => {
@@ -372,20 +741,19 @@ impl SessionMonitor {
     }
 
     /// 手动刷新指定会话状态
-    pub async fn refresh_session(&self, session_id: &str) -> Result<()> {
+    pub async fn refresh_session(&mut self, session_id: &str) -> Result<()> {
         let log_path = self
             .get_session_log_path(session_id)
-            .await
             .ok_or_else(|| crate::error::AppError::StorageError("未找到日志文件".to_string()))?;
 
         // 检测新状态
         let new_status = StatusDetector::detect(&log_path)?;
 
         // 获取旧状态
-        let old_status = {
-            let cache = self.status_cache.read().await;
-            *cache.get(session_id).unwrap_or(&SessionStatus::Unknown)
-        };
+        let old_status = *self
+            .status_cache
+            .get(session_id)
+            .unwrap_or(&SessionStatus::Unknown);
 
         // 如果状态变化，更新并发送事件
         if new_status != old_status {
@@ -394,38 +762,27 @@ impl SessionMonitor {
                 session_id, old_status, new_status
             );
 
-            {
-                let mut cache = self.status_cache.write().await;
-                cache.insert(session_id.to_string(), new_status);
-            }
+            self.status_cache.insert(session_id.to_string(), new_status);
 
-            {
-                let mut sessions = self.sessions.write().await;
-                if let Some(session) = sessions.get_mut(session_id) {
-                    session.status = new_status;
-                }
+            if let Some(session) = self.sessions.get_mut(session_id) {
+                session.status = new_status;
             }
 
             // 发送状态变更事件
-            let _ = self
-                .event_sender
-                .send(MonitorEvent::StatusChanged {
-                    session_id: session_id.to_string(),
-                    old_status,
-                    new_status,
-                })
-                .await;
+            self.event_bus.publish(MonitorEvent::StatusChanged {
+                session_id: session_id.to_string(),
+                old_status,
+                new_status,
+            });
+            self.publish_snapshot();
         }
 
         Ok(())
     }
 
     /// 手动刷新所有会话
-    pub async fn refresh_all(&self) -> Result<()> {
-        let session_ids: Vec<String> = {
-            let sessions = self.sessions.read().await;
-            sessions.keys().cloned().collect()
-        };
+    pub async fn refresh_all(&mut self) -> Result<()> {
+        let session_ids: Vec<String> = self.sessions.keys().cloned().collect();
 
         for session_id in session_ids {
             if let Err(e) = self.refresh_session(&session_id).await {
@@ -436,37 +793,51 @@ impl SessionMonitor {
         Ok(())
     }
 
-    /// 获取下一个事件
-    pub async fn next_event(&mut self) -> Option<MonitorEvent> {
-        self.event_receiver.recv().await
+    /// 在所有根目录下发现会话
+    async fn discover_all(&self) -> Result<Vec<DiscoveredSession>> {
+        let mut discovered = Vec::new();
+        for discovery in &self.discoveries {
+            discovered.extend(discovery.discover_sessions().await?);
+        }
+        Ok(discovered)
     }
 
-    /// 获取事件接收器的可变引用
-    pub fn event_stream(&mut self) -> &mut mpsc::Receiver<MonitorEvent> {
-        &mut self.event_receiver
+    /// 所有根目录下的 IDE 锁文件目录
+    fn ide_dirs(&self) -> Vec<PathBuf> {
+        self.discoveries.iter().map(|d| d.ide_dir.clone()).collect()
     }
 
-    /// 获取事件接收器的所有权
-    /// 用于在需要 move 接收器的场景
-    /// 创建一个新的接收器来替换原接收器
-    pub fn take_event_stream(&mut self) -> mpsc::Receiver<MonitorEvent> {
-        // 创建一个新的 sender 和 receiver 对
-        let (new_sender, new_receiver) = mpsc::channel(100);
+    /// 在所有根目录下查找项目最新的 jsonl 日志文件
+    fn find_latest_log(&self, project_path: &str) -> Option<PathBuf> {
+        discovery::find_latest_log(&self.discoveries, project_path)
+    }
 
-        // 用新的 sender 替换原有的 sender
-        let old_sender = mem::replace(&mut self.event_sender, new_sender);
+    /// 订阅监控事件
+    ///
+    /// 每个订阅者独立接收全部事件，订阅时会先回放最近一次会话快照
+    pub fn subscribe(&self) -> EventSubscription {
+        self.event_bus.subscribe()
+    }
 
-        // 用新的 receiver 替换原有的 receiver
-        let old_receiver = mem::replace(&mut self.event_receiver, new_receiver);
+    /// 获取事件总线（可跨任务共享）
+    pub fn event_bus(&self) -> EventBus {
+        self.event_bus.clone()
+    }
 
-        old_receiver
+    /// 发布当前全部会话的快照
+    ///
+    /// 同时更新句柄读取的快照和事件总线上的快照事件
+    fn publish_snapshot(&self) {
+        let sessions = self.get_active_sessions();
+        self.snapshot_tx.send_replace(Arc::new(sessions.clone()));
+        self.event_bus.publish(MonitorEvent::Snapshot { sessions });
     }
 
     /// 发现现有会话
     async fn discover_existing_sessions(&mut self) -> Result<()> {
         info!("发现现有会话...");
 
-        let discovered = self.discovery.discover_sessions().await?;
+        let discovered = self.discover_all().await?;
 
         for disc in discovered {
             debug!(
@@ -479,143 +850,110 @@ impl SessionMonitor {
             let session_id = session.id.clone();
 
             // 添加到缓存
-            {
-                let mut sessions = self.sessions.write().await;
-                sessions.insert(session_id.clone(), session.clone());
-            }
+            self.sessions.insert(session_id.clone(), session.clone());
 
             // 开始监控日志文件
-            if let Some(ref log_path) = disc.log_path {
-                if let Err(e) = self.watch_manager.watch_session(log_path).await {
-                    warn!("监控会话日志失败 {}: {}", session_id, e);
-                }
-            }
+            self.watch_session_log(&session_id, &session.project_path).await;
 
             // 发送发现事件
-            let _ = self
-                .event_sender
-                .send(MonitorEvent::SessionDiscovered { session })
-                .await;
+            self.event_bus.publish(MonitorEvent::SessionDiscovered {
+                session: Box::new(session),
+            });
         }
 
-        let count = self.sessions.read().await.len();
+        let count = self.sessions.len();
         info!("已发现 {} 个活跃会话", count);
 
+        self.publish_snapshot();
+
         Ok(())
     }
 
-    /// 启动事件处理循环
-    fn spawn_event_handler(&mut self) {
-        // 从 self 中提取需要在异步任务中使用的数据
-        let event_sender = self.event_sender.clone();
-        let sessions = self.sessions.clone();
-        let status_cache = self.status_cache.clone();
-        let running = self.running.clone();
-
-        // 获取事件流接收器
-        let watch_rx = self.watch_manager.take_event_stream();
-        let Some(mut watch_rx) = watch_rx else {
-            error!("无法获取事件流接收器");
-            return;
-        };
-
-        tokio::spawn(async move {
-            info!("事件处理循环已启动");
-
-            loop {
-                // 检查是否仍在运行
-                if !*running.read().await {
-                    break;
+    /// 处理文件监控事件
+    async fn handle_watch_event(&mut self, event: WatchEvent) {
+        match event {
+            WatchEvent::SessionDiscovered { session: disc } => {
+                // 检查是否已存在
+                let exists = self
+                    .sessions
+                    .values()
+                    .any(|s| s.project_path == disc.project_path.to_string_lossy());
+
+                if !exists {
+                    if let Ok(session) = Self::convert_discovered_to_session(&disc).await {
+                        let session_id = session.id.clone();
+                        self.sessions.insert(session_id.clone(), session.clone());
+                        self.watch_session_log(&session_id, &session.project_path).await;
+
+                        self.event_bus.publish(MonitorEvent::SessionDiscovered {
+                            session: Box::new(session),
+                        });
+                        self.publish_snapshot();
+                    }
                 }
+            }
+            WatchEvent::LogChanged { path } => {
+                let Some(session_id) = self.session_for_log(&path) else {
+                    debug!("日志不属于已知会话: {:?}", path);
+                    return;
+                };
 
-                match watch_rx.recv().await {
-                    Some(WatchEvent::SessionDiscovered { session: disc }) => {
-                        // 检查是否已存在
-                        let exists = {
-                            let sessions = sessions.read().await;
-                            sessions.values().any(|s| {
-                                s.project_path == disc.project_path.to_string_lossy()
-                            })
-                        };
-
-                        if !exists {
-                            if let Ok(session) =
-                                Self::convert_discovered_to_session(&disc).await
-                            {
-                                let session_id = session.id.clone();
-
-                                {
-                                    let mut sessions = sessions.write().await;
-                                    sessions.insert(session_id.clone(), session.clone());
-                                }
-
-                                let _ = event_sender
-                                    .send(MonitorEvent::SessionDiscovered { session })
-                                    .await;
-                            }
+                // 发布新追加的消息；未读取过的日志（如新建的对话日志）从头读取
+                let offset = self.log_offsets.get(&path).copied().unwrap_or(0);
+                match StatusDetector::read_new_messages(&path, offset) {
+                    Ok((messages, offset)) => {
+                        self.log_offsets.insert(path.clone(), offset);
+                        for message in messages {
+                            self.event_bus.publish(MonitorEvent::NewMessage {
+                                session_id: session_id.clone(),
+                                message,
+                            });
                         }
                     }
-                    Some(WatchEvent::LogChanged { session_id, path }) => {
-                        // 检测状态变化
-                        if let Ok(new_status) = StatusDetector::detect(&path) {
-                            let old_status = {
-                                let cache = status_cache.read().await;
-                                *cache.get(&session_id).unwrap_or(&SessionStatus::Unknown)
-                            };
-
-                            if new_status != old_status {
-                                {
-                                    let mut cache = status_cache.write().await;
-                                    cache.insert(session_id.clone(), new_status);
-                                }
+                    Err(e) => debug!("读取新消息失败 {:?}: {}", path, e),
+                }
 
-                                {
-                                    let mut sessions = sessions.write().await;
-                                    if let Some(session) = sessions.get_mut(&session_id) {
-                                        session.status = new_status;
-                                    }
-                                }
+                // 检测状态变化
+                if let Ok(new_status) = StatusDetector::detect(&path) {
+                    let old_status = *self
+                        .status_cache
+                        .get(&session_id)
+                        .unwrap_or(&SessionStatus::Unknown);
 
-                                let _ = event_sender
-                                    .send(MonitorEvent::StatusChanged {
-                                        session_id,
-                                        old_status,
-                                        new_status,
-                                    })
-                                    .await;
-                            }
-                        }
-                    }
-                    Some(WatchEvent::SessionEnded { session_id }) => {
-                        {
-                            let mut sessions = sessions.write().await;
-                            sessions.remove(&session_id);
-                        }
+                    if new_status != old_status {
+                        self.status_cache.insert(session_id.clone(), new_status);
 
-                        {
-                            let mut cache = status_cache.write().await;
-                            cache.remove(&session_id);
+                        if let Some(session) = self.sessions.get_mut(&session_id) {
+                            session.status = new_status;
                         }
 
-                        let _ = event_sender
-                            .send(MonitorEvent::SessionEnded { session_id })
-                            .await;
-                    }
-                    Some(WatchEvent::Error { message }) => {
-                        error!("监控错误: {}", message);
-                        let _ = event_sender
-                            .send(MonitorEvent::Error { message })
-                            .await;
-                    }
-                    None => {
-                        // 通道关闭
-                        break;
+                        self.event_bus.publish(MonitorEvent::StatusChanged {
+                            session_id,
+                            old_status,
+                            new_status,
+                        });
+                        self.publish_snapshot();
                     }
                 }
             }
+            WatchEvent::SessionEnded { session_id } => {
+                // 应用启动的会话以进程存活为准
+                if self.managed_alive(&session_id) == Some(true) {
+                    return;
+                }
+                self.managed.remove(&session_id);
+                self.sessions.remove(&session_id);
+                self.status_cache.remove(&session_id);
+                self.unwatch_session_log(&session_id).await;
 
-            info!("事件处理循环已停止");
-        });
+                self.event_bus.publish(MonitorEvent::SessionEnded { session_id });
+                self.publish_snapshot();
+            }
+            WatchEvent::Error { message } => {
+                error!("监控错误: {}", message);
+                self.event_bus.publish(MonitorEvent::Error { message });
+            }
+        }
     }
 
     /// 转换发现的会话为 Session 对象
@@ -671,6 +1009,17 @@ impl SessionMonitor {
         let now = chrono::Utc::now();
         let created_at = disc.start_time.unwrap_or(now);
 
+        // 有 PID 时记录会话所在的 tmux 窗格
+        let tmux_pane = if disc.pid != 0 {
+            let pid = disc.pid;
+            tokio::task::spawn_blocking(move || crate::tmux::find_pane(None, pid))
+                .await
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
+
         Ok(Session {
             id: session_id,
             title: format!("{} | {}", disc.project_name, title_prompt),
@@ -682,39 +1031,21 @@ impl SessionMonitor {
             last_active_at: now,
             summary: if summary_text.is_empty() { None } else { Some(summary_text) },
             is_archived: false,
+            pid: (disc.pid != 0).then_some(disc.pid),
+            tmux_pane,
+            parent_repo: crate::worktree::parent_repo(&disc.project_path)
+                .map(|p| p.to_string_lossy().to_string()),
+            ended_at: None,
+            marked_completed_at: None,
+            custom_title: None,
         })
     }
 
     /// 获取会话的日志路径
-    async fn get_session_log_path(&self, session_id: &str) -> Option<PathBuf> {
-        let sessions = self.sessions.read().await;
-        sessions.get(session_id).and_then(|session| {
-            // 从项目路径构造日志路径
-            let home = dirs::home_dir()?;
-            let project_path = &session.project_path;
-            let encoded = project_path.replace('/', "-").replace('\\', "-");
-            let log_dir = home.join(".claude").join("projects").join(encoded);
-
-            // 查找最新的 jsonl 文件
-            std::fs::read_dir(&log_dir)
-                .ok()?
-                .filter_map(|entry| entry.ok())
-                .filter(|entry| {
-                    entry
-                        .path()
-                        .extension()
-                        .map(|ext| ext == "jsonl")
-                        .unwrap_or(false)
-                })
-                .max_by_key(|entry| {
-                    entry
-                        .metadata()
-                        .ok()
-                        .and_then(|m| m.modified().ok())
-                        .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
-                })
-                .map(|entry| entry.path())
-        })
+    fn get_session_log_path(&self, session_id: &str) -> Option<PathBuf> {
+        self.sessions
+            .get(session_id)
+            .and_then(|session| self.find_latest_log(&session.project_path))
     }
 
     /// 使用 flock 检查进程是否存在
@@ -733,103 +1064,105 @@ impl SessionMonitor {
         // 归一化路径比较（转小写）
         let target_path = project_path.to_string_lossy().to_lowercase();
 
-        // 查找 IDE 目录下的锁文件
-        let ide_dir = &self.discovery.ide_dir;
-        if !ide_dir.exists() {
-            debug!("[check_process_existence] IDE 目录不存在");
-            return ProcessExistence::NotFound;
-        }
-
-        let mut entries = match tokio::fs::read_dir(ide_dir).await {
-            Ok(entries) => entries,
-            Err(e) => {
-                debug!("[check_process_existence] 读取 IDE 目录失败: {}", e);
-                return ProcessExistence::NotFound;
-            }
-        };
-
-        while let Ok(Some(entry)) = entries.next_entry().await {
-            let path = entry.path();
-            if path.extension() != Some("lock".as_ref()) {
+        // 查找各根目录 IDE 目录下的锁文件
+        for ide_dir in self.ide_dirs() {
+            if !ide_dir.exists() {
+                debug!("[check_process_existence] IDE 目录不存在: {:?}", ide_dir);
                 continue;
             }
 
-            // 读取锁文件内容，检查是否包含目标项目
-            match tokio::fs::read_to_string(&path).await {
-                Ok(content) => {
-                    match serde_json::from_str::<serde_json::Value>(&content) {
-                        Ok(lock) => {
-                            // 归一化路径比较
-                            let workspaces = lock.get("workspaceFolders");
-                            if let Some(ws_array) = workspaces {
-                                if let Some(ws_vec) = ws_array.as_array() {
-                                    let matches = ws_vec.iter().any(|w| {
-                                        w.as_str().map(|s| {
-                                            let lock_path = s.to_lowercase();
-                                            // 支持精确匹配和前缀匹配
-                                            lock_path == target_path ||
-                                                lock_path.starts_with(&target_path)
-                                        }).unwrap_or(false)
-                                    });
-                                    if !matches {
-                                        continue;
+            let mut entries = match tokio::fs::read_dir(&ide_dir).await {
+                Ok(entries) => entries,
+                Err(e) => {
+                    debug!("[check_process_existence] 读取 IDE 目录失败: {}", e);
+                    continue;
+                }
+            };
+
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                let path = entry.path();
+                if path.extension() != Some("lock".as_ref()) {
+                    continue;
+                }
+
+                // 读取锁文件内容，检查是否包含目标项目
+                match tokio::fs::read_to_string(&path).await {
+                    Ok(content) => {
+                        match serde_json::from_str::<serde_json::Value>(&content) {
+                            Ok(lock) => {
+                                // 归一化路径比较
+                                let workspaces = lock.get("workspaceFolders");
+                                if let Some(ws_array) = workspaces {
+                                    if let Some(ws_vec) = ws_array.as_array() {
+                                        let matches = ws_vec.iter().any(|w| {
+                                            w.as_str().map(|s| {
+                                                let lock_path = s.to_lowercase();
+                                                // 支持精确匹配和前缀匹配
+                                                lock_path == target_path ||
+                                                    lock_path.starts_with(&target_path)
+                                            }).unwrap_or(false)
+                                        });
+                                        if !matches {
+                                            continue;
+                                        }
                                     }
                                 }
                             }
-                        }
-                        Err(e) => {
-                            debug!("[check_process_existence] 解析锁文件失败: {}", e);
-                            continue;
+                            Err(e) => {
+                                debug!("[check_process_existence] 解析锁文件失败: {}", e);
+                                continue;
+                            }
                         }
                     }
+                    Err(e) => {
+                        debug!("[check_process_existence] 读取锁文件失败: {}", e);
+                        continue;
+                    }
                 }
-                Err(e) => {
-                    debug!("[check_process_existence] 读取锁文件失败: {}", e);
-                    continue;
-                }
-            }
 
-            // 找到匹配的锁文件，尝试获取排他锁
-            match std::fs::File::open(&path) {
-                Ok(file) => {
-                    // 尝试获取非阻塞排他锁
-                    #[cfg(unix)]
-                    {
-                        let fd = file.as_raw_fd();
-                        match flock(fd, FlockArg::LockExclusive) {
-                            Ok(()) => {
-                                // 加锁成功，说明原进程已释放锁（进程已死）
-                                let _ = flock(fd, FlockArg::Unlock);
-                                debug!("[check_process_existence] 锁可获取，进程已死");
-                                return ProcessExistence::Dead;
-                            }
-                            Err(nix::errno::Errno::EWOULDBLOCK) | Err(nix::errno::Errno::EAGAIN) => {
-                                // 加锁失败，说明锁正被占用（进程活着）
-                                debug!("[check_process_existence] 锁被占用，进程在运行");
-                                return ProcessExistence::Alive;
+                // 找到匹配的锁文件，尝试获取排他锁
+                match std::fs::File::open(&path) {
+                    Ok(file) => {
+                        // 尝试获取非阻塞排他锁
+                        #[cfg(unix)]
+                        {
+                            let fd = file.as_raw_fd();
+                            match flock(fd, FlockArg::LockExclusive) {
+                                Ok(()) => {
+                                    // 加锁成功，说明原进程已释放锁（进程已死）
+                                    let _ = flock(fd, FlockArg::Unlock);
+                                    debug!("[check_process_existence] 锁可获取，进程已死");
+                                    return ProcessExistence::Dead;
+                                }
+                                Err(nix::errno::Errno::EWOULDBLOCK) | Err(nix::errno::Errno::EAGAIN) => {
+                                    // 加锁失败，说明锁正被占用（进程活着）
+                                    debug!("[check_process_existence] 锁被占用，进程在运行");
+                                    return ProcessExistence::Alive;
+                                }
+                                Err(e) => {
+                                    debug!("[check_process_existence] flock 错误: {}，保守认为进程存活", e);
+                                    // 其他错误，保守处理认为进程存活
+                                    return ProcessExistence::Alive;
+                                }
                             }
-                            Err(e) => {
-                                debug!("[check_process_existence] flock 错误: {}，保守认为进程存活", e);
-                                // 其他错误，保守处理认为进程存活
-                                return ProcessExistence::Alive;
+                        }
+
+                        // Windows: 使用 has_active_lock_file 作为后备
+                        #[cfg(windows)]
+                        {
+                            for discovery in &self.discoveries {
+                                if discovery.has_active_lock_file(project_path).await {
+                                    return ProcessExistence::Alive;
+                                }
                             }
+                            return ProcessExistence::NotFound;
                         }
                     }
-
-                    // Windows: 使用 has_active_lock_file 作为后备
-                    #[cfg(windows)]
-                    {
-                        let has_lock = self
-                            .discovery
-                            .has_active_lock_file(project_path)
-                            .await;
-                        return if has_lock { ProcessExistence::Alive } else { ProcessExistence::NotFound };
+                    Err(e) => {
+                        debug!("[check_process_existence] 打开锁文件失败: {}", e);
+                        continue;
                     }
                 }
-                Err(e) => {
-                    debug!("[check_process_existence] 打开锁文件失败: {}", e);
-                    continue;
-                }
             }
         }
 
@@ -841,27 +1174,28 @@ impl SessionMonitor {
     /// 快速扫描 IDE 目录，建立 路径 -> 锁是否被占用 的映射
     async fn scan_all_locks(&self) -> HashMap<String, bool> {
         let mut lock_map: HashMap<String, bool> = HashMap::new();
-        let ide_dir = &self.discovery.ide_dir;
 
-        if let Ok(mut entries) = tokio::fs::read_dir(ide_dir).await {
-            while let Ok(Some(entry)) = entries.next_entry().await {
-                let path = entry.path();
-                if path.extension().map_or(true, |ext| ext != "lock") {
-                    continue;
-                }
+        for ide_dir in self.ide_dirs() {
+            if let Ok(mut entries) = tokio::fs::read_dir(&ide_dir).await {
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    let path = entry.path();
+                    if path.extension().map_or(true, |ext| ext != "lock") {
+                        continue;
+                    }
 
-                // 每一个锁文件只判定一次状态
-                let is_alive = self.is_lock_busy(&path);
-                debug!("[scan_all_locks] 锁文件 {} 状态: {}", path.display(), is_alive);
-
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(lock_json) = serde_json::from_str::<serde_json::Value>(&content) {
-                        if let Some(folders) = lock_json["workspaceFolders"].as_array() {
-                            for folder in folders {
-                                if let Some(p) = folder.as_str() {
-                                    let normalized_key = normalize_path(p);
-                                    lock_map.insert(normalized_key.clone(), is_alive);
-                                    debug!("[scan_all_locks] 映射: {} -> {}", normalized_key, is_alive);
+                    // 每一个锁文件只判定一次状态
+                    let is_alive = self.is_lock_busy(&path);
+                    debug!("[scan_all_locks] 锁文件 {} 状态: {}", path.display(), is_alive);
+
+                    if let Ok(content) = fs::read_to_string(&path) {
+                        if let Ok(lock_json) = serde_json::from_str::<serde_json::Value>(&content) {
+                            if let Some(folders) = lock_json["workspaceFolders"].as_array() {
+                                for folder in folders {
+                                    if let Some(p) = folder.as_str() {
+                                        let normalized_key = normalize_path(p);
+                                        lock_map.insert(normalized_key.clone(), is_alive);
+                                        debug!("[scan_all_locks] 映射: {} -> {}", normalized_key, is_alive);
+                                    }
                                 }
                             }
                         }
@@ -908,29 +1242,30 @@ impl SessionMonitor {
     /// 实时验证特定项目的锁状态
     async fn verify_project_lock_realtime(&self, project_path: &Path) -> bool {
         let project_key = normalize_path(&project_path.to_string_lossy());
-        let ide_dir = &self.discovery.ide_dir;
 
-        debug!("[verify_realtime] 实时检查项目: {} (ide_dir: {:?})", project_key, ide_dir);
+        for ide_dir in self.ide_dirs() {
+            debug!("[verify_realtime] 实时检查项目: {} (ide_dir: {:?})", project_key, ide_dir);
 
-        if let Ok(mut entries) = tokio::fs::read_dir(ide_dir).await {
-            while let Ok(Some(entry)) = entries.next_entry().await {
-                let path = entry.path();
-                if path.extension().map_or(true, |ext| ext != "lock") {
-                    continue;
-                }
+            if let Ok(mut entries) = tokio::fs::read_dir(&ide_dir).await {
+                while let Ok(Some(entry)) = entries.next_entry().await {
+                    let path = entry.path();
+                    if path.extension().map_or(true, |ext| ext != "lock") {
+                        continue;
+                    }
 
-                // 检查是否包含目标项目
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(lock_json) = serde_json::from_str::<serde_json::Value>(&content) {
-                        if let Some(folders) = lock_json["workspaceFolders"].as_array() {
-                            let matches = folders.iter().any(|f| {
-                                f.as_str().map_or(false, |p| normalize_path(p) == project_key)
-                            });
-                            if matches {
-                                // 找到匹配的锁文件，实时检查
-                                let is_busy = self.is_lock_busy(&path);
-                                debug!("[verify_realtime] 找到锁文件 {}，状态: {}", path.display(), is_busy);
-                                return is_busy;
+                    // 检查是否包含目标项目
+                    if let Ok(content) = fs::read_to_string(&path) {
+                        if let Ok(lock_json) = serde_json::from_str::<serde_json::Value>(&content) {
+                            if let Some(folders) = lock_json["workspaceFolders"].as_array() {
+                                let matches = folders.iter().any(|f| {
+                                    f.as_str().map_or(false, |p| normalize_path(p) == project_key)
+                                });
+                                if matches {
+                                    // 找到匹配的锁文件，实时检查
+                                    let is_busy = self.is_lock_busy(&path);
+                                    debug!("[verify_realtime] 找到锁文件 {}，状态: {}", path.display(), is_busy);
+                                    return is_busy;
+                                }
                             }
                         }
                     }
@@ -944,21 +1279,10 @@ impl SessionMonitor {
     /// 获取日志文件的空闲时间（分钟）
     /// 返回 None 表示无法获取（日志文件不存在等）
     fn get_log_idle_minutes(&self, project_path: &str) -> Option<i64> {
-        let home = dirs::home_dir()?;
-        let encoded = project_path.replace('/', "-").replace('\\', "-");
-        let log_dir = home.join(".claude").join("projects").join(encoded);
-
         // 查找最新的 jsonl 文件
-        let latest_file = std::fs::read_dir(&log_dir).ok()?
-            .filter_map(|entry| entry.ok())
-            .filter(|entry| {
-                entry.path().extension().map(|ext| ext == "jsonl").unwrap_or(false)
-            })
-            .max_by_key(|entry| {
-                entry.metadata().ok()?.modified().ok()
-            })?;
+        let latest_file = self.find_latest_log(project_path)?;
 
-        let metadata = latest_file.metadata().ok()?;
+        let metadata = std::fs::metadata(latest_file).ok()?;
         let mtime = metadata.modified().ok()?;
         let mtime: chrono::DateTime<chrono::Utc> = mtime.into();
 
@@ -979,10 +1303,11 @@ impl SessionMonitor {
         let lock_snapshot = self.scan_all_locks().await;
 
         // --- 第二阶段：扫描日志（发现会话） ---
-        let discovered = self.discovery.discover_sessions().await?;
+        let discovered = self.discover_all().await?;
         debug!("[instant_refresh] 发现 {} 个会话", discovered.len());
 
-        let mut sessions = self.sessions.write().await;
+        // 暂时取出会话缓存，以便在遍历期间调用 &self 方法
+        let mut sessions = mem::take(&mut self.sessions);
         let mut current_round_ids: HashSet<String> = HashSet::new();
 
         for disc in discovered {
@@ -999,7 +1324,13 @@ impl SessionMonitor {
 
             if !sessions.contains_key(&session_id) {
                 // === 新面孔 ===
-                let mut new_session = Self::convert_discovered_to_session(&disc).await?;
+                let mut new_session = match Self::convert_discovered_to_session(&disc).await {
+                    Ok(session) => session,
+                    Err(e) => {
+                        warn!("[instant_refresh] 转换会话失败 {}: {}", disc.project_name, e);
+                        continue;
+                    }
+                };
                 new_session.created_at = now;
 
                 // 根据快照状态设置初始状态
@@ -1015,12 +1346,9 @@ impl SessionMonitor {
                     disc.project_name, new_session.status
                 );
 
-                let _ = self
-                    .event_sender
-                    .send(MonitorEvent::SessionDiscovered {
-                        session: new_session,
-                    })
-                    .await;
+                self.event_bus.publish(MonitorEvent::SessionDiscovered {
+                        session: Box::new(new_session),
+                    });
             } else {
                 // === 老员工 ===
                 let session = sessions.get_mut(&session_id).unwrap();
@@ -1031,8 +1359,11 @@ impl SessionMonitor {
                     disc.project_name, session.status, uptime
                 );
 
-                // 核心修复：无论快照说什么，都实时验证
-                let is_currently_alive = self.verify_project_lock_realtime(&disc.project_path).await;
+                // 核心修复：无论快照说什么，都实时验证（应用启动的会话以进程为准）
+                let is_currently_alive = match self.managed_alive(&session_id) {
+                    Some(alive) => alive,
+                    None => self.verify_project_lock_realtime(&disc.project_path).await,
+                };
                 debug!("[instant_refresh] {} 实时锁状态: {}", disc.project_name, is_currently_alive);
 
                 if !is_currently_alive {
@@ -1065,19 +1396,26 @@ impl SessionMonitor {
                     if session.status == SessionStatus::Initializing {
                         debug!("[instant_refresh] {} 转正: Initializing -> Running", disc.project_name);
                         session.status = SessionStatus::Running;
-                        let _ = self
-                            .event_sender
-                            .send(MonitorEvent::StatusChanged {
-                                session_id: session_id.clone(),
-                                old_status: SessionStatus::Initializing,
-                                new_status: SessionStatus::Running,
-                            })
-                            .await;
+                        self.event_bus.publish(MonitorEvent::StatusChanged {
+                            session_id: session_id.clone(),
+                            old_status: SessionStatus::Initializing,
+                            new_status: SessionStatus::Running,
+                        });
                     }
                 }
             }
         }
 
+        // 应用启动的会话在日志出现前不会被发现，进程存活即保留
+        let managed_ids: Vec<String> = self.managed.keys().cloned().collect();
+        for id in managed_ids {
+            if self.managed_alive(&id) == Some(true) && sessions.contains_key(&id) {
+                current_round_ids.insert(id);
+            } else {
+                self.managed.remove(&id);
+            }
+        }
+
         // 清理阶段
         let mut to_remove = Vec::new();
         sessions.retain(|id, session| {
@@ -1090,12 +1428,24 @@ impl SessionMonitor {
         });
 
         for id in to_remove {
-            let _ = self
-                .event_sender
-                .send(MonitorEvent::SessionEnded { session_id: id })
-                .await;
+            self.unwatch_session_log(&id).await;
+            self.event_bus.publish(MonitorEvent::SessionEnded { session_id: id });
         }
 
+        self.sessions = sessions;
+
+        // 补上新会话与日志后出现的会话（如应用启动的会话）的日志监控
+        let watched: Vec<(String, String)> = self
+            .sessions
+            .values()
+            .map(|s| (s.id.clone(), s.project_path.clone()))
+            .collect();
+        for (session_id, project_path) in watched {
+            self.watch_session_log(&session_id, &project_path).await;
+        }
+
+        self.publish_snapshot();
+
         info!(
             "[instant_refresh] 完成，活跃会话: {}",
             current_round_ids.len()
@@ -1104,6 +1454,14 @@ impl SessionMonitor {
     }
 }
 
+/// 接收下一个文件监控事件；监控已停止时永远挂起
+async fn next_watch_event(watch_rx: &mut Option<mpsc::Receiver<WatchEvent>>) -> Option<WatchEvent> {
+    match watch_rx {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 /// 使用 flock 检查锁文件是否被占用
 async fn check_physical_alive(lock_path: &PathBuf) -> bool {
     use nix::fcntl::flock;
@@ -1152,9 +1510,215 @@ mod tests {
     #[tokio::test]
     async fn test_monitor_creation() {
         // 这个测试需要 Claude Code 环境，仅在本地运行
-        if let Ok(monitor) = SessionMonitor::new().await {
-            // 成功创建
-            assert!(!(*monitor.running.read().await));
+        if let Ok(monitor) = SessionMonitor::new(test_config(Vec::new())) {
+            // 成功创建，尚未发现任何会话
+            assert!(monitor.get_active_sessions().is_empty());
+
+            let handle = monitor.spawn();
+            assert!(handle.get_session("sess_missing").is_none());
         }
     }
+
+    fn test_config(roots: Vec<PathBuf>) -> MonitorConfig {
+        MonitorConfig {
+            refresh_interval: Duration::from_secs(60),
+            roots,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_stop_restart_shutdown() {
+        let root = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(root.path().join("ide")).unwrap();
+        std::fs::create_dir_all(root.path().join("projects")).unwrap();
+
+        let handle = SessionMonitor::new(test_config(vec![root.path().to_path_buf()]))
+            .unwrap()
+            .spawn();
+
+        handle.refresh().await.unwrap();
+        assert!(handle.is_running());
+
+        handle.stop().await.unwrap();
+        assert!(!handle.is_running());
+
+        handle.restart().await.unwrap();
+        assert!(handle.is_running());
+
+        handle.shutdown().await.unwrap();
+        assert!(!handle.is_running());
+        assert!(handle.refresh().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reconfigure_keeps_running() {
+        let first = tempfile::TempDir::new().unwrap();
+        let second = tempfile::TempDir::new().unwrap();
+
+        let handle = SessionMonitor::new(test_config(vec![first.path().to_path_buf()]))
+            .unwrap()
+            .spawn();
+        handle.refresh().await.unwrap();
+
+        let mut config = test_config(vec![second.path().to_path_buf()]);
+        config.refresh_interval = Duration::from_secs(30);
+        handle.reconfigure(config).await.unwrap();
+
+        assert!(handle.is_running());
+        handle.refresh().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_log_append_reaches_notification_filter() {
+        use crate::models::NotificationSettings;
+        use crate::notifications::filter::{NotificationFilter, NotificationKind};
+        use std::io::Write;
+        use std::time::Instant;
+
+        let root = tempfile::TempDir::new().unwrap();
+        let project = root.path().join("work").join("app");
+        let log_dir = root
+            .path()
+            .join("projects")
+            .join(discovery::encode_project_path(&project));
+        std::fs::create_dir_all(root.path().join("ide")).unwrap();
+        std::fs::create_dir_all(&log_dir).unwrap();
+        // 日志文件名是 Claude Code 的会话 UUID，与监控的会话 ID 不同
+        let log_path = log_dir.join("0f6c3a52-1d2e-4b7a-9c0d-5e8f7a6b4c3d.jsonl");
+        let line = |kind: &str, text: &str| {
+            format!(
+                r#"{{"type":"{kind}","cwd":"{}","timestamp":"{}","message":{{"role":"{kind}","content":[{{"type":"text","text":"{text}"}}]}}}}"#,
+                project.display(),
+                Utc::now().to_rfc3339()
+            )
+        };
+        std::fs::write(&log_path, line("user", "修复登录") + "\n").unwrap();
+
+        let handle = SessionMonitor::new(test_config(vec![root.path().to_path_buf()]))
+            .unwrap()
+            .spawn();
+        let mut events = handle.subscribe();
+        // 应用启动的会话登记时没有日志路径
+        let session = handle
+            .register(DiscoveredSession {
+                pid: std::process::id(),
+                project_path: project.clone(),
+                project_name: "app".to_string(),
+                log_path: None,
+                start_time: None,
+            })
+            .await
+            .unwrap();
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&log_path).unwrap();
+        writeln!(file, "{}", line("assistant", "要继续吗？")).unwrap();
+        drop(file);
+
+        let settings = NotificationSettings {
+            min_duration_secs: 0,
+            ..NotificationSettings::default()
+        };
+        let mut filter = NotificationFilter::new(true, settings);
+        let mut known = HashSet::new();
+        let due = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let event = events.recv().await.unwrap();
+                match &event {
+                    MonitorEvent::Snapshot { sessions } => {
+                        known.extend(sessions.iter().map(|s| s.id.clone()));
+                    }
+                    MonitorEvent::SessionDiscovered { session } => {
+                        known.insert(session.id.clone());
+                    }
+                    _ => {}
+                }
+                filter.on_event(&event, Instant::now());
+                let due = filter.take_due(Instant::now());
+                if !due.is_empty() {
+                    break due;
+                }
+            }
+        })
+        .await
+        .expect("日志追加后应产生通知");
+
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].session_id, session.id);
+        assert_eq!(due[0].kind, NotificationKind::WaitingInput);
+        // 通知任务只为快照中出现过的会话发送通知
+        assert!(known.contains(&session.id));
+        assert_eq!(
+            handle.get_session(&session.id).unwrap().status,
+            SessionStatus::WaitingInput
+        );
+    }
+
+    #[tokio::test]
+    async fn test_log_append_publishes_new_messages() {
+        use std::io::Write;
+
+        let root = tempfile::TempDir::new().unwrap();
+        let project = root.path().join("work").join("app");
+        let log_dir = root
+            .path()
+            .join("projects")
+            .join(discovery::encode_project_path(&project));
+        std::fs::create_dir_all(root.path().join("ide")).unwrap();
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let log_path = log_dir.join("0f6c3a52-1d2e-4b7a-9c0d-5e8f7a6b4c3d.jsonl");
+        let line = |kind: &str, text: &str| {
+            format!(
+                r#"{{"type":"{kind}","timestamp":"{}","message":{{"role":"{kind}","content":[{{"type":"text","text":"{text}"}}]}}}}"#,
+                Utc::now().to_rfc3339()
+            )
+        };
+        std::fs::write(&log_path, line("user", "旧消息") + "\n").unwrap();
+
+        let handle = SessionMonitor::new(test_config(vec![root.path().to_path_buf()]))
+            .unwrap()
+            .spawn();
+        let mut events = handle.subscribe();
+        let session = handle
+            .register(DiscoveredSession {
+                pid: std::process::id(),
+                project_path: project.clone(),
+                project_name: "app".to_string(),
+                log_path: None,
+                start_time: None,
+            })
+            .await
+            .unwrap();
+
+        // 写到一半的行等写完后再发布
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&log_path)
+            .unwrap();
+        let assistant = line("assistant", "12 tests passed");
+        let (head, tail) = assistant.split_at(20);
+        write!(file, "{}", head).unwrap();
+        file.flush().unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        writeln!(file, "{}", tail).unwrap();
+        drop(file);
+
+        let message = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                if let MonitorEvent::NewMessage {
+                    session_id,
+                    message,
+                } = events.recv().await.unwrap()
+                {
+                    break (session_id, message);
+                }
+            }
+        })
+        .await
+        .expect("日志追加后应发布新消息");
+
+        // 登记前已有的消息不会重复发布
+        assert_eq!(message.0, session.id);
+        assert_eq!(message.1.role, crate::models::MessageRole::Assistant);
+        assert_eq!(message.1.content, "12 tests passed");
+    }
 }
@@ -7,6 +7,7 @@ use crate::models::{Message, MessageMetadata, MessageRole, SessionStatus};
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
 use tracing::{debug, trace};
 
@@ -80,6 +81,30 @@ impl StatusDetector {
         Ok(messages)
     }
 
+    /// 读取偏移位置之后新追加的消息，返回消息与新的偏移位置
+    ///
+    /// 只处理以换行结尾的完整行，写到一半的行留到下次读取；文件变短（被重写）时从头读取。
+    pub fn read_new_messages(log_path: &Path, offset: u64) -> Result<(Vec<Message>, u64)> {
+        let mut file = std::fs::File::open(log_path)?;
+        let len = file.metadata()?.len();
+        let offset = if offset > len { 0 } else { offset };
+
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let complete = match bytes.iter().rposition(|b| *b == b'\n') {
+            Some(last_newline) => &bytes[..=last_newline],
+            None => &bytes[..0],
+        };
+
+        let messages = String::from_utf8_lossy(complete)
+            .lines()
+            .filter_map(|line| serde_json::from_str::<LogEvent>(line).ok())
+            .filter_map(|event| Self::convert_to_message(&event))
+            .collect();
+        Ok((messages, offset + complete.len() as u64))
+    }
+
     /// 提取第一条用户消息
     pub fn extract_first_user_message(log_path: &Path) -> Result<Option<Message>> {
         if !log_path.exists() {
@@ -16,8 +16,8 @@ use tracing::{debug, error, info, warn};
 pub enum WatchEvent {
     /// 发现新会话
     SessionDiscovered { session: DiscoveredSession },
-    /// 会话状态可能变更
-    LogChanged { session_id: String, path: PathBuf },
+    /// 日志文件变更（由监控器按日志路径映射到所属会话）
+    LogChanged { path: PathBuf },
     /// 会话结束（锁文件被删除）
     SessionEnded { session_id: String },
     /// 监控错误
@@ -32,15 +32,17 @@ pub struct LogWatcher {
     watched_paths: Arc<RwLock<HashSet<PathBuf>>>,
     /// 事件发送通道
     event_sender: mpsc::Sender<WatchEvent>,
-    /// 会话发现器
-    discovery: SessionDiscovery,
+    /// 会话发现器（每个根目录一个）
+    discoveries: Vec<SessionDiscovery>,
 }
 
 impl LogWatcher {
     /// 创建新的文件监控器
-    pub fn new(event_sender: mpsc::Sender<WatchEvent>) -> Result<Self> {
+    pub fn new(
+        event_sender: mpsc::Sender<WatchEvent>,
+        discoveries: Vec<SessionDiscovery>,
+    ) -> Result<Self> {
         let watched_paths = Arc::new(RwLock::new(HashSet::new()));
-        let discovery = SessionDiscovery::new()?;
 
         // 创建 notify 监控器
         let watcher = Self::create_watcher(event_sender.clone(), watched_paths.clone())?;
@@ -49,7 +51,7 @@ impl LogWatcher {
             watcher,
             watched_paths,
             event_sender,
-            discovery,
+            discoveries,
         })
     }
 
@@ -105,12 +107,7 @@ impl LogWatcher {
                         }
                     } else if Self::is_log_file(path) {
                         // 新日志文件 - 发送变更事件
-                        if let Some(session_id) = Self::extract_session_id_from_log(path) {
-                            let _ = sender.try_send(WatchEvent::LogChanged {
-                                session_id,
-                                path: path.clone(),
-                            });
-                        }
+                        let _ = sender.try_send(WatchEvent::LogChanged { path: path.clone() });
                     }
                 }
             }
@@ -118,12 +115,7 @@ impl LogWatcher {
                 // 文件修改 - 日志更新
                 for path in &event.paths {
                     if Self::is_log_file(path) {
-                        if let Some(session_id) = Self::extract_session_id_from_log(path) {
-                            let _ = sender.try_send(WatchEvent::LogChanged {
-                                session_id,
-                                path: path.clone(),
-                            });
-                        }
+                        let _ = sender.try_send(WatchEvent::LogChanged { path: path.clone() });
                     }
                 }
             }
@@ -158,26 +150,29 @@ impl LogWatcher {
     pub async fn initialize(&mut self) -> Result<()> {
         info!("初始化文件监控...");
 
-        // 监控锁文件目录
-        let ide_dir = self.discovery.ide_dir.clone();
-        if ide_dir.exists() {
-            self.watcher.watch(&ide_dir, RecursiveMode::NonRecursive)?;
-            info!("开始监控锁文件目录: {:?}", ide_dir);
-        }
+        for discovery in self.discoveries.clone() {
+            // 监控锁文件目录
+            let ide_dir = discovery.ide_dir.clone();
+            if ide_dir.exists() {
+                self.watcher.watch(&ide_dir, RecursiveMode::NonRecursive)?;
+                self.watched_paths.write().await.insert(ide_dir.clone());
+                info!("开始监控锁文件目录: {:?}", ide_dir);
+            }
 
-        // 发现已存在的会话并开始监控
-        match self.discovery.discover_sessions().await {
-            Ok(sessions) => {
-                for session in sessions {
-                    if let Some(log_path) = &session.log_path {
-                        if let Err(e) = self.watch_log(log_path).await {
-                            warn!("监控日志文件失败 {:?}: {}", log_path, e);
+            // 发现已存在的会话并开始监控
+            match discovery.discover_sessions().await {
+                Ok(sessions) => {
+                    for session in sessions {
+                        if let Some(log_path) = &session.log_path {
+                            if let Err(e) = self.watch_log(log_path).await {
+                                warn!("监控日志文件失败 {:?}: {}", log_path, e);
+                            }
                         }
                     }
                 }
-            }
-            Err(e) => {
-                warn!("发现会话失败: {}", e);
+                Err(e) => {
+                    warn!("发现会话失败: {}", e);
+                }
             }
         }
 
@@ -226,6 +221,19 @@ impl LogWatcher {
         }
     }
 
+    /// 取消所有监控
+    pub async fn unwatch_all(&mut self) {
+        let paths: Vec<PathBuf> = self.watched_paths.write().await.drain().collect();
+
+        for path in &paths {
+            if let Err(e) = self.watcher.unwatch(path) {
+                debug!("取消监控失败 {:?}: {}", path, e);
+            }
+        }
+
+        info!("已取消 {} 个文件监控", paths.len());
+    }
+
     /// 检查是否是锁文件
     fn is_lock_file(path: &Path) -> bool {
         path.extension() == Some("lock".as_ref())
@@ -243,13 +251,6 @@ impl LogWatcher {
             .map(|s| s.to_string())
     }
 
-    /// 从日志文件路径提取会话 ID
-    fn extract_session_id_from_log(path: &Path) -> Option<String> {
-        path.file_stem()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_string())
-    }
-
     /// 解析锁文件以发现会话
     async fn parse_lock_file_for_discovery(path: &Path) -> Option<DiscoveredSession> {
         // 简化实现，实际应该复用 SessionDiscovery 的逻辑
@@ -342,26 +343,16 @@ impl PollingWatcher {
             let mut last = self.last_modified.write().await;
             last.insert(path.to_path_buf(), modified);
 
-            if let Some(session_id) = Self::extract_session_id(path) {
-                let _ = self
-                    .event_sender
-                    .send(WatchEvent::LogChanged {
-                        session_id,
-                        path: path.to_path_buf(),
-                    })
-                    .await;
-            }
+            let _ = self
+                .event_sender
+                .send(WatchEvent::LogChanged {
+                    path: path.to_path_buf(),
+                })
+                .await;
         }
 
         Ok(())
     }
-
-    /// 提取会话 ID
-    fn extract_session_id(path: &Path) -> Option<String> {
-        path.file_stem()
-            .and_then(|s| s.to_str())
-            .map(|s| s.to_string())
-    }
 }
 
 /// 监控管理器
@@ -372,9 +363,9 @@ pub struct WatchManager {
 
 impl WatchManager {
     /// 创建并初始化监控管理器
-    pub async fn new() -> Result<Self> {
+    pub async fn new(discoveries: Vec<SessionDiscovery>) -> Result<Self> {
         let (tx, rx) = mpsc::channel(100);
-        let watcher = LogWatcher::new(tx)?;
+        let watcher = LogWatcher::new(tx, discoveries)?;
 
         Ok(Self {
             watcher,
@@ -415,4 +406,11 @@ impl WatchManager {
     ) {
         self.watcher.unwatch(log_path).await;
     }
+
+    /// 拆除全部监控
+    ///
+    /// 取消所有路径监控；WatchManager 被丢弃后事件通道随之关闭。
+    pub async fn shutdown(&mut self) {
+        self.watcher.unwatch_all().await;
+    }
 }
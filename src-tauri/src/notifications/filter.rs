@@ -0,0 +1,334 @@
+//! 通知过滤模块
+//!
+//! 根据通知设置决定哪些监控事件需要弹出桌面通知，
+//! 并对状态做最短持续时间过滤：状态在延迟期内再次变化则取消通知。
+//! 等待输入/阻塞的通知发出后，若状态一直未变，按设置的间隔再次提醒。
+
+use crate::models::{NotificationSettings, SessionStatus};
+use crate::monitor::MonitorEvent;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 通知类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// 等待输入
+    WaitingInput,
+    /// 执行阻塞
+    Blocked,
+    /// 会话完成或结束
+    Ended,
+}
+
+impl NotificationKind {
+    /// 通知标题中显示的文本
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            NotificationKind::WaitingInput => "等待输入",
+            NotificationKind::Blocked => "执行阻塞",
+            NotificationKind::Ended => "会话已结束",
+        }
+    }
+}
+
+/// 到期待发送的通知
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueNotification {
+    pub session_id: String,
+    pub kind: NotificationKind,
+    /// 第几次再次提醒（0 表示首次通知）
+    pub escalation: u32,
+}
+
+/// 等待到期的通知
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    kind: NotificationKind,
+    due: Instant,
+    escalation: u32,
+}
+
+/// 通知过滤器
+#[derive(Debug, Default)]
+pub struct NotificationFilter {
+    /// 通知总开关
+    enabled: bool,
+    /// 细分设置
+    settings: NotificationSettings,
+    /// 等待到期的通知（会话 ID -> 待发通知）
+    pending: HashMap<String, Pending>,
+}
+
+impl NotificationFilter {
+    /// 创建过滤器
+    pub fn new(enabled: bool, settings: NotificationSettings) -> Self {
+        Self {
+            enabled,
+            settings,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// 更新设置；关闭总开关时丢弃所有待发通知
+    pub fn update_settings(&mut self, enabled: bool, settings: NotificationSettings) {
+        if !enabled {
+            self.pending.clear();
+        }
+        self.enabled = enabled;
+        self.settings = settings;
+    }
+
+    /// 处理监控事件
+    pub fn on_event(&mut self, event: &MonitorEvent, now: Instant) {
+        match event {
+            MonitorEvent::StatusChanged {
+                session_id,
+                new_status,
+                ..
+            } => {
+                // 任何新状态都会取消该会话尚未到期的通知
+                self.pending.remove(session_id);
+                if let Some(kind) = self.kind_for_status(*new_status) {
+                    self.schedule(session_id, kind, now, self.min_duration(), 0);
+                }
+            }
+            MonitorEvent::SessionEnded { session_id } => {
+                self.pending.remove(session_id);
+                if self.is_kind_enabled(NotificationKind::Ended) {
+                    let delay = self.min_duration();
+                    self.schedule(session_id, NotificationKind::Ended, now, delay, 0);
+                }
+            }
+            MonitorEvent::SessionDiscovered { session } => {
+                // 会话重新出现，结束通知不再有意义
+                if let Some(Pending {
+                    kind: NotificationKind::Ended,
+                    ..
+                }) = self.pending.get(&session.id)
+                {
+                    self.pending.remove(&session.id);
+                }
+            }
+            MonitorEvent::Snapshot { .. }
+            | MonitorEvent::NewMessage { .. }
+            | MonitorEvent::Error { .. } => {}
+        }
+    }
+
+    /// 取出所有已到期的通知
+    ///
+    /// 等待输入/阻塞通知到期后，若启用了再次提醒，会安排下一次提醒。
+    pub fn take_due(&mut self, now: Instant) -> Vec<DueNotification> {
+        let due_ids: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.due <= now)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut due: Vec<(Instant, DueNotification)> = Vec::new();
+        for session_id in due_ids {
+            let Some(pending) = self.pending.remove(&session_id) else {
+                continue;
+            };
+
+            if let Some(interval) = self.escalation_interval(pending) {
+                self.schedule(
+                    &session_id,
+                    pending.kind,
+                    now,
+                    interval,
+                    pending.escalation + 1,
+                );
+            }
+
+            due.push((
+                pending.due,
+                DueNotification {
+                    session_id,
+                    kind: pending.kind,
+                    escalation: pending.escalation,
+                },
+            ));
+        }
+
+        due.sort_by_key(|(at, _)| *at);
+        due.into_iter().map(|(_, n)| n).collect()
+    }
+
+    /// 最早的到期时间
+    pub fn next_due(&self) -> Option<Instant> {
+        self.pending.values().map(|pending| pending.due).min()
+    }
+
+    /// 下一次再次提醒的间隔（不需要再提醒时返回 None）
+    fn escalation_interval(&self, pending: Pending) -> Option<Duration> {
+        if pending.kind == NotificationKind::Ended
+            || pending.escalation >= self.settings.max_escalations
+        {
+            return None;
+        }
+
+        self.settings
+            .escalate_after_mins
+            .filter(|mins| *mins > 0)
+            .map(|mins| Duration::from_secs(mins * 60))
+    }
+
+    /// 最短持续时间
+    fn min_duration(&self) -> Duration {
+        Duration::from_secs(self.settings.min_duration_secs)
+    }
+
+    /// 状态对应的通知类型（未启用时返回 None）
+    fn kind_for_status(&self, status: SessionStatus) -> Option<NotificationKind> {
+        let kind = match status {
+            SessionStatus::WaitingInput => NotificationKind::WaitingInput,
+            SessionStatus::Blocked => NotificationKind::Blocked,
+            SessionStatus::Completed => NotificationKind::Ended,
+            _ => return None,
+        };
+
+        self.is_kind_enabled(kind).then_some(kind)
+    }
+
+    /// 指定类型的通知是否启用
+    fn is_kind_enabled(&self, kind: NotificationKind) -> bool {
+        self.enabled
+            && match kind {
+                NotificationKind::WaitingInput => self.settings.on_waiting_input,
+                NotificationKind::Blocked => self.settings.on_blocked,
+                NotificationKind::Ended => self.settings.on_ended,
+            }
+    }
+
+    /// 安排通知在指定延迟后到期
+    fn schedule(
+        &mut self,
+        session_id: &str,
+        kind: NotificationKind,
+        now: Instant,
+        delay: Duration,
+        escalation: u32,
+    ) {
+        self.pending.insert(
+            session_id.to_string(),
+            Pending {
+                kind,
+                due: now + delay,
+                escalation,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changed(id: &str, new_status: SessionStatus) -> MonitorEvent {
+        MonitorEvent::StatusChanged {
+            session_id: id.to_string(),
+            old_status: SessionStatus::Running,
+            new_status,
+        }
+    }
+
+    fn filter(min_duration_secs: u64) -> NotificationFilter {
+        NotificationFilter::new(
+            true,
+            NotificationSettings {
+                on_waiting_input: true,
+                on_blocked: true,
+                on_ended: false,
+                min_duration_secs,
+                ..NotificationSettings::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_notifies_after_min_duration() {
+        let mut filter = filter(5);
+        let now = Instant::now();
+
+        filter.on_event(&changed("s1", SessionStatus::WaitingInput), now);
+        assert!(filter.take_due(now + Duration::from_secs(4)).is_empty());
+        assert_eq!(filter.next_due(), Some(now + Duration::from_secs(5)));
+
+        let due = filter.take_due(now + Duration::from_secs(5));
+        assert_eq!(
+            due,
+            vec![DueNotification {
+                session_id: "s1".to_string(),
+                kind: NotificationKind::WaitingInput,
+                escalation: 0,
+            }]
+        );
+        assert!(filter.next_due().is_none());
+    }
+
+    #[test]
+    fn test_status_change_within_min_duration_cancels() {
+        let mut filter = filter(5);
+        let now = Instant::now();
+
+        filter.on_event(&changed("s1", SessionStatus::WaitingInput), now);
+        filter.on_event(
+            &changed("s1", SessionStatus::Running),
+            now + Duration::from_secs(2),
+        );
+
+        assert!(filter.take_due(now + Duration::from_secs(10)).is_empty());
+    }
+
+    #[test]
+    fn test_respects_toggles() {
+        let mut filter = filter(0);
+        let now = Instant::now();
+
+        filter.on_event(&changed("s1", SessionStatus::Completed), now);
+        filter.on_event(
+            &MonitorEvent::SessionEnded {
+                session_id: "s2".to_string(),
+            },
+            now,
+        );
+        assert!(filter.take_due(now).is_empty());
+
+        filter.update_settings(false, NotificationSettings::default());
+        filter.on_event(&changed("s3", SessionStatus::Blocked), now);
+        assert!(filter.take_due(now).is_empty());
+    }
+
+    #[test]
+    fn test_escalates_while_status_unchanged() {
+        let mut filter = filter(0);
+        filter.update_settings(
+            true,
+            NotificationSettings {
+                min_duration_secs: 0,
+                escalate_after_mins: Some(10),
+                max_escalations: 1,
+                ..NotificationSettings::default()
+            },
+        );
+        let now = Instant::now();
+        let ten_mins = Duration::from_secs(600);
+
+        filter.on_event(&changed("s1", SessionStatus::WaitingInput), now);
+        assert_eq!(filter.take_due(now)[0].escalation, 0);
+        assert_eq!(filter.next_due(), Some(now + ten_mins));
+
+        let due = filter.take_due(now + ten_mins);
+        assert_eq!(due[0].escalation, 1);
+        // 已达到最多提醒次数
+        assert!(filter.next_due().is_none());
+
+        // 状态变化会取消尚未到期的再次提醒
+        filter.on_event(&changed("s2", SessionStatus::Blocked), now);
+        filter.take_due(now);
+        filter.on_event(&changed("s2", SessionStatus::Running), now);
+        assert!(filter.take_due(now + ten_mins).is_empty());
+    }
+}
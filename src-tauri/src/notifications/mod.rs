@@ -0,0 +1,216 @@
+//! 桌面通知模块
+//!
+//! 订阅监控事件总线，会话进入等待输入、阻塞或结束状态时弹出桌面通知。
+//!
+//! - `filter`: 按通知设置与最短持续时间过滤事件，安排再次提醒
+//! - `policy`: 免打扰时段、项目静音与会话暂停提醒
+//!
+//! 桌面通知本身不提供点击回调，点击通知会激活应用窗口；
+//! 因此在窗口获得焦点时，若刚刚发送过通知，则通知前端聚焦对应会话。
+
+pub mod filter;
+pub mod policy;
+
+use crate::models::{AppConfig, MessageRole, Session};
+use crate::monitor::discovery::{self, SessionDiscovery};
+use crate::monitor::event_bus::EventSubscription;
+use crate::monitor::status_detector::StatusDetector;
+use crate::monitor::{MonitorConfig, MonitorEvent};
+use filter::{DueNotification, NotificationFilter};
+use policy::Snoozes;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::RwLock;
+
+/// 通知正文最大字符数
+const BODY_MAX_CHARS: usize = 140;
+
+/// 通知发出后，窗口获得焦点视为点击通知的时间窗口
+const FOCUS_WINDOW: Duration = Duration::from_secs(30);
+
+/// 前端聚焦会话事件名
+pub const FOCUS_EVENT: &str = "session:focus";
+
+/// 最近一次通知对应的会话（用于点击通知后聚焦）
+#[derive(Default)]
+pub struct FocusTarget {
+    last: Mutex<Option<(String, Instant)>>,
+}
+
+impl FocusTarget {
+    /// 记录刚刚通知的会话
+    pub fn record(&self, session_id: &str) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some((session_id.to_string(), Instant::now()));
+        }
+    }
+
+    /// 取出时间窗口内最近通知的会话
+    pub fn take_recent(&self, within: Duration) -> Option<String> {
+        let mut last = self.last.lock().ok()?;
+        match last.take() {
+            Some((session_id, at)) if at.elapsed() <= within => Some(session_id),
+            _ => None,
+        }
+    }
+}
+
+/// 窗口获得焦点时调用：若刚刚发送过通知，通知前端聚焦该会话
+pub fn handle_window_focused(app: &AppHandle) {
+    let Some(target) = app.try_state::<FocusTarget>() else {
+        return;
+    };
+
+    if let Some(session_id) = target.take_recent(FOCUS_WINDOW) {
+        let payload = serde_json::json!({ "sessionId": session_id });
+        if let Err(e) = app.emit(FOCUS_EVENT, payload) {
+            tracing::warn!("推送会话聚焦事件失败: {}", e);
+        }
+    }
+}
+
+/// 启动桌面通知任务
+pub fn spawn_notifier(
+    app: AppHandle,
+    mut events: EventSubscription,
+    config: Arc<RwLock<AppConfig>>,
+    snoozes: Arc<RwLock<Snoozes>>,
+) {
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("桌面通知任务已启动");
+
+        let mut filter = {
+            let config = config.read().await;
+            NotificationFilter::new(
+                config.settings.notification_enabled,
+                config.settings.notifications.clone(),
+            )
+        };
+        // 通知内容需要的会话信息（会话结束后快照中已不存在）
+        let mut known: HashMap<String, Session> = HashMap::new();
+
+        loop {
+            tokio::select! {
+                event = events.recv() => {
+                    let Some(event) = event else { break };
+
+                    match &event {
+                        MonitorEvent::Snapshot { sessions } => {
+                            for session in sessions {
+                                known.insert(session.id.clone(), session.clone());
+                            }
+                        }
+                        MonitorEvent::SessionDiscovered { session } => {
+                            known.insert(session.id.clone(), session.as_ref().clone());
+                        }
+                        _ => {}
+                    }
+
+                    {
+                        let config = config.read().await;
+                        filter.update_settings(
+                            config.settings.notification_enabled,
+                            config.settings.notifications.clone(),
+                        );
+                    }
+                    filter.on_event(&event, Instant::now());
+                }
+                _ = sleep_until(filter.next_due()) => {}
+            }
+
+            for due in filter.take_due(Instant::now()) {
+                let Some(session) = known.get(&due.session_id).cloned() else {
+                    continue;
+                };
+                let (settings, roots) = {
+                    let config = config.read().await;
+                    (
+                        config.settings.notifications.clone(),
+                        MonitorConfig::from_settings(&config.settings).roots,
+                    )
+                };
+                let suppressed = {
+                    let snoozes = snoozes.read().await;
+                    policy::is_suppressed(&settings, &session, &snoozes, chrono::Local::now())
+                };
+                if suppressed {
+                    tracing::debug!("通知被策略屏蔽: {}", due.session_id);
+                    continue;
+                }
+                show_notification(&app, &session, &due, &roots).await;
+            }
+        }
+
+        tracing::info!("桌面通知任务已停止");
+    });
+}
+
+/// 等待到指定时间；无到期时间时永久挂起
+async fn sleep_until(due: Option<Instant>) {
+    match due {
+        Some(due) => tokio::time::sleep_until(due.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 发送一条会话通知
+async fn show_notification(
+    app: &AppHandle,
+    session: &Session,
+    due: &DueNotification,
+    roots: &[std::path::PathBuf],
+) {
+    let title = if due.escalation > 0 {
+        format!("{} · 仍在{}", session.project_name, due.kind.display_name())
+    } else {
+        format!("{} · {}", session.project_name, due.kind.display_name())
+    };
+
+    let project_path = session.project_path.clone();
+    let roots = roots.to_vec();
+    let last_text = tokio::task::spawn_blocking(move || last_assistant_text(&roots, &project_path))
+        .await
+        .ok()
+        .flatten();
+    let body = truncate(
+        last_text.as_deref().unwrap_or(&session.title),
+        BODY_MAX_CHARS,
+    );
+
+    match app.notification().builder().title(title).body(body).show() {
+        Ok(()) => {
+            if let Some(target) = app.try_state::<FocusTarget>() {
+                target.record(&due.session_id);
+            }
+        }
+        Err(e) => tracing::warn!("发送桌面通知失败 {}: {}", due.session_id, e),
+    }
+}
+
+/// 读取项目最新日志中最后一条助手文本
+fn last_assistant_text(roots: &[std::path::PathBuf], project_path: &str) -> Option<String> {
+    let discoveries = SessionDiscovery::for_roots(roots).ok()?;
+    let log_path = discovery::find_latest_log(&discoveries, project_path)?;
+    let messages = StatusDetector::extract_recent_messages(&log_path, 10).ok()?;
+
+    messages
+        .into_iter()
+        .rev()
+        .find(|m| m.role == MessageRole::Assistant && !m.content.trim().is_empty())
+        .map(|m| m.content)
+}
+
+/// 按字符截断并压缩空白
+fn truncate(text: &str, max_chars: usize) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= max_chars {
+        return collapsed;
+    }
+
+    let mut truncated: String = collapsed.chars().take(max_chars).collect();
+    truncated.push('…');
+    truncated
+}
@@ -0,0 +1,125 @@
+//! 通知策略模块
+//!
+//! 在通知到期发送前做最后判断：免打扰时段、项目静音、会话暂停提醒。
+
+use crate::models::{NotificationSettings, QuietHours, Session};
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use std::collections::HashMap;
+
+/// 会话暂停提醒表（会话 ID -> 截止时间）
+pub type Snoozes = HashMap<String, DateTime<Utc>>;
+
+/// 通知是否应被策略屏蔽
+pub fn is_suppressed(
+    settings: &NotificationSettings,
+    session: &Session,
+    snoozes: &Snoozes,
+    now: DateTime<Local>,
+) -> bool {
+    if snoozes
+        .get(&session.id)
+        .map(|until| *until > now.with_timezone(&Utc))
+        .unwrap_or(false)
+    {
+        return true;
+    }
+
+    if is_project_muted(&settings.muted_projects, session) {
+        return true;
+    }
+
+    in_quiet_hours(&settings.quiet_hours, now.time())
+}
+
+/// 项目是否被静音（按项目路径或项目名匹配）
+pub fn is_project_muted(muted_projects: &[String], session: &Session) -> bool {
+    let project_path = session.project_path.trim_end_matches('/');
+    muted_projects.iter().any(|muted| {
+        let muted = muted.trim_end_matches('/');
+        muted == project_path || muted == session.project_name
+    })
+}
+
+/// 指定时间是否落在任一免打扰时段内
+pub fn in_quiet_hours(quiet_hours: &[QuietHours], time: NaiveTime) -> bool {
+    quiet_hours.iter().any(|window| {
+        let (Some(start), Some(end)) = (parse_time(&window.start), parse_time(&window.end)) else {
+            tracing::warn!("免打扰时段格式无效: {} - {}", window.start, window.end);
+            return false;
+        };
+
+        if start <= end {
+            start <= time && time < end
+        } else {
+            // 跨越午夜，如 22:00 - 08:00
+            time >= start || time < end
+        }
+    })
+}
+
+/// 解析 "HH:MM" 格式的时间
+fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// 移除已过期的暂停提醒
+pub fn prune_expired(snoozes: &mut Snoozes, now: DateTime<Utc>) {
+    snoozes.retain(|_, until| *until > now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    fn window(start: &str, end: &str) -> QuietHours {
+        QuietHours {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_quiet_hours_windows() {
+        let overnight = [window("22:00", "08:00")];
+        assert!(in_quiet_hours(&overnight, time(23, 30)));
+        assert!(in_quiet_hours(&overnight, time(7, 59)));
+        assert!(!in_quiet_hours(&overnight, time(8, 0)));
+        assert!(!in_quiet_hours(&overnight, time(12, 0)));
+
+        let lunch = [window("12:00", "13:00"), window("bad", "13:00")];
+        assert!(in_quiet_hours(&lunch, time(12, 30)));
+        assert!(!in_quiet_hours(&lunch, time(13, 0)));
+    }
+
+    #[test]
+    fn test_mute_and_snooze() {
+        let session = Session::new("会话", "demo", "/path/to/demo");
+        let now = Local.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+
+        let mut settings = NotificationSettings::default();
+        assert!(!is_suppressed(&settings, &session, &Snoozes::new(), now));
+
+        settings.muted_projects = vec!["/path/to/demo/".to_string()];
+        assert!(is_suppressed(&settings, &session, &Snoozes::new(), now));
+
+        settings.muted_projects.clear();
+        let mut snoozes = Snoozes::new();
+        let now_utc = now.with_timezone(&Utc);
+        snoozes.insert(session.id.clone(), now_utc + Duration::minutes(30));
+        assert!(is_suppressed(&settings, &session, &snoozes, now));
+        assert!(!is_suppressed(
+            &settings,
+            &session,
+            &snoozes,
+            now + Duration::hours(1)
+        ));
+
+        prune_expired(&mut snoozes, now_utc + Duration::hours(1));
+        assert!(snoozes.is_empty());
+    }
+}
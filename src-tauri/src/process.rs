@@ -0,0 +1,196 @@
+//! 进程控制
+//!
+//! 向会话进程发送信号：中断（SIGINT）与终止（SIGTERM，超时后 SIGKILL）。
+//! 发送前核对 PID 仍属于 Claude Code 进程，避免 PID 被复用后误杀其他进程。
+
+use crate::error::{AppError, Result};
+use crate::monitor::discovery::SessionDiscovery;
+use nix::sys::signal::{kill, Signal};
+use nix::unistd::Pid;
+use serde::Serialize;
+use std::time::Duration;
+
+/// 轮询进程状态的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// SIGKILL 后等待进程消失的时间
+const KILL_WAIT: Duration = Duration::from_secs(2);
+
+/// npm 安装的 Claude Code 入口所在包
+const AGENT_PACKAGE: &str = "@anthropic-ai/claude-code";
+
+/// 终止结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TerminateOutcome {
+    /// 收到 SIGTERM 后自行退出
+    Terminated,
+    /// 超时未退出，已强制结束
+    Killed,
+}
+
+/// 进程是否为 Claude Code
+pub fn is_agent_process(pid: u32) -> bool {
+    SessionDiscovery::process_exists(pid)
+        && command_line(pid)
+            .map(|argv| is_agent_command(&argv))
+            .unwrap_or(false)
+}
+
+/// 命令行是否为 Claude Code：`claude` 本身，或由 node 运行的 claude 入口
+fn is_agent_command(argv: &[String]) -> bool {
+    let Some(program) = argv.first() else {
+        return false;
+    };
+    match file_name(program) {
+        "claude" => true,
+        "node" => argv[1..]
+            .iter()
+            .find(|arg| !arg.starts_with('-'))
+            .map(|script| file_name(script) == "claude" || script.contains(AGENT_PACKAGE))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// 路径的最后一段
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// 进程命令行参数
+#[cfg(target_os = "linux")]
+fn command_line(pid: u32) -> Option<Vec<String>> {
+    let raw = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let argv: Vec<String> = raw
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect();
+    (!argv.is_empty()).then_some(argv)
+}
+
+/// 进程命令行参数（ps 输出已合并为一行，按空白拆分）
+#[cfg(not(target_os = "linux"))]
+fn command_line(pid: u32) -> Option<Vec<String>> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "command=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let argv: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .map(str::to_string)
+        .collect();
+    (!argv.is_empty()).then_some(argv)
+}
+
+/// 向进程发送信号
+pub fn send_signal(pid: u32, signal: Signal) -> Result<()> {
+    if pid == 0 || pid > i32::MAX as u32 {
+        return Err(AppError::InvalidInput(format!("无效的 PID: {}", pid)));
+    }
+    kill(Pid::from_raw(pid as i32), signal)
+        .map_err(|e| AppError::ProcessError(format!("向进程 {} 发送 {} 失败: {}", pid, signal, e)))
+}
+
+/// 等待条件成立，超时返回 false
+pub async fn wait_until<F>(timeout: Duration, mut condition: F) -> bool
+where
+    F: FnMut() -> bool,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if condition() {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// 终止进程：先发送 SIGTERM，`grace` 内未退出则发送 SIGKILL
+pub async fn terminate(pid: u32, grace: Duration) -> Result<TerminateOutcome> {
+    send_signal(pid, Signal::SIGTERM)?;
+    if wait_until(grace, || !SessionDiscovery::process_exists(pid)).await {
+        return Ok(TerminateOutcome::Terminated);
+    }
+
+    tracing::warn!("进程 {} 未响应 SIGTERM，强制结束", pid);
+    // 进程可能恰好在此刻退出
+    if let Err(e) = send_signal(pid, Signal::SIGKILL) {
+        if !SessionDiscovery::process_exists(pid) {
+            return Ok(TerminateOutcome::Terminated);
+        }
+        return Err(e);
+    }
+    if wait_until(KILL_WAIT, || !SessionDiscovery::process_exists(pid)).await {
+        Ok(TerminateOutcome::Killed)
+    } else {
+        Err(AppError::ProcessError(format!("进程 {} 无法结束", pid)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    /// 启动子进程并在后台回收，避免退出后残留僵尸进程
+    fn spawn_reaped(script: &str) -> u32 {
+        let mut child = Command::new("sh")
+            .args(["-c", script])
+            .stdout(Stdio::null())
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        std::thread::spawn(move || {
+            let _ = child.wait();
+        });
+        pid
+    }
+
+    #[tokio::test]
+    async fn test_terminate_escalates_to_kill() {
+        let polite = spawn_reaped("exec sleep 30");
+        assert_eq!(
+            terminate(polite, Duration::from_secs(2)).await.unwrap(),
+            TerminateOutcome::Terminated
+        );
+
+        // 忽略 SIGTERM 的进程在宽限期后被强制结束
+        let stubborn = spawn_reaped("trap '' TERM; while true; do sleep 0.1; done");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(
+            terminate(stubborn, Duration::from_millis(300))
+                .await
+                .unwrap(),
+            TerminateOutcome::Killed
+        );
+        assert!(!SessionDiscovery::process_exists(stubborn));
+    }
+
+    #[test]
+    fn test_agent_process_check() {
+        // 测试进程本身不是 Claude Code
+        assert!(!is_agent_process(std::process::id()));
+        assert!(send_signal(0, Signal::SIGINT).is_err());
+
+        let argv = |cmd: &str| cmd.split(' ').map(str::to_string).collect::<Vec<_>>();
+        assert!(is_agent_command(&argv("claude")));
+        assert!(is_agent_command(&argv(
+            "/usr/local/bin/claude --resume 3f2a-01"
+        )));
+        assert!(is_agent_command(&argv("node /home/dev/.local/bin/claude")));
+        assert!(is_agent_command(&argv(
+            "/usr/bin/node --no-warnings /usr/lib/node_modules/@anthropic-ai/claude-code/cli.js"
+        )));
+
+        // 命令行里只是出现了 claude 字样
+        assert!(!is_agent_command(&argv("vim /work/claude/CLAUDE.md")));
+        assert!(!is_agent_command(&argv("/opt/claude-tools/bin/server")));
+        assert!(!is_agent_command(&argv("node server.js --name claude")));
+        assert!(!is_agent_command(&[]));
+    }
+}
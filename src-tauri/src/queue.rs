@@ -0,0 +1,511 @@
+//! 提示队列
+//!
+//! 每个会话维护一个待发送的提示队列，持久化于存储中。
+//! 应用启动的会话进入等待输入状态（`MonitorEvent::StatusChanged`）时自动发送队首提示，
+//! 队列暂停时保留提示但不发送。发送后直到监控看到会话开始处理前，不再发送下一条；
+//! 监控错过状态变化时（处理快于刷新间隔），超时后重新检查队列。
+
+use crate::error::{AppError, Result};
+use crate::models::SessionStatus;
+use crate::monitor::event_bus::EventSubscription;
+use crate::monitor::handle::MonitorHandle;
+use crate::monitor::MonitorEvent;
+use crate::storage::Storage;
+use crate::wrapper::ProcessWrapperManager;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// 发送后等待会话开始处理的最长时间，超时视为监控错过了状态变化
+const PICKUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 全部会话的提示队列（会话 ID -> 队列）
+pub type PromptQueues = HashMap<String, PromptQueue>;
+
+/// 排队的提示
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedPrompt {
+    pub id: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 单个会话的提示队列
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PromptQueue {
+    /// 暂停时不自动发送
+    pub paused: bool,
+    pub prompts: Vec<QueuedPrompt>,
+}
+
+impl PromptQueue {
+    /// 追加提示到队尾
+    pub fn enqueue(&mut self, content: &str) -> Result<QueuedPrompt> {
+        let content = content.trim();
+        if content.is_empty() {
+            return Err(AppError::InvalidInput("提示内容为空".to_string()));
+        }
+
+        let prompt = QueuedPrompt {
+            id: uuid::Uuid::new_v4().to_string(),
+            content: content.to_string(),
+            created_at: Utc::now(),
+        };
+        self.prompts.push(prompt.clone());
+        Ok(prompt)
+    }
+
+    /// 移除提示
+    pub fn remove(&mut self, prompt_id: &str) -> Result<QueuedPrompt> {
+        let index = self
+            .prompts
+            .iter()
+            .position(|p| p.id == prompt_id)
+            .ok_or_else(|| AppError::InvalidInput(format!("队列中没有该提示: {}", prompt_id)))?;
+        Ok(self.prompts.remove(index))
+    }
+
+    /// 按给定的 ID 顺序重排，ID 必须与队列中的提示一一对应
+    pub fn reorder(&mut self, prompt_ids: &[String]) -> Result<()> {
+        let current: HashSet<&str> = self.prompts.iter().map(|p| p.id.as_str()).collect();
+        let requested: HashSet<&str> = prompt_ids.iter().map(String::as_str).collect();
+        if requested.len() != prompt_ids.len() || current != requested {
+            return Err(AppError::InvalidInput(
+                "新顺序必须恰好包含队列中的全部提示".to_string(),
+            ));
+        }
+
+        let mut prompts = std::mem::take(&mut self.prompts);
+        for id in prompt_ids {
+            if let Some(index) = prompts.iter().position(|p| &p.id == id) {
+                self.prompts.push(prompts.swap_remove(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// 取出下一条待发送的提示（暂停时不取）
+    pub fn take_next(&mut self) -> Option<QueuedPrompt> {
+        if self.paused || self.prompts.is_empty() {
+            return None;
+        }
+        Some(self.prompts.remove(0))
+    }
+
+    /// 发送失败的提示放回队首
+    pub fn requeue_front(&mut self, prompt: QueuedPrompt) {
+        self.prompts.insert(0, prompt);
+    }
+
+    /// 是否没有需要保存的内容
+    pub fn is_empty(&self) -> bool {
+        !self.paused && self.prompts.is_empty()
+    }
+}
+
+/// 已发送提示、等待会话开始处理的会话
+///
+/// 送达确认后监控快照可能仍停留在等待输入，期间重复的事件或手动发送不应再发下一条。
+/// 会话转入其他状态后解除；监控错过状态变化时超时后解除。
+#[derive(Debug)]
+struct PickupTracker {
+    sent_at: HashMap<String, Instant>,
+    timeout: Duration,
+}
+
+impl Default for PickupTracker {
+    fn default() -> Self {
+        Self {
+            sent_at: HashMap::new(),
+            timeout: PICKUP_TIMEOUT,
+        }
+    }
+}
+
+impl PickupTracker {
+    fn mark(&mut self, session_id: &str, now: Instant) {
+        self.sent_at.insert(session_id.to_string(), now);
+    }
+
+    fn clear(&mut self, session_id: &str) {
+        self.sent_at.remove(session_id);
+    }
+
+    fn is_pending(&self, session_id: &str, now: Instant) -> bool {
+        self.sent_at
+            .get(session_id)
+            .is_some_and(|sent_at| now.duration_since(*sent_at) < self.timeout)
+    }
+}
+
+/// 提示队列管理器
+///
+/// 可克隆共享，克隆体共用同一组队列。
+#[derive(Clone)]
+pub struct PromptQueueManager {
+    queues: Arc<RwLock<PromptQueues>>,
+    storage: Arc<Storage>,
+    monitor: MonitorHandle,
+    wrapper: ProcessWrapperManager,
+    /// 正在发送提示的会话，避免同一会话并发出队
+    sending: Arc<Mutex<HashSet<String>>>,
+    /// 已发送提示、尚未开始处理的会话
+    pickup: Arc<Mutex<PickupTracker>>,
+}
+
+impl PromptQueueManager {
+    pub fn new(
+        queues: PromptQueues,
+        storage: Arc<Storage>,
+        monitor: MonitorHandle,
+        wrapper: ProcessWrapperManager,
+    ) -> Self {
+        Self {
+            queues: Arc::new(RwLock::new(queues)),
+            storage,
+            monitor,
+            wrapper,
+            sending: Arc::new(Mutex::new(HashSet::new())),
+            pickup: Arc::new(Mutex::new(PickupTracker::default())),
+        }
+    }
+
+    /// 会话的提示队列
+    pub async fn get(&self, session_id: &str) -> PromptQueue {
+        self.queues
+            .read()
+            .await
+            .get(session_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// 修改会话的提示队列并保存，返回修改后的队列
+    ///
+    /// 修改后若会话正在等待输入，立即尝试发送队首提示。
+    pub async fn update<T, F>(&self, session_id: &str, f: F) -> Result<(T, PromptQueue)>
+    where
+        F: FnOnce(&mut PromptQueue) -> Result<T>,
+    {
+        let result = self.modify(session_id, f).await?;
+
+        let manager = self.clone();
+        let session_id = session_id.to_string();
+        tokio::spawn(async move { manager.deliver_logged(&session_id).await });
+
+        Ok(result)
+    }
+
+    /// 修改并保存队列，不触发发送
+    async fn modify<T, F>(&self, session_id: &str, f: F) -> Result<(T, PromptQueue)>
+    where
+        F: FnOnce(&mut PromptQueue) -> Result<T>,
+    {
+        let mut queues = self.queues.write().await;
+        let mut queue = queues.get(session_id).cloned().unwrap_or_default();
+        let value = f(&mut queue)?;
+
+        if queue.is_empty() {
+            queues.remove(session_id);
+        } else {
+            queues.insert(session_id.to_string(), queue.clone());
+        }
+        self.storage.save_prompt_queues(&queues).await?;
+        Ok((value, queue))
+    }
+
+    /// 会话处于等待输入且由应用启动时，发送队首提示并返回它
+    pub async fn deliver_next(&self, session_id: &str) -> Result<Option<QueuedPrompt>> {
+        {
+            let Ok(mut sending) = self.sending.lock() else {
+                return Err(AppError::Internal("提示队列锁已损坏".to_string()));
+            };
+            if !sending.insert(session_id.to_string()) {
+                return Ok(None);
+            }
+        }
+
+        let result = self.deliver(session_id).await;
+
+        if let Ok(mut sending) = self.sending.lock() {
+            sending.remove(session_id);
+        }
+        result
+    }
+
+    async fn deliver(&self, session_id: &str) -> Result<Option<QueuedPrompt>> {
+        let picking_up = self
+            .pickup
+            .lock()
+            .map(|pickup| pickup.is_pending(session_id, Instant::now()))
+            .unwrap_or(true);
+        if picking_up {
+            return Ok(None);
+        }
+
+        let waiting = self
+            .monitor
+            .get_session(session_id)
+            .map(|s| s.status == SessionStatus::WaitingInput)
+            .unwrap_or(false);
+        if !waiting || !self.wrapper.can_send_input(session_id).await {
+            return Ok(None);
+        }
+
+        let (prompt, _) = self
+            .modify(session_id, |queue| Ok(queue.take_next()))
+            .await?;
+        let Some(prompt) = prompt else {
+            return Ok(None);
+        };
+
+        tracing::info!("发送排队提示 {}: {}", session_id, prompt.id);
+        // 写入终端前登记，会话随后的状态变化一定晚于登记
+        self.set_pickup(session_id, true);
+        if let Err(e) = self.wrapper.send_message(session_id, &prompt.content).await {
+            self.set_pickup(session_id, false);
+            let failed = prompt.clone();
+            self.modify(session_id, move |queue| {
+                queue.requeue_front(failed);
+                Ok(())
+            })
+            .await?;
+            return Err(e);
+        }
+        Ok(Some(prompt))
+    }
+
+    /// 登记或解除会话的待处理状态
+    fn set_pickup(&self, session_id: &str, pending: bool) {
+        if let Ok(mut pickup) = self.pickup.lock() {
+            if pending {
+                pickup.mark(session_id, Instant::now());
+            } else {
+                pickup.clear(session_id);
+            }
+        }
+    }
+
+    async fn deliver_logged(&self, session_id: &str) {
+        match self.deliver_next(session_id).await {
+            Ok(Some(_)) => self.schedule_recheck(session_id),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("发送排队提示失败 {}: {}", session_id, e),
+        }
+    }
+
+    /// 等待超时后重新尝试发送
+    ///
+    /// 会话处理快于监控刷新时一直停留在等待输入，不会再有状态变化事件触发发送。
+    fn schedule_recheck(&self, session_id: &str) {
+        let timeout = self
+            .pickup
+            .lock()
+            .map(|pickup| pickup.timeout)
+            .unwrap_or(PICKUP_TIMEOUT);
+        let manager = self.clone();
+        let session_id = session_id.to_string();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+            manager.deliver_logged(&session_id).await;
+        });
+    }
+
+    /// 启动队列分发任务：会话进入等待输入时发送队首提示
+    pub fn spawn_dispatcher(&self, mut events: EventSubscription) {
+        let manager = self.clone();
+
+        tauri::async_runtime::spawn(async move {
+            tracing::info!("提示队列分发任务已启动");
+
+            while let Some(event) = events.recv().await {
+                match event {
+                    MonitorEvent::StatusChanged {
+                        session_id,
+                        new_status: SessionStatus::WaitingInput,
+                        ..
+                    } => {
+                        let manager = manager.clone();
+                        tokio::spawn(async move { manager.deliver_logged(&session_id).await });
+                    }
+                    // 转入其他状态说明会话已开始处理上一条提示
+                    MonitorEvent::StatusChanged { session_id, .. }
+                    | MonitorEvent::SessionEnded { session_id } => {
+                        manager.set_pickup(&session_id, false);
+                    }
+                    _ => {}
+                }
+            }
+
+            tracing::info!("提示队列分发任务已停止");
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_enqueue_reorder_remove() {
+        let mut queue = PromptQueue::default();
+        let first = queue.enqueue("运行测试").unwrap();
+        let second = queue.enqueue("  更新文档  ").unwrap();
+        let third = queue.enqueue("提交").unwrap();
+        assert_eq!(second.content, "更新文档");
+        assert!(queue.enqueue("   ").is_err());
+
+        queue
+            .reorder(&[third.id.clone(), first.id.clone(), second.id.clone()])
+            .unwrap();
+        assert_eq!(queue.prompts[0].id, third.id);
+        // 缺少或重复的 ID 被拒绝，队列保持不变
+        assert!(queue
+            .reorder(&[third.id.clone(), first.id.clone()])
+            .is_err());
+        assert!(queue
+            .reorder(&[third.id.clone(), third.id.clone(), first.id.clone()])
+            .is_err());
+        assert_eq!(queue.prompts.len(), 3);
+
+        queue.remove(&first.id).unwrap();
+        assert!(queue.remove(&first.id).is_err());
+
+        queue.paused = true;
+        assert!(queue.take_next().is_none());
+        queue.paused = false;
+        let next = queue.take_next().unwrap();
+        assert_eq!(next.id, third.id);
+        queue.requeue_front(next);
+        assert_eq!(queue.prompts[0].id, third.id);
+    }
+
+    #[test]
+    fn test_pickup_blocks_until_cleared_or_expired() {
+        let mut pickup = PickupTracker::default();
+        let sent_at = Instant::now();
+        assert!(!pickup.is_pending("session-1", sent_at));
+
+        pickup.mark("session-1", sent_at);
+        assert!(pickup.is_pending("session-1", sent_at + Duration::from_secs(1)));
+        assert!(!pickup.is_pending("session-2", sent_at));
+        assert!(!pickup.is_pending("session-1", sent_at + PICKUP_TIMEOUT));
+
+        pickup.clear("session-1");
+        assert!(!pickup.is_pending("session-1", sent_at));
+    }
+
+    #[tokio::test]
+    async fn test_recheck_delivers_when_session_stays_waiting() {
+        use crate::monitor::discovery;
+        use crate::monitor::{MonitorConfig, SessionMonitor};
+        use crate::wrapper::StartOptions;
+        use std::os::unix::fs::PermissionsExt;
+
+        let claude_root = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let data = TempDir::new().unwrap();
+        let log_path = claude_root
+            .path()
+            .join("projects")
+            .join(discovery::encode_project_path(project.path()))
+            .join("session.jsonl");
+        std::fs::create_dir_all(log_path.parent().unwrap()).unwrap();
+        std::fs::write(&log_path, "").unwrap();
+
+        // 每条提示的用户消息与助手回复一次写入，监控看不到会话开始处理
+        let agent = project.path().join("fake-claude.sh");
+        std::fs::write(
+            &agent,
+            concat!(
+                "#!/bin/sh\n",
+                "reply='{\"type\":\"assistant\",\"timestamp\":\"2026-01-01T00:00:00Z\",\"message\":{\"role\":\"assistant\",\"content\":[{\"type\":\"text\",\"text\":\"要继续吗？\"}]}}'\n",
+                "printf '%s\\n' \"$reply\" >> \"$FAKE_LOG\"\n",
+                "while IFS= read -r line; do\n",
+                "  printf '{\"type\":\"user\",\"timestamp\":\"2026-01-01T00:00:00Z\",\"message\":{\"role\":\"user\",\"content\":\"%s\"}}\\n%s\\n' \"$line\" \"$reply\" >> \"$FAKE_LOG\"\n",
+                "done\n",
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&agent, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let monitor = SessionMonitor::new(MonitorConfig {
+            refresh_interval: Duration::from_secs(60),
+            roots: vec![claude_root.path().to_path_buf()],
+        })
+        .unwrap()
+        .spawn();
+        let wrapper = ProcessWrapperManager::with_program(monitor.clone(), agent.to_string_lossy());
+        let storage = Arc::new(Storage::open(data.path().to_path_buf()).await.unwrap());
+        let manager = PromptQueueManager::new(
+            PromptQueues::new(),
+            storage,
+            monitor.clone(),
+            wrapper.clone(),
+        );
+        manager.pickup.lock().unwrap().timeout = Duration::from_millis(500);
+
+        let session_id = crate::monitor::session_id_for_path(project.path());
+        manager
+            .modify(&session_id, |queue| {
+                queue.enqueue("第一条")?;
+                queue.enqueue("第二条")
+            })
+            .await
+            .unwrap();
+        manager.spawn_dispatcher(monitor.subscribe());
+
+        let options = StartOptions {
+            env: HashMap::from([(
+                "FAKE_LOG".to_string(),
+                log_path.to_string_lossy().to_string(),
+            )]),
+            ..StartOptions::default()
+        };
+        let session = wrapper.start(project.path(), None, options).await.unwrap();
+        assert_eq!(session.id, session_id);
+
+        tokio::time::timeout(Duration::from_secs(20), async {
+            while !manager.get(&session_id).await.prompts.is_empty()
+                || !std::fs::read_to_string(&log_path)
+                    .unwrap()
+                    .contains("第二条")
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("会话一直等待输入时，超时后应发送下一条提示");
+        assert_eq!(
+            monitor.get_session(&session_id).unwrap().status,
+            SessionStatus::WaitingInput
+        );
+
+        wrapper.get(&session_id).await.unwrap().terminate().unwrap();
+        monitor.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_queues_persist() {
+        let dir = TempDir::new().unwrap();
+        let storage = Storage::open(dir.path().to_path_buf()).await.unwrap();
+
+        let mut queues = PromptQueues::new();
+        let mut queue = PromptQueue {
+            paused: true,
+            ..PromptQueue::default()
+        };
+        queue.enqueue("运行测试").unwrap();
+        queues.insert("session-1".to_string(), queue.clone());
+        storage.save_prompt_queues(&queues).await.unwrap();
+
+        let loaded = storage.load_prompt_queues().await.unwrap();
+        assert_eq!(loaded.get("session-1"), Some(&queue));
+    }
+}
@@ -0,0 +1,486 @@
+//! 会话保留策略
+//!
+//! 按配置周期执行以下规则，每次执行的结果记录在内存中的报告日志里：
+//! - 日志超过指定小时数未更新的会话自动标记完成（会话之后重新活跃时标记失效）
+//! - 完成超过指定天数的会话自动归档
+//!
+//! 以上两条规则默认关闭，且跳过监控快照中仍在运行的会话。
+//! - 只保留最近 `max_session_history` 个会话的详情、消息与状态变化记录
+//! - 可选：把长时间未更新的 Claude Code 日志压缩备份到应用数据目录，原日志保持不变
+
+use crate::error::Result;
+use crate::models::{Session, SessionStatus, Settings};
+use crate::monitor::discovery;
+use crate::search;
+use crate::state::AppState;
+use crate::storage::Storage;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// 报告日志保留条数
+const LOG_CAPACITY: usize = 50;
+
+/// 启动后首次执行前的等待时间，先让监控与历史记录完成首次写入
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+
+/// 一次保留策略执行的结果
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionReport {
+    pub ran_at: DateTime<Utc>,
+    /// 因空闲被标记完成的会话
+    pub marked_completed: Vec<String>,
+    /// 被自动归档的会话
+    pub archived: Vec<String>,
+    /// 超出保留数量、详情被清理的会话
+    pub pruned_details: Vec<String>,
+    /// 本次新压缩备份的日志路径
+    pub compressed_transcripts: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+/// 保留策略引擎
+///
+/// 可克隆共享，克隆体共用同一个报告日志。
+#[derive(Clone)]
+pub struct RetentionEngine {
+    storage: Arc<Storage>,
+    log: Arc<Mutex<VecDeque<RetentionReport>>>,
+}
+
+impl RetentionEngine {
+    pub fn new(storage: Arc<Storage>) -> Self {
+        Self {
+            storage,
+            log: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// 获取执行报告（最新的在前）
+    pub fn reports(&self) -> Vec<RetentionReport> {
+        let Ok(log) = self.log.lock() else {
+            return Vec::new();
+        };
+        log.iter().rev().cloned().collect()
+    }
+
+    /// 执行一次所有规则并记录报告
+    ///
+    /// `live` 为监控中的会话，不会被标记完成或归档；
+    /// `live_projects` 为仍有进程运行的项目路径，其日志不会被压缩。
+    /// 单条规则失败时记录错误并继续执行其余规则。
+    pub async fn run(
+        &self,
+        settings: &Settings,
+        projects_dirs: &[PathBuf],
+        live: &HashSet<String>,
+        live_projects: &HashSet<PathBuf>,
+        now: DateTime<Utc>,
+    ) -> RetentionReport {
+        let policy = &settings.retention;
+        let mut report = RetentionReport {
+            ran_at: now,
+            ..RetentionReport::default()
+        };
+
+        match self.storage.load_session_index().await {
+            Ok(sessions) => {
+                if let Some(hours) = policy.auto_complete_after_hours {
+                    let cutoff = now - ChronoDuration::hours(hours as i64);
+                    let candidates: Vec<Session> = sessions
+                        .iter()
+                        .filter(|s| !live.contains(&s.id))
+                        .cloned()
+                        .collect();
+                    let projects_dirs = projects_dirs.to_vec();
+                    let idle = tokio::task::spawn_blocking(move || {
+                        idle_sessions(&candidates, &projects_dirs, cutoff)
+                    })
+                    .await
+                    .unwrap_or_else(|e| {
+                        report.errors.push(format!("检查会话活动失败: {}", e));
+                        Vec::new()
+                    });
+                    for id in idle {
+                        let result = self
+                            .storage
+                            .modify_session(&id, move |session| {
+                                session.status = SessionStatus::Completed;
+                                session.marked_completed_at = Some(now);
+                            })
+                            .await;
+                        match result {
+                            Ok(_) => report.marked_completed.push(id),
+                            Err(e) => report.errors.push(format!("标记完成失败 {}: {}", id, e)),
+                        }
+                    }
+                }
+
+                if let Some(days) = policy.auto_archive_after_days {
+                    let cutoff = now - ChronoDuration::days(days as i64);
+                    // 重新读取，包含刚被标记完成的会话
+                    let sessions = self.storage.load_session_index().await.unwrap_or(sessions);
+                    for id in archivable_sessions(&sessions, cutoff) {
+                        if live.contains(&id) {
+                            continue;
+                        }
+                        let result = self
+                            .storage
+                            .modify_session(&id, |session| session.is_archived = true)
+                            .await;
+                        match result {
+                            Ok(_) => report.archived.push(id),
+                            Err(e) => report.errors.push(format!("归档失败 {}: {}", id, e)),
+                        }
+                    }
+                }
+            }
+            Err(e) => report.errors.push(format!("读取会话失败: {}", e)),
+        }
+
+        match self
+            .storage
+            .prune_session_data(settings.max_session_history)
+            .await
+        {
+            Ok(pruned) => report.pruned_details = pruned,
+            Err(e) => report.errors.push(format!("清理会话详情失败: {}", e)),
+        }
+
+        if let Some(days) = policy.compress_transcripts_after_days {
+            let cutoff = SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+            let projects_dirs = projects_dirs.to_vec();
+            let archive_dir = self.storage.transcripts_dir();
+            let live_dirs: HashSet<String> = live_projects
+                .iter()
+                .map(|path| discovery::encode_project_path(path))
+                .collect();
+            let result = tokio::task::spawn_blocking(move || {
+                compress_transcripts(&projects_dirs, &archive_dir, &live_dirs, cutoff)
+            })
+            .await;
+            match result {
+                Ok((compressed, errors)) => {
+                    report.compressed_transcripts = compressed;
+                    report.errors.extend(errors);
+                }
+                Err(e) => report.errors.push(format!("压缩日志失败: {}", e)),
+            }
+        }
+
+        if let Ok(mut log) = self.log.lock() {
+            if log.len() >= LOG_CAPACITY {
+                log.pop_front();
+            }
+            log.push_back(report.clone());
+        }
+        report
+    }
+}
+
+/// 最后活动早于截止时间、尚未完成的会话
+fn idle_sessions(
+    sessions: &[Session],
+    projects_dirs: &[PathBuf],
+    cutoff: DateTime<Utc>,
+) -> Vec<String> {
+    sessions
+        .iter()
+        .filter(|s| !s.is_archived && s.status != SessionStatus::Completed)
+        .filter(|s| last_activity(s, projects_dirs) < cutoff)
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+/// 会话的最后活动时间
+///
+/// `last_active_at` 只在会话被发现时设置，以项目日志的最新修改时间为准。
+fn last_activity(session: &Session, projects_dirs: &[PathBuf]) -> DateTime<Utc> {
+    let encoded = discovery::encode_project_path(Path::new(&session.project_path));
+    projects_dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir.join(&encoded)).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().contains(".jsonl"))
+        .filter_map(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(DateTime::<Utc>::from)
+        .fold(session.last_active_at, DateTime::max)
+}
+
+/// 完成时间早于截止时间、尚未归档的会话
+fn archivable_sessions(sessions: &[Session], cutoff: DateTime<Utc>) -> Vec<String> {
+    sessions
+        .iter()
+        .filter(|s| !s.is_archived && s.status == SessionStatus::Completed)
+        .filter(|s| {
+            let completed_at = s
+                .ended_at
+                .or(s.marked_completed_at)
+                .unwrap_or(s.last_active_at);
+            completed_at < cutoff
+        })
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+/// 压缩备份修改时间早于截止时间的日志，返回新压缩的日志路径与错误
+///
+/// 副本写入 `archive_dir/<项目目录>/<日志名>.gz`，Claude Code 的原日志保持不变，
+/// 恢复会话、历史列表与搜索不受影响。`live_dirs` 中的项目目录仍有进程在写入，跳过；
+/// 副本不旧于原日志时跳过。
+fn compress_transcripts(
+    projects_dirs: &[PathBuf],
+    archive_dir: &Path,
+    live_dirs: &HashSet<String>,
+    cutoff: SystemTime,
+) -> (Vec<String>, Vec<String>) {
+    let mut compressed = Vec::new();
+    let mut errors = Vec::new();
+
+    for log_path in search::list_logs(projects_dirs) {
+        let Some(project_dir) = log_path.parent().and_then(Path::file_name) else {
+            continue;
+        };
+        if live_dirs.contains(project_dir.to_string_lossy().as_ref()) {
+            continue;
+        }
+        let Ok(modified) = std::fs::metadata(&log_path).and_then(|m| m.modified()) else {
+            continue;
+        };
+        if modified >= cutoff {
+            continue;
+        }
+
+        let mut name = log_path.file_name().unwrap_or_default().to_os_string();
+        name.push(".gz");
+        let target = archive_dir.join(project_dir).join(name);
+        let archived = std::fs::metadata(&target).and_then(|m| m.modified());
+        if matches!(archived, Ok(archived) if archived >= modified) {
+            continue;
+        }
+
+        match compress_file(&log_path, &target) {
+            Ok(()) => compressed.push(log_path.to_string_lossy().to_string()),
+            Err(e) => errors.push(format!("压缩日志失败 {}: {}", log_path.display(), e)),
+        }
+    }
+    (compressed, errors)
+}
+
+/// 把日志压缩写入目标路径，原文件保持不变
+///
+/// 先写入临时文件再重命名，中途失败时不会留下不完整的副本。
+fn compress_file(path: &Path, target: &Path) -> std::io::Result<()> {
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let tmp = target.with_file_name(name);
+
+    {
+        let mut input = std::fs::File::open(path)?;
+        let mut encoder = GzEncoder::new(std::fs::File::create(&tmp)?, Compression::default());
+        std::io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?.sync_all()?;
+    }
+    std::fs::rename(&tmp, target)
+}
+
+/// 按当前配置立即执行一次保留策略
+pub async fn run_now(state: &AppState) -> Result<RetentionReport> {
+    let settings = state.config.read().await.settings.clone();
+    let projects_dirs = state.claude_projects_dirs().await?;
+    let sessions = state.monitor.sessions();
+    let live: HashSet<String> = sessions.iter().map(|s| s.id.clone()).collect();
+
+    // 监控中的会话与持有锁文件的进程所在项目
+    let mut live_projects: HashSet<PathBuf> = sessions
+        .iter()
+        .map(|s| PathBuf::from(&s.project_path))
+        .collect();
+    for discovery in state.discoveries().await? {
+        match discovery.discover_sessions().await {
+            Ok(found) => live_projects.extend(found.into_iter().map(|s| s.project_path)),
+            Err(e) => tracing::warn!("扫描锁文件失败 {}: {}", discovery.ide_dir.display(), e),
+        }
+    }
+
+    Ok(state
+        .retention
+        .run(&settings, &projects_dirs, &live, &live_projects, Utc::now())
+        .await)
+}
+
+/// 启动保留策略定时任务，执行间隔随配置变化
+pub fn spawn_scheduler(state: AppState) {
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("保留策略任务已启动");
+        tokio::time::sleep(STARTUP_DELAY).await;
+
+        loop {
+            match run_now(&state).await {
+                Ok(report) => tracing::info!(
+                    "保留策略已执行: 标记完成 {} 个, 归档 {} 个, 清理详情 {} 个, 压缩日志 {} 个, 错误 {} 个",
+                    report.marked_completed.len(),
+                    report.archived.len(),
+                    report.pruned_details.len(),
+                    report.compressed_transcripts.len(),
+                    report.errors.len()
+                ),
+                Err(e) => tracing::warn!("保留策略执行失败: {}", e),
+            }
+
+            let interval_mins = state.config.read().await.settings.retention.interval_mins;
+            tokio::time::sleep(Duration::from_secs(interval_mins.max(1) * 60)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{AppConfig, SessionDetail, SessionStats};
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    fn session_at(title: &str, status: SessionStatus, last_active_at: DateTime<Utc>) -> Session {
+        let mut session = Session::new(title, "app", format!("/work/{}", uuid::Uuid::new_v4()));
+        session.status = status;
+        session.last_active_at = last_active_at;
+        session
+    }
+
+    #[tokio::test]
+    async fn test_rules_mark_archive_and_prune() {
+        let temp = TempDir::new().unwrap();
+        let storage = Arc::new(Storage::open(temp.path().join("data")).await.unwrap());
+        let engine = RetentionEngine::new(storage.clone());
+        let now = Utc::now();
+
+        let active = session_at("活跃", SessionStatus::Running, now);
+        let idle = session_at(
+            "空闲",
+            SessionStatus::WaitingInput,
+            now - ChronoDuration::hours(30),
+        );
+        // 仍在监控中，或日志仍在更新的会话不算空闲
+        let live = session_at(
+            "监控中",
+            SessionStatus::Running,
+            now - ChronoDuration::hours(30),
+        );
+        let writing = session_at(
+            "日志更新中",
+            SessionStatus::Running,
+            now - ChronoDuration::hours(30),
+        );
+        let mut ended = session_at(
+            "已结束",
+            SessionStatus::Completed,
+            now - ChronoDuration::days(10),
+        );
+        ended.ended_at = Some(now - ChronoDuration::days(9));
+        for session in [&active, &idle, &live, &writing, &ended] {
+            storage
+                .save_session_detail(&SessionDetail {
+                    session: session.clone(),
+                    messages: Vec::new(),
+                    process_info: None,
+                    stats: SessionStats {
+                        message_count: 0,
+                        total_tokens: None,
+                        duration_secs: 0,
+                    },
+                })
+                .await
+                .unwrap();
+        }
+        let projects_dirs = vec![temp.path().join("projects")];
+        let log_dir = projects_dirs[0].join(discovery::encode_project_path(Path::new(
+            &writing.project_path,
+        )));
+        std::fs::create_dir_all(&log_dir).unwrap();
+        std::fs::write(log_dir.join("aaa.jsonl"), "{}\n").unwrap();
+
+        let mut settings = AppConfig::default().settings;
+        settings.max_session_history = 4;
+        let live_ids = HashSet::from([live.id.clone()]);
+
+        // 默认不自动标记完成或归档
+        let report = engine
+            .run(&settings, &projects_dirs, &live_ids, &HashSet::new(), now)
+            .await;
+        assert!(report.marked_completed.is_empty() && report.archived.is_empty());
+        assert_eq!(report.pruned_details, vec![ended.id.clone()]);
+
+        settings.retention.auto_complete_after_hours = Some(24);
+        settings.retention.auto_archive_after_days = Some(7);
+        let report = engine
+            .run(&settings, &projects_dirs, &live_ids, &HashSet::new(), now)
+            .await;
+
+        assert!(report.errors.is_empty());
+        assert_eq!(report.marked_completed, vec![idle.id.clone()]);
+        // 刚被标记完成的会话要等归档期限过后才归档
+        assert_eq!(report.archived, vec![ended.id.clone()]);
+
+        let stored = storage.get_session(&idle.id).await.unwrap();
+        assert_eq!(stored.status, SessionStatus::Completed);
+        assert_eq!(stored.marked_completed_at, Some(now));
+        assert!(!stored.is_archived);
+        assert!(storage.get_session(&ended.id).await.unwrap().is_archived);
+        assert!(storage.load_session_detail(&ended.id).await.is_err());
+        assert!(storage.load_session_detail(&active.id).await.is_ok());
+        assert_eq!(engine.reports().len(), 2);
+    }
+
+    #[test]
+    fn test_compresses_old_transcripts() {
+        let temp = TempDir::new().unwrap();
+        let projects_dir = temp.path().join("projects");
+        let archive_dir = temp.path().join("transcripts");
+        let project = projects_dir.join("-work-app");
+        let running = projects_dir.join("-work-running");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(&running).unwrap();
+        let log = project.join("aaa.jsonl");
+        let live_log = running.join("bbb.jsonl");
+        std::fs::write(&log, "{\"type\":\"user\"}\n").unwrap();
+        std::fs::write(&live_log, "{\"type\":\"user\"}\n").unwrap();
+        let dirs = [projects_dir.clone()];
+        let live_dirs = HashSet::from([discovery::encode_project_path(Path::new("/work/running"))]);
+
+        // 截止时间早于修改时间的日志保持不变
+        let past = SystemTime::now() - Duration::from_secs(3600);
+        let (compressed, errors) = compress_transcripts(&dirs, &archive_dir, &live_dirs, past);
+        assert!(compressed.is_empty() && errors.is_empty());
+
+        // 仍在运行的会话的日志不压缩；原日志保留
+        let future = SystemTime::now() + Duration::from_secs(3600);
+        let (compressed, errors) = compress_transcripts(&dirs, &archive_dir, &live_dirs, future);
+        assert!(errors.is_empty());
+        assert_eq!(compressed, vec![log.to_string_lossy().to_string()]);
+        assert!(log.exists() && live_log.exists());
+        assert!(!archive_dir.join("-work-running").exists());
+
+        let mut content = String::new();
+        flate2::read::GzDecoder::new(
+            std::fs::File::open(archive_dir.join("-work-app").join("aaa.jsonl.gz")).unwrap(),
+        )
+        .read_to_string(&mut content)
+        .unwrap();
+        assert_eq!(content, "{\"type\":\"user\"}\n");
+
+        // 原日志未变化时不重复压缩
+        let (compressed, _) = compress_transcripts(&dirs, &archive_dir, &live_dirs, future);
+        assert!(compressed.is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! 日志条目提取
+//!
+//! 从 Claude Code 的 jsonl 日志行中提取可检索的文本：用户与助手的文本、
+//! 调用的工具名以及工具参数中的文件路径。工具结果不建立索引。
+
+use crate::monitor::history::user_prompt_text;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 工具参数中表示文件路径的字段
+const PATH_FIELDS: &[&str] = &["file_path", "path", "notebook_path"];
+
+/// 索引条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// 用户输入
+    User,
+    /// 助手回复
+    Assistant,
+    /// 工具调用（工具名与文件路径）
+    Tool,
+}
+
+impl EntryKind {
+    /// 存储名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntryKind::User => "user",
+            EntryKind::Assistant => "assistant",
+            EntryKind::Tool => "tool",
+        }
+    }
+
+    /// 解析存储名称
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "user" => Some(EntryKind::User),
+            "assistant" => Some(EntryKind::Assistant),
+            "tool" => Some(EntryKind::Tool),
+            _ => None,
+        }
+    }
+}
+
+/// 日志行中提取出的可检索文本
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub kind: EntryKind,
+    /// 所在消息的 uuid
+    pub message_uuid: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub content: String,
+}
+
+/// 解析后的日志行
+#[derive(Debug, Default)]
+pub struct LogLine {
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub entries: Vec<LogEntry>,
+}
+
+/// 解析一行日志，不是合法 JSON 时返回 None
+pub fn parse_line(line: &str) -> Option<LogLine> {
+    let event: Value = serde_json::from_str(line).ok()?;
+
+    let text = |key: &str| event.get(key).and_then(Value::as_str).map(String::from);
+    let message_uuid = text("uuid");
+    let timestamp = event
+        .get("timestamp")
+        .and_then(Value::as_str)
+        .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|ts| ts.with_timezone(&Utc));
+    let entry = |kind, content| LogEntry {
+        kind,
+        message_uuid: message_uuid.clone(),
+        timestamp,
+        content,
+    };
+
+    let mut entries = Vec::new();
+    match event.get("type").and_then(Value::as_str) {
+        Some("user") => {
+            if let Some(content) = user_prompt_text(&event) {
+                entries.push(entry(EntryKind::User, content));
+            }
+        }
+        Some("assistant") => {
+            let blocks = event
+                .pointer("/message/content")
+                .and_then(Value::as_array)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let assistant_text: Vec<&str> = blocks
+                .iter()
+                .filter(|b| b.get("type").and_then(Value::as_str) == Some("text"))
+                .filter_map(|b| b.get("text").and_then(Value::as_str))
+                .collect();
+            let assistant_text = assistant_text.join("\n");
+            if !assistant_text.trim().is_empty() {
+                entries.push(entry(
+                    EntryKind::Assistant,
+                    assistant_text.trim().to_string(),
+                ));
+            }
+
+            for block in blocks
+                .iter()
+                .filter(|b| b.get("type").and_then(Value::as_str) == Some("tool_use"))
+            {
+                if let Some(content) = tool_text(block) {
+                    entries.push(entry(EntryKind::Tool, content));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    Some(LogLine {
+        session_id: text("sessionId"),
+        cwd: text("cwd"),
+        entries,
+    })
+}
+
+/// 工具调用的可检索文本：工具名加参数中的文件路径
+fn tool_text(block: &Value) -> Option<String> {
+    let name = block.get("name").and_then(Value::as_str)?;
+    let mut parts = vec![name.to_string()];
+    if let Some(input) = block.get("input") {
+        parts.extend(
+            PATH_FIELDS
+                .iter()
+                .filter_map(|field| input.get(*field).and_then(Value::as_str))
+                .map(String::from),
+        );
+    }
+    Some(parts.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extracts_text_tools_and_paths() {
+        let line = r#"{"type":"assistant","uuid":"m-2","sessionId":"s-1","cwd":"/work/app","timestamp":"2026-01-01T10:05:00Z","message":{"role":"assistant","content":[{"type":"text","text":"我来修改登录逻辑"},{"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"/work/app/src/login.rs","old_string":"a"}}]}}"#;
+        let parsed = parse_line(line).unwrap();
+        assert_eq!(parsed.session_id.as_deref(), Some("s-1"));
+        assert_eq!(parsed.cwd.as_deref(), Some("/work/app"));
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[0].kind, EntryKind::Assistant);
+        assert_eq!(parsed.entries[0].content, "我来修改登录逻辑");
+        assert_eq!(parsed.entries[1].kind, EntryKind::Tool);
+        assert_eq!(parsed.entries[1].content, "Edit /work/app/src/login.rs");
+        assert_eq!(parsed.entries[1].message_uuid.as_deref(), Some("m-2"));
+
+        // 工具结果不建立索引
+        let tool_result = r#"{"type":"user","uuid":"m-3","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#;
+        assert!(parse_line(tool_result).unwrap().entries.is_empty());
+        assert!(parse_line("{ truncated").is_none());
+    }
+}
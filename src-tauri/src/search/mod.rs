@@ -0,0 +1,699 @@
+//! 全文检索模块
+//!
+//! 为所有根目录下的 Claude Code jsonl 日志建立本地全文索引（SQLite FTS5，trigram 分词，
+//! 支持中文子串匹配）。日志只会追加，索引按文件记录已处理的字节位置增量更新；
+//! 文件被截断或重写时整体重建该文件的索引。
+//! 索引是可重建的缓存，保存在 ~/.codeagent/cache/search.db。
+
+use crate::error::{AppError, Result};
+use chrono::{DateTime, Utc};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+pub mod extract;
+pub mod snippet;
+
+use extract::EntryKind;
+use snippet::SnippetSegment;
+
+/// 索引文件名（位于缓存目录）
+pub const INDEX_FILE: &str = "search.db";
+
+/// 索引结构版本，结构变化时递增，旧索引会被丢弃重建
+const INDEX_VERSION: i64 = 1;
+
+/// 单次查询读取的最大命中条数
+const MAX_HITS: usize = 2000;
+
+/// 每个会话返回的最大命中条数
+const HITS_PER_SESSION: usize = 5;
+
+/// 默认返回的最大会话数
+const DEFAULT_SESSION_LIMIT: usize = 50;
+
+/// trigram 分词下可走索引的最短关键词（字符）
+const MIN_MATCH_CHARS: usize = 3;
+
+/// 文件变化后等待合并的时间，避免每次追加都触发一次索引
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 检查尚不存在的 projects 目录是否已创建的间隔
+const RESCAN_INTERVAL: Duration = Duration::from_secs(30);
+
+const SCHEMA: &str = r#"
+    CREATE TABLE files (
+        path TEXT PRIMARY KEY,
+        session_id TEXT NOT NULL,
+        project_path TEXT,
+        indexed_bytes INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE VIRTUAL TABLE entries USING fts5(
+        content,
+        kind UNINDEXED,
+        path UNINDEXED,
+        message_uuid UNINDEXED,
+        timestamp UNINDEXED,
+        tokenize = 'trigram'
+    );
+"#;
+
+/// 搜索过滤条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchFilters {
+    /// 只搜索该项目的会话
+    pub project_path: Option<String>,
+    /// 只搜索这些类型的条目（user、assistant、tool），为空时搜索全部
+    pub kinds: Vec<EntryKind>,
+    /// 只搜索该时间之后的消息
+    pub since: Option<DateTime<Utc>>,
+    /// 只搜索该时间之前的消息
+    pub until: Option<DateTime<Utc>>,
+    /// 最多返回的会话数
+    pub limit: Option<usize>,
+}
+
+/// 单条命中
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub kind: EntryKind,
+    /// 命中消息的 uuid，可用于定位到日志中的具体消息
+    pub message_uuid: Option<String>,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub snippet: Vec<SnippetSegment>,
+}
+
+/// 命中的会话（按最近一次命中的时间倒序）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchResult {
+    /// Claude Code 会话 ID
+    pub session_id: String,
+    pub project_path: Option<String>,
+    pub log_path: String,
+    /// 该会话的命中总数
+    pub match_count: usize,
+    /// 最近的若干条命中
+    pub hits: Vec<SearchHit>,
+}
+
+/// 全文索引
+#[derive(Debug, Clone)]
+pub struct SearchIndex {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SearchIndex {
+    /// 打开索引，索引损坏或版本不符时重建
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = match Self::open_conn(path) {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("全文索引不可用，重建索引: {}", e);
+                let _ = std::fs::remove_file(path);
+                Self::open_conn(path)?
+            }
+        };
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    fn open_conn(path: &Path) -> Result<Connection> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version != INDEX_VERSION {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS files;
+                 DROP TABLE IF EXISTS entries;",
+            )?;
+            conn.execute_batch(SCHEMA)?;
+            conn.pragma_update(None, "user_version", INDEX_VERSION)?;
+        }
+        Ok(conn)
+    }
+
+    fn conn(&self) -> Result<MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| AppError::StorageError("全文索引连接已损坏".to_string()))
+    }
+
+    /// 索引所有 projects 目录下的日志，返回新增的条目数
+    pub fn index_all(&self, projects_dirs: &[PathBuf]) -> Result<usize> {
+        let mut added = 0;
+        for log_path in list_logs(projects_dirs) {
+            match self.index_file(&log_path) {
+                Ok(count) => added += count,
+                Err(e) => tracing::warn!("索引日志失败 {}: {}", log_path.display(), e),
+            }
+        }
+        Ok(added)
+    }
+
+    /// 增量索引单个日志文件，返回新增的条目数
+    ///
+    /// 只处理以换行结尾的完整行，写到一半的行留到下次处理。
+    pub fn index_file(&self, log_path: &Path) -> Result<usize> {
+        let key = log_path.to_string_lossy().to_string();
+        let mut file = match std::fs::File::open(log_path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.remove_file(log_path)?;
+                return Ok(0);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let len = file.metadata()?.len();
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let stored: Option<(String, Option<String>, i64)> = tx
+            .query_row(
+                "SELECT session_id, project_path, indexed_bytes FROM files WHERE path = ?1",
+                [&key],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+
+        let default_session_id = log_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (mut session_id, mut project_path, mut offset, fresh) = match stored {
+            Some((session_id, project_path, offset)) if offset as u64 <= len => {
+                (session_id, project_path, offset as u64, false)
+            }
+            Some(_) => {
+                // 文件被截断或重写，重新索引
+                tx.execute("DELETE FROM entries WHERE path = ?1", [&key])?;
+                (default_session_id, None, 0, true)
+            }
+            None => (default_session_id, None, 0, true),
+        };
+        if !fresh && offset == len {
+            return Ok(0);
+        }
+
+        file.seek(SeekFrom::Start(offset))?;
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let complete = match bytes.iter().rposition(|b| *b == b'\n') {
+            Some(last_newline) => &bytes[..=last_newline],
+            None => &bytes[..0],
+        };
+
+        let mut added = 0;
+        let mut session_found = false;
+        let mut insert = tx.prepare(
+            "INSERT INTO entries (content, kind, path, message_uuid, timestamp)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for line in String::from_utf8_lossy(complete).lines() {
+            let Some(parsed) = extract::parse_line(line) else {
+                continue;
+            };
+            // 新文件以日志中的会话 ID 为准（文件名通常与之相同）
+            if fresh && !session_found {
+                if let Some(id) = parsed.session_id {
+                    session_id = id;
+                    session_found = true;
+                }
+            }
+            if project_path.is_none() {
+                project_path = parsed.cwd;
+            }
+            for entry in parsed.entries {
+                insert.execute(params![
+                    entry.content,
+                    entry.kind.as_str(),
+                    key,
+                    entry.message_uuid,
+                    entry.timestamp.map(|ts| ts.to_rfc3339()),
+                ])?;
+                added += 1;
+            }
+        }
+        drop(insert);
+        offset += complete.len() as u64;
+
+        tx.execute(
+            "INSERT INTO files (path, session_id, project_path, indexed_bytes)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(path) DO UPDATE SET
+                 session_id = excluded.session_id,
+                 project_path = excluded.project_path,
+                 indexed_bytes = excluded.indexed_bytes",
+            params![key, session_id, project_path, offset as i64],
+        )?;
+        tx.commit()?;
+        Ok(added)
+    }
+
+    /// 移除不在这些 projects 目录下的日志索引，返回移除的文件数
+    pub fn retain_dirs(&self, projects_dirs: &[PathBuf]) -> Result<usize> {
+        let paths: Vec<String> = {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare("SELECT path FROM files")?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+
+        let mut removed = 0;
+        for path in paths.iter().map(Path::new) {
+            if !projects_dirs.iter().any(|dir| path.starts_with(dir)) {
+                self.remove_file(path)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// 移除日志文件的索引
+    pub fn remove_file(&self, log_path: &Path) -> Result<()> {
+        let key = log_path.to_string_lossy().to_string();
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM entries WHERE path = ?1", [&key])?;
+        tx.execute("DELETE FROM files WHERE path = ?1", [&key])?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 搜索会话
+    ///
+    /// 空白分隔的多个关键词需同时出现在同一条目中。
+    pub fn search(&self, query: &str, filters: &SearchFilters) -> Result<Vec<SessionSearchResult>> {
+        let terms: Vec<String> = query.split_whitespace().map(String::from).collect();
+        if terms.is_empty() {
+            return Err(AppError::InvalidInput("搜索内容不能为空".to_string()));
+        }
+
+        let mut conditions = Vec::new();
+        let mut values: Vec<String> = Vec::new();
+        if terms.iter().all(|t| t.chars().count() >= MIN_MATCH_CHARS) {
+            conditions.push("e.content MATCH ?".to_string());
+            values.push(
+                terms
+                    .iter()
+                    .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        } else {
+            // 短关键词无法组成 trigram，退回逐条匹配
+            for term in &terms {
+                conditions.push("e.content LIKE ? ESCAPE '\\'".to_string());
+                values.push(format!("%{}%", escape_like(term)));
+            }
+        }
+        if let Some(project_path) = &filters.project_path {
+            conditions.push("f.project_path = ?".to_string());
+            values.push(project_path.clone());
+        }
+        if !filters.kinds.is_empty() {
+            let placeholders = vec!["?"; filters.kinds.len()].join(", ");
+            conditions.push(format!("e.kind IN ({})", placeholders));
+            values.extend(filters.kinds.iter().map(|k| k.as_str().to_string()));
+        }
+        if let Some(since) = filters.since {
+            conditions.push("e.timestamp >= ?".to_string());
+            values.push(since.to_rfc3339());
+        }
+        if let Some(until) = filters.until {
+            conditions.push("e.timestamp <= ?".to_string());
+            values.push(until.to_rfc3339());
+        }
+
+        let sql = format!(
+            "SELECT f.session_id, f.project_path, e.path, e.kind, e.message_uuid, e.timestamp, e.content
+             FROM entries e JOIN files f ON f.path = e.path
+             WHERE {}
+             ORDER BY e.timestamp DESC
+             LIMIT {}",
+            conditions.join(" AND "),
+            MAX_HITS
+        );
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+                row.get::<_, String>(6)?,
+            ))
+        })?;
+
+        let limit = filters.limit.unwrap_or(DEFAULT_SESSION_LIMIT);
+        let mut results: Vec<SessionSearchResult> = Vec::new();
+        let mut by_log: HashMap<String, usize> = HashMap::new();
+        for row in rows {
+            let (session_id, project_path, log_path, kind, message_uuid, timestamp, content) = row?;
+
+            let index = match by_log.get(&log_path) {
+                Some(index) => *index,
+                None => {
+                    if results.len() >= limit {
+                        continue;
+                    }
+                    by_log.insert(log_path.clone(), results.len());
+                    results.push(SessionSearchResult {
+                        session_id,
+                        project_path,
+                        log_path,
+                        match_count: 0,
+                        hits: Vec::new(),
+                    });
+                    results.len() - 1
+                }
+            };
+
+            let result = &mut results[index];
+            result.match_count += 1;
+            if result.hits.len() < HITS_PER_SESSION {
+                result.hits.push(SearchHit {
+                    kind: EntryKind::parse(&kind).unwrap_or(EntryKind::Assistant),
+                    message_uuid,
+                    timestamp: timestamp
+                        .and_then(|ts| DateTime::parse_from_rfc3339(&ts).ok())
+                        .map(|ts| ts.with_timezone(&Utc)),
+                    snippet: snippet::build(&content, &terms),
+                });
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// 转义 LIKE 模式中的通配符
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// 列出 projects 目录下所有项目的 jsonl 日志
+pub fn list_logs(projects_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    projects_dirs
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| is_log_file(path))
+        .collect()
+}
+
+fn is_log_file(path: &Path) -> bool {
+    path.extension() == Some("jsonl".as_ref())
+}
+
+/// 启动索引任务
+///
+/// 先补齐所有日志的索引，之后监控 projects 目录，日志新增、追加或删除时增量更新。
+/// `projects_dirs` 变化（配置的根目录变化）或配置的目录在启动后才创建时，
+/// 移除旧目录的索引、重建文件监控并重新补齐索引。
+pub fn spawn_indexer(index: SearchIndex, mut projects_dirs: watch::Receiver<Vec<PathBuf>>) {
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("全文索引任务已启动");
+
+        let (sender, mut changes) = mpsc::unbounded_channel::<PathBuf>();
+        let mut rescan = tokio::time::interval(RESCAN_INTERVAL);
+        rescan.tick().await;
+
+        let mut dirs = projects_dirs.borrow_and_update().clone();
+        let mut watched = existing_dirs(&dirs);
+        // 监控器需在任务存续期间保持存活
+        let mut watcher = reindex(&index, &dirs, &watched, sender.clone()).await;
+
+        loop {
+            tokio::select! {
+                changed = projects_dirs.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                    dirs = projects_dirs.borrow_and_update().clone();
+                    watched = existing_dirs(&dirs);
+                    watcher.take();
+                    watcher = reindex(&index, &dirs, &watched, sender.clone()).await;
+                }
+                _ = rescan.tick() => {
+                    let existing = existing_dirs(&dirs);
+                    if existing != watched {
+                        watched = existing;
+                        watcher.take();
+                        watcher = reindex(&index, &dirs, &watched, sender.clone()).await;
+                    }
+                }
+                Some(path) = changes.recv() => {
+                    // 合并短时间内的连续变化
+                    let mut pending = vec![path];
+                    tokio::time::sleep(DEBOUNCE).await;
+                    while let Ok(path) = changes.try_recv() {
+                        if !pending.contains(&path) {
+                            pending.push(path);
+                        }
+                    }
+                    // 忽略旧目录的监控器在切换前发出的事件
+                    pending.retain(|path| dirs.iter().any(|dir| path.starts_with(dir)));
+
+                    let index = index.clone();
+                    let result = tokio::task::spawn_blocking(move || {
+                        for path in pending {
+                            if let Err(e) = index.index_file(&path) {
+                                tracing::warn!("更新全文索引失败 {}: {}", path.display(), e);
+                            }
+                        }
+                    })
+                    .await;
+                    if let Err(e) = result {
+                        tracing::warn!("全文索引任务异常: {}", e);
+                    }
+                }
+            }
+        }
+
+        tracing::info!("全文索引任务已停止");
+    });
+}
+
+/// 已存在的 projects 目录
+fn existing_dirs(projects_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    projects_dirs
+        .iter()
+        .filter(|dir| dir.exists())
+        .cloned()
+        .collect()
+}
+
+/// 重建文件监控并按当前目录补齐索引，移除其他目录的索引
+async fn reindex(
+    index: &SearchIndex,
+    projects_dirs: &[PathBuf],
+    watched: &[PathBuf],
+    sender: mpsc::UnboundedSender<PathBuf>,
+) -> Option<RecommendedWatcher> {
+    // 先建立监控，补齐索引期间的变化不会丢失
+    let watcher = match watch_logs(watched, sender) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            tracing::warn!("监控日志目录失败，全文索引不会自动更新: {}", e);
+            None
+        }
+    };
+
+    let result = {
+        let index = index.clone();
+        let projects_dirs = projects_dirs.to_vec();
+        tokio::task::spawn_blocking(move || {
+            let removed = index.retain_dirs(&projects_dirs)?;
+            let added = index.index_all(&projects_dirs)?;
+            Ok::<_, AppError>((removed, added))
+        })
+        .await
+    };
+    match result {
+        Ok(Ok((removed, added))) => tracing::info!(
+            "全文索引已更新，新增 {} 条，移除 {} 个旧目录下的日志",
+            added,
+            removed
+        ),
+        Ok(Err(e)) => tracing::warn!("全文索引失败: {}", e),
+        Err(e) => tracing::warn!("全文索引任务异常: {}", e),
+    }
+    watcher
+}
+
+/// 递归监控 projects 目录中 jsonl 日志的变化
+fn watch_logs(
+    projects_dirs: &[PathBuf],
+    sender: mpsc::UnboundedSender<PathBuf>,
+) -> Result<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+        let Ok(event) = res else {
+            return;
+        };
+        if matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) {
+            for path in event.paths.into_iter().filter(|p| is_log_file(p)) {
+                let _ = sender.send(path);
+            }
+        }
+    })?;
+
+    for dir in projects_dirs {
+        watcher.watch(dir, RecursiveMode::Recursive)?;
+    }
+    Ok(watcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const USER_LINE: &str = r#"{"type":"user","uuid":"m-1","sessionId":"aaa","cwd":"/work/app","timestamp":"2026-01-01T10:00:00Z","message":{"role":"user","content":"修复登录页面的跳转问题"}}"#;
+    const TOOL_LINE: &str = r#"{"type":"assistant","uuid":"m-2","sessionId":"aaa","timestamp":"2026-01-01T10:01:00Z","message":{"role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Edit","input":{"file_path":"/work/app/src/Login.tsx"}}]}}"#;
+    const OTHER_LINE: &str = r#"{"type":"user","uuid":"m-9","sessionId":"bbb","cwd":"/work/api","timestamp":"2026-01-02T09:00:00Z","message":{"role":"user","content":"add rate limiting to the login endpoint"}}"#;
+
+    fn setup() -> (TempDir, SearchIndex, PathBuf) {
+        let temp = TempDir::new().unwrap();
+        let projects = temp.path().join("projects");
+        std::fs::create_dir_all(projects.join("-work-app")).unwrap();
+        std::fs::create_dir_all(projects.join("-work-api")).unwrap();
+        std::fs::write(
+            projects.join("-work-api").join("bbb.jsonl"),
+            format!("{}\n", OTHER_LINE),
+        )
+        .unwrap();
+        let index = SearchIndex::open(&temp.path().join(INDEX_FILE)).unwrap();
+        (temp, index, projects)
+    }
+
+    #[test]
+    fn test_incremental_index_and_search() {
+        let (_temp, index, projects) = setup();
+        let log = projects.join("-work-app").join("aaa.jsonl");
+
+        // 写到一半的行暂不索引
+        std::fs::write(&log, format!("{}\n{{\"type\":", USER_LINE)).unwrap();
+        assert_eq!(index.index_all(std::slice::from_ref(&projects)).unwrap(), 2);
+        assert_eq!(index.index_all(std::slice::from_ref(&projects)).unwrap(), 0);
+
+        std::fs::write(&log, format!("{}\n{}\n", USER_LINE, TOOL_LINE)).unwrap();
+        assert_eq!(index.index_file(&log).unwrap(), 1);
+
+        let results = index.search("登录", &SearchFilters::default()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].session_id, "aaa");
+        assert_eq!(results[0].project_path.as_deref(), Some("/work/app"));
+        assert_eq!(results[0].hits[0].message_uuid.as_deref(), Some("m-1"));
+        assert!(results[0].hits[0]
+            .snippet
+            .iter()
+            .any(|s| s.highlighted && s.text == "登录"));
+
+        // 文件路径与工具名可检索，英文不区分大小写
+        let results = index.search("login", &SearchFilters::default()).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].session_id, "bbb");
+        assert_eq!(results[1].hits[0].message_uuid.as_deref(), Some("m-2"));
+
+        let filters = SearchFilters {
+            project_path: Some("/work/app".to_string()),
+            kinds: vec![EntryKind::Tool],
+            ..SearchFilters::default()
+        };
+        let results = index.search("login.tsx", &filters).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].match_count, 1);
+    }
+
+    #[test]
+    fn test_rewritten_and_removed_logs() {
+        let (_temp, index, projects) = setup();
+        let log = projects.join("-work-api").join("bbb.jsonl");
+        index.index_all(std::slice::from_ref(&projects)).unwrap();
+        assert_eq!(
+            index
+                .search("rate", &SearchFilters::default())
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // 文件被重写为更短的内容时重建索引
+        std::fs::write(&log, "{\"type\":\"summary\"}\n").unwrap();
+        index.index_file(&log).unwrap();
+        assert!(index
+            .search("rate", &SearchFilters::default())
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_file(&log).unwrap();
+        index.index_file(&log).unwrap();
+        assert!(index.search("  ", &SearchFilters::default()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_indexer_follows_projects_dirs() {
+        let (temp, index, projects) = setup();
+        let other = temp.path().join("other-root").join("projects");
+        let (dirs, receiver) = watch::channel(vec![projects.clone()]);
+        spawn_indexer(index.clone(), receiver);
+
+        let search = |query: &'static str| {
+            let index = index.clone();
+            async move {
+                for _ in 0..100 {
+                    let results = index.search(query, &SearchFilters::default()).unwrap();
+                    if !results.is_empty() {
+                        return results;
+                    }
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                }
+                Vec::new()
+            }
+        };
+        assert_eq!(search("rate").await.len(), 1);
+
+        // 切换到新的根目录：旧目录的索引被移除，新目录被索引并监控
+        std::fs::create_dir_all(other.join("-work-app")).unwrap();
+        dirs.send_replace(vec![other.clone()]);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(index
+            .search("rate", &SearchFilters::default())
+            .unwrap()
+            .is_empty());
+
+        std::fs::write(
+            other.join("-work-app").join("aaa.jsonl"),
+            format!("{}\n", USER_LINE),
+        )
+        .unwrap();
+        assert_eq!(search("登录").await[0].session_id, "aaa");
+    }
+}
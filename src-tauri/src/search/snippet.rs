@@ -0,0 +1,136 @@
+//! 搜索结果摘要
+//!
+//! 截取命中位置附近的文本，并把命中的关键词标记为高亮片段。
+//! 匹配对 ASCII 字母不区分大小写，与索引的 trigram 分词一致。
+
+use serde::Serialize;
+
+/// 摘要最大长度（字符）
+const SNIPPET_CHARS: usize = 160;
+
+/// 首个命中位置之前保留的字符数
+const LEADING_CHARS: usize = 40;
+
+/// 摘要片段，前端按顺序拼接并高亮 `highlighted` 的片段
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetSegment {
+    pub text: String,
+    pub highlighted: bool,
+}
+
+/// 生成带高亮的摘要，文本中没有命中时从开头截取
+pub fn build(content: &str, terms: &[String]) -> Vec<SnippetSegment> {
+    let chars: Vec<char> = content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+    let ranges = match_ranges(&chars, terms);
+
+    let start = ranges
+        .first()
+        .map(|(start, _)| start.saturating_sub(LEADING_CHARS))
+        .unwrap_or(0);
+    let end = (start + SNIPPET_CHARS).min(chars.len());
+
+    let mut segments = Vec::new();
+    let mut push = |text: String, highlighted: bool| {
+        if !text.is_empty() {
+            segments.push(SnippetSegment { text, highlighted });
+        }
+    };
+
+    let mut cursor = start;
+    let mut leading = if start > 0 {
+        "…".to_string()
+    } else {
+        String::new()
+    };
+    for (match_start, match_end) in ranges {
+        if match_end <= start || match_start >= end {
+            continue;
+        }
+        let match_start = match_start.max(start);
+        let match_end = match_end.min(end);
+        leading.extend(&chars[cursor..match_start]);
+        push(std::mem::take(&mut leading), false);
+        push(chars[match_start..match_end].iter().collect(), true);
+        cursor = match_end;
+    }
+    leading.extend(&chars[cursor..end]);
+    if end < chars.len() {
+        leading.push('…');
+    }
+    push(leading, false);
+
+    segments
+}
+
+/// 所有关键词在文本中的命中区间（字符下标），按位置排序并合并重叠
+fn match_ranges(chars: &[char], terms: &[String]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() || term.len() > chars.len() {
+            continue;
+        }
+        for start in 0..=chars.len() - term.len() {
+            let matches = chars[start..start + term.len()]
+                .iter()
+                .zip(&term)
+                .all(|(a, b)| a.eq_ignore_ascii_case(b));
+            if matches {
+                ranges.push((start, start + term.len()));
+            }
+        }
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(segments: &[SnippetSegment]) -> String {
+        segments
+            .iter()
+            .map(|s| {
+                if s.highlighted {
+                    format!("[{}]", s.text)
+                } else {
+                    s.text.clone()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_highlights_terms_case_insensitively() {
+        let segments = build(
+            "修复 Login 页面的\n登录 bug",
+            &["login".to_string(), "bug".to_string()],
+        );
+        assert_eq!(render(&segments), "修复 [Login] 页面的 登录 [bug]");
+    }
+
+    #[test]
+    fn test_window_around_first_match() {
+        let content = format!("{}needle{}", "a".repeat(100), "b".repeat(300));
+        let rendered = render(&build(&content, &["needle".to_string()]));
+        assert!(rendered.starts_with('…'));
+        assert!(rendered.ends_with('…'));
+        assert!(rendered.contains("[needle]"));
+        assert_eq!(rendered.chars().count(), SNIPPET_CHARS + 4);
+    }
+}
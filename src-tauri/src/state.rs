@@ -1,16 +1,41 @@
 use crate::error::Result;
 use crate::models::AppConfig;
-use crate::monitor::SessionMonitor;
+use crate::monitor::{
+    discovery::SessionDiscovery, handle::MonitorHandle, MonitorConfig, SessionMonitor,
+};
+use crate::notifications::policy::{self, Snoozes};
+use crate::queue::PromptQueueManager;
+use crate::retention::RetentionEngine;
+use crate::search::{self, SearchIndex};
 use crate::storage::{config::ConfigStorage, Storage};
+use crate::webhooks::WebhookDispatcher;
+use crate::workflow::WorkflowEngine;
+use crate::wrapper::ProcessWrapperManager;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, RwLock};
 
 /// 全局应用状态
 #[derive(Clone)]
 pub struct AppState {
     pub config: Arc<RwLock<AppConfig>>,
     pub storage: Arc<Storage>,
-    pub monitor: Arc<RwLock<SessionMonitor>>,
+    pub monitor: MonitorHandle,
+    pub webhooks: WebhookDispatcher,
+    /// 会话暂停提醒表（持久化于存储中）
+    pub snoozes: Arc<RwLock<Snoozes>>,
+    /// 应用在伪终端中启动的会话
+    pub process_wrapper: ProcessWrapperManager,
+    /// 会话的待发送提示队列
+    pub prompt_queue: PromptQueueManager,
+    /// 多会话工作流
+    pub workflows: WorkflowEngine,
+    /// 历史日志全文索引
+    pub search: SearchIndex,
+    /// 全文索引的 projects 目录，配置变化时更新，索引任务随之重新索引
+    pub search_dirs: Arc<watch::Sender<Vec<PathBuf>>>,
+    /// 会话保留策略
+    pub retention: RetentionEngine,
 }
 
 impl AppState {
@@ -20,12 +45,51 @@ impl AppState {
         let storage = Storage::new().await?;
         // 再加载配置（现在目录已存在）
         let config = ConfigStorage::load().await?;
-        let monitor = SessionMonitor::new().await?;
+        // 监控器在后台任务中运行，按配置的刷新间隔周期对账
+        let monitor = SessionMonitor::new(MonitorConfig::from_settings(&config.settings))?.spawn();
+        // 暂停提醒需跨重启保留，读取失败时从空表开始
+        let mut snoozes = storage.load_snoozes().await.unwrap_or_else(|e| {
+            tracing::warn!("读取暂停提醒失败: {}", e);
+            Snoozes::new()
+        });
+        policy::prune_expired(&mut snoozes, chrono::Utc::now());
+
+        let process_wrapper = ProcessWrapperManager::new(monitor.clone());
+
+        let queues = storage.load_prompt_queues().await.unwrap_or_else(|e| {
+            tracing::warn!("读取提示队列失败: {}", e);
+            Default::default()
+        });
+        let workflows = storage.load_workflows().await.unwrap_or_else(|e| {
+            tracing::warn!("读取工作流失败: {}", e);
+            Vec::new()
+        });
+        let storage = Arc::new(storage);
+        let prompt_queue = PromptQueueManager::new(
+            queues,
+            storage.clone(),
+            monitor.clone(),
+            process_wrapper.clone(),
+        );
+        let workflows = WorkflowEngine::new(workflows, storage.clone());
+        // 全文索引是可重建的缓存，放在缓存目录
+        let index_path = Storage::data_dir()?.join("cache").join(search::INDEX_FILE);
+        let search = SearchIndex::open(&index_path)?;
+        let (search_dirs, _) = watch::channel(projects_dirs(&config)?);
+        let retention = RetentionEngine::new(storage.clone());
 
         Ok(Self {
             config: Arc::new(RwLock::new(config)),
-            storage: Arc::new(storage),
-            monitor: Arc::new(RwLock::new(monitor)),
+            storage,
+            monitor,
+            webhooks: WebhookDispatcher::new(),
+            snoozes: Arc::new(RwLock::new(snoozes)),
+            process_wrapper,
+            prompt_queue,
+            workflows,
+            search,
+            search_dirs: Arc::new(search_dirs),
+            retention,
         })
     }
 
@@ -47,4 +111,64 @@ impl AppState {
         let config = self.config.read().await;
         ConfigStorage::save(&*config).await
     }
+
+    /// 暂停会话提醒直到指定时间；截止时间已过则取消暂停
+    pub async fn snooze_session(
+        &self,
+        session_id: &str,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let mut snoozes = self.snoozes.write().await;
+        let now = chrono::Utc::now();
+        if until > now {
+            snoozes.insert(session_id.to_string(), until);
+        } else {
+            snoozes.remove(session_id);
+        }
+        policy::prune_expired(&mut snoozes, now);
+        self.storage.save_snoozes(&snoozes).await
+    }
+
+    /// 当前配置下各 Claude Code 根目录的会话发现器
+    pub async fn discoveries(&self) -> Result<Vec<SessionDiscovery>> {
+        let roots = {
+            let config = self.config.read().await;
+            MonitorConfig::from_settings(&config.settings).roots
+        };
+        SessionDiscovery::for_roots(&roots)
+    }
+
+    /// 当前配置下所有 Claude Code 根目录的 projects 目录
+    pub async fn claude_projects_dirs(&self) -> Result<Vec<PathBuf>> {
+        projects_dirs(&*self.config.read().await)
+    }
+
+    /// 将当前配置热应用到运行中的组件
+    pub async fn apply_config(&self) -> Result<()> {
+        let monitor_config = {
+            let config = self.config.read().await;
+            MonitorConfig::from_settings(&config.settings)
+        };
+        self.monitor.reconfigure(monitor_config).await?;
+
+        // 根目录变化时全文索引切换到新的 projects 目录
+        let dirs = self.claude_projects_dirs().await?;
+        self.search_dirs.send_if_modified(|current| {
+            if *current == dirs {
+                return false;
+            }
+            *current = dirs;
+            true
+        });
+        Ok(())
+    }
+}
+
+/// 配置中各 Claude Code 根目录的 projects 目录
+fn projects_dirs(config: &AppConfig) -> Result<Vec<PathBuf>> {
+    let roots = MonitorConfig::from_settings(&config.settings).roots;
+    Ok(SessionDiscovery::for_roots(&roots)?
+        .into_iter()
+        .map(|discovery| discovery.projects_dir)
+        .collect())
 }
@@ -0,0 +1,254 @@
+//! 崩溃安全的 JSON 文件读写
+//!
+//! 写入先落到同目录的临时文件并 fsync，再重命名覆盖目标文件，中途崩溃不会截断原文件；
+//! 覆盖前把上一版保留为 `.bak`，读取时文件损坏则从中恢复。
+//! 写入方通过 `.lock` 文件上的咨询锁串行化，同一应用的多个命令与多个应用实例都不会交错写入。
+
+use crate::error::{AppError, Result};
+use fs2::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 文件的咨询锁，释放时自动解锁
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    /// 获取目标文件的排他锁，其他持有者释放前一直等待
+    pub async fn acquire(path: &Path) -> Result<Self> {
+        let lock_path = sibling(path, "lock");
+        tokio::task::spawn_blocking(move || -> Result<Self> {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&lock_path)?;
+            file.lock_exclusive()?;
+            Ok(Self { file })
+        })
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+/// 原子写入 JSON 文件
+///
+/// 调用方需持有该文件的 [`FileLock`]。
+pub async fn write_json<T: serde::Serialize>(path: &Path, data: &T) -> Result<()> {
+    let json =
+        serde_json::to_string_pretty(data).map_err(|e| AppError::Serialization(e.to_string()))?;
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || replace_file(&path, json.as_bytes(), true))
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+        .map_err(|e| AppError::StorageError(format!("写入文件失败: {}", e)))
+}
+
+/// 读取 JSON 文件，文件不存在时返回 None
+///
+/// 文件无法解析时获取该文件的 [`FileLock`] 后再按 [`read_json_locked`] 读取与恢复，
+/// 不会与正在进行的写入交错。
+pub async fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let content = match read_content(path).await? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+    if let Ok(data) = serde_json::from_str(&content) {
+        return Ok(Some(data));
+    }
+
+    let _lock = FileLock::acquire(path).await?;
+    read_json_locked(path).await
+}
+
+/// 读取 JSON 文件，文件不存在时返回 None
+///
+/// 调用方需持有该文件的 [`FileLock`]。
+/// 文件无法解析时从 `.bak` 恢复：损坏的文件另存为 `.corrupt`，备份写回原路径。
+/// 没有可用备份时返回序列化错误，原文件保持不变。
+pub async fn read_json_locked<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    let content = match read_content(path).await? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+
+    let error = match serde_json::from_str(&content) {
+        Ok(data) => return Ok(Some(data)),
+        Err(e) => e,
+    };
+    tracing::warn!("文件已损坏 {}: {}", path.display(), error);
+
+    let backup_path = sibling(path, "bak");
+    let backup = match tokio::fs::read_to_string(&backup_path).await {
+        Ok(backup) => backup,
+        Err(_) => return Err(AppError::Serialization(error.to_string())),
+    };
+    let data = match serde_json::from_str(&backup) {
+        Ok(data) => data,
+        Err(_) => return Err(AppError::Serialization(error.to_string())),
+    };
+
+    let target = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        fs::write(sibling(&target, "corrupt"), content)?;
+        replace_file(&target, backup.as_bytes(), false)
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?
+    .map_err(|e| AppError::StorageError(format!("从备份恢复失败: {}", e)))?;
+
+    tracing::warn!("已从备份恢复 {}", path.display());
+    Ok(Some(data))
+}
+
+/// 读取文件内容，文件不存在时返回 None
+async fn read_content(path: &Path) -> Result<Option<String>> {
+    match tokio::fs::read_to_string(path).await {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::StorageError(format!("读取文件失败: {}", e))),
+    }
+}
+
+/// 通过临时文件替换目标文件
+///
+/// `keep_backup` 时先把当前版本复制为 `.bak`；当前版本无法解析时保留原有备份，
+/// 避免一次损坏的写入覆盖唯一可用的备份。
+fn replace_file(path: &Path, bytes: &[u8], keep_backup: bool) -> std::io::Result<()> {
+    let tmp_path = sibling(path, "tmp");
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+
+    if keep_backup && is_valid_json(path) {
+        fs::copy(path, sibling(path, "bak"))?;
+    }
+    fs::rename(&tmp_path, path)?;
+
+    // 目录项也需落盘，否则崩溃后重命名可能丢失
+    #[cfg(unix)]
+    {
+        if let Some(parent) = path.parent() {
+            File::open(parent)?.sync_all()?;
+        }
+    }
+    Ok(())
+}
+
+/// 文件存在且内容是合法 JSON
+fn is_valid_json(path: &Path) -> bool {
+    fs::read(path)
+        .is_ok_and(|bytes| serde_json::from_slice::<serde::de::IgnoredAny>(&bytes).is_ok())
+}
+
+/// 同目录下追加扩展名的文件路径，如 config.json -> config.json.bak
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_write_keeps_backup_and_read_recovers() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("queues.json");
+        assert!(read_json::<Vec<u32>>(&path).await.unwrap().is_none());
+
+        write_json(&path, &vec![1]).await.unwrap();
+        write_json(&path, &vec![1, 2]).await.unwrap();
+        assert!(!sibling(&path, "tmp").exists());
+        assert_eq!(
+            read_json::<Vec<u32>>(&path).await.unwrap(),
+            Some(vec![1, 2])
+        );
+
+        // 模拟旧版非原子写入导致的截断
+        std::fs::write(&path, "[1, 2").unwrap();
+        assert_eq!(read_json::<Vec<u32>>(&path).await.unwrap(), Some(vec![1]));
+        assert_eq!(
+            std::fs::read_to_string(sibling(&path, "corrupt")).unwrap(),
+            "[1, 2"
+        );
+        assert_eq!(read_json::<Vec<u32>>(&path).await.unwrap(), Some(vec![1]));
+
+        // 没有可用备份时报告错误
+        std::fs::write(&path, "{").unwrap();
+        std::fs::write(sibling(&path, "bak"), "{").unwrap();
+        assert!(matches!(
+            read_json::<Vec<u32>>(&path).await,
+            Err(AppError::Serialization(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_file_does_not_replace_backup() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("queues.json");
+
+        write_json(&path, &vec![1]).await.unwrap();
+        write_json(&path, &vec![1, 2]).await.unwrap();
+        std::fs::write(&path, "[1, 2").unwrap();
+        write_json(&path, &vec![3]).await.unwrap();
+
+        let backup = std::fs::read_to_string(sibling(&path, "bak")).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<u32>>(&backup).unwrap(), vec![1]);
+    }
+
+    #[tokio::test]
+    async fn test_recovery_waits_for_writer_lock() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.json");
+        write_json(&path, &vec![1]).await.unwrap();
+        write_json(&path, &vec![1, 2]).await.unwrap();
+
+        // 写入方持锁期间文件处于损坏状态，恢复需等待写入完成
+        let lock = FileLock::acquire(&path).await.unwrap();
+        std::fs::write(&path, "[1, 2").unwrap();
+        let reader = tokio::spawn({
+            let path = path.clone();
+            async move { read_json::<Vec<u32>>(&path).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!reader.is_finished());
+
+        write_json(&path, &vec![1, 2, 3]).await.unwrap();
+        drop(lock);
+        assert_eq!(reader.await.unwrap().unwrap(), Some(vec![1, 2, 3]));
+        assert!(!sibling(&path, "corrupt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_lock_serializes_holders() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("config.json");
+
+        let lock = FileLock::acquire(&path).await.unwrap();
+        let waiter = tokio::spawn({
+            let path = path.clone();
+            async move { FileLock::acquire(&path).await.map(|_| ()) }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!waiter.is_finished());
+
+        drop(lock);
+        waiter.await.unwrap().unwrap();
+    }
+}
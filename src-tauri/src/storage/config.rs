@@ -1,8 +1,9 @@
 //! 配置存储管理
 
 use crate::error::{AppError, Result};
+use super::atomic::{self, FileLock};
 use crate::models::AppConfig;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 
 pub struct ConfigStorage;
@@ -15,53 +16,53 @@ impl ConfigStorage {
     }
 
     /// 加载配置，如果不存在则创建默认配置
+    ///
+    /// 配置文件损坏时从上一版备份恢复。
     pub async fn load() -> Result<AppConfig> {
         let path = Self::config_path()?;
 
-        if !path.exists() {
-            let default_config = AppConfig::default();
-            Self::save(&default_config).await?;
-            return Ok(default_config);
+        match atomic::read_json(&path).await? {
+            Some(config) => Ok(config),
+            None => {
+                let default_config = AppConfig::default();
+                Self::save(&default_config).await?;
+                Ok(default_config)
+            }
         }
-
-        let content = fs::read_to_string(&path)
-            .await
-            .map_err(|e| AppError::StorageError(format!("读取配置失败: {}", e)))?;
-
-        let config: AppConfig = serde_json::from_str(&content)
-            .map_err(|e| AppError::Serialization(e.to_string()))?;
-
-        Ok(config)
     }
 
     /// 保存配置
     pub async fn save(config: &AppConfig) -> Result<()> {
         let path = Self::config_path()?;
+        Self::ensure_parent(&path).await?;
 
-        // 确保目录存在
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .await
-                .map_err(|e| AppError::StorageError(format!("创建配置目录失败: {}", e)))?;
-        }
-
-        let json = serde_json::to_string_pretty(config)
-            .map_err(|e| AppError::Serialization(e.to_string()))?;
-
-        fs::write(&path, json)
-            .await
-            .map_err(|e| AppError::StorageError(format!("保存配置失败: {}", e)))?;
-
-        Ok(())
+        let _lock = FileLock::acquire(&path).await?;
+        atomic::write_json(&path, config).await
     }
 
     /// 更新配置（部分更新）
+    ///
+    /// 读取到保存期间持有配置文件锁，并发的更新不会互相覆盖。
     pub async fn update<F>(f: F) -> Result<()>
     where
         F: FnOnce(&mut AppConfig),
     {
-        let mut config = Self::load().await?;
+        let path = Self::config_path()?;
+        Self::ensure_parent(&path).await?;
+
+        let _lock = FileLock::acquire(&path).await?;
+        let mut config = atomic::read_json_locked(&path).await?.unwrap_or_default();
         f(&mut config);
-        Self::save(&config).await
+        atomic::write_json(&path, &config).await
+    }
+
+    /// 确保配置目录存在
+    async fn ensure_parent(path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::StorageError(format!("创建配置目录失败: {}", e)))?;
+        }
+        Ok(())
     }
 }
@@ -0,0 +1,417 @@
+//! SQLite 数据库
+//!
+//! 打开 ~/.codeagent/codeagent.db 并按 `user_version` 依次执行迁移。
+//! 会话以 JSON 保存在 `data` 列中，常用于筛选的字段另存为独立列。
+
+use crate::error::{AppError, Result};
+use crate::models::{
+    Message, ProcessInfo, Project, Session, SessionStats, SessionStatus, StatusTransition,
+};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use std::path::Path;
+use std::time::Duration;
+
+/// 数据库文件名
+pub const DB_FILE: &str = "codeagent.db";
+
+/// 其他进程持有写锁时的最长等待时间
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 迁移脚本，第 N 项把数据库从版本 N 升级到 N+1，只能追加不能修改
+const MIGRATIONS: &[&str] = &[
+    // v1: 会话、项目、会话详情与消息
+    r#"
+    CREATE TABLE sessions (
+        id TEXT PRIMARY KEY,
+        project_path TEXT NOT NULL,
+        status TEXT NOT NULL,
+        is_archived INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL,
+        last_active_at TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_sessions_project ON sessions (project_path);
+
+    CREATE TABLE projects (
+        path TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        last_accessed_at TEXT NOT NULL,
+        session_count INTEGER NOT NULL DEFAULT 0,
+        config TEXT
+    );
+
+    CREATE TABLE session_details (
+        session_id TEXT PRIMARY KEY,
+        process_info TEXT,
+        stats TEXT NOT NULL
+    );
+
+    CREATE TABLE messages (
+        session_id TEXT NOT NULL,
+        seq INTEGER NOT NULL,
+        id TEXT NOT NULL,
+        role TEXT NOT NULL,
+        content TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        metadata TEXT,
+        PRIMARY KEY (session_id, seq)
+    );
+    "#,
+    // v2: 状态变化记录与元数据
+    r#"
+    CREATE TABLE transitions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        session_id TEXT NOT NULL,
+        old_status TEXT NOT NULL,
+        new_status TEXT NOT NULL,
+        changed_at TEXT NOT NULL
+    );
+    CREATE INDEX idx_transitions_session ON transitions (session_id, changed_at);
+
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    "#,
+];
+
+/// 打开数据库并执行未完成的迁移
+pub fn open(path: &Path) -> Result<Connection> {
+    let mut conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+/// 当前数据库结构版本
+pub fn schema_version(conn: &Connection) -> Result<usize> {
+    let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    Ok(version as usize)
+}
+
+/// 依次执行尚未执行的迁移，每个迁移在独立事务中完成
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    if current > MIGRATIONS.len() {
+        return Err(AppError::StorageError(format!(
+            "数据库版本 {} 高于当前程序支持的版本 {}",
+            current,
+            MIGRATIONS.len()
+        )));
+    }
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", (index + 1) as i64)?;
+        tx.commit()?;
+        tracing::info!("数据库已迁移到版本 {}", index + 1);
+    }
+    Ok(())
+}
+
+/// 开始写事务
+///
+/// 事务开始时即获取写锁，另一个应用实例的读-改-写会等待（最长 [`BUSY_TIMEOUT`]），
+/// 不会在读取后升级写锁时失败。
+pub fn begin_write(conn: &mut Connection) -> Result<Transaction<'_>> {
+    Ok(conn.transaction_with_behavior(TransactionBehavior::Immediate)?)
+}
+
+/// 读取元数据
+pub fn get_meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+            row.get(0)
+        })
+        .optional()?)
+}
+
+/// 写入元数据
+pub fn set_meta(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// 状态的存储名称（与序列化格式一致，如 waiting_input）
+pub fn status_name(status: SessionStatus) -> String {
+    serde_json::to_value(status)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// 解析存储的状态名称，无法识别时为 Unknown
+pub fn parse_status(name: &str) -> SessionStatus {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).unwrap_or_default()
+}
+
+/// 解析存储的 RFC3339 时间
+pub fn parse_time(value: &str) -> Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+/// 读取单个会话
+pub fn get_session(conn: &Connection, id: &str) -> Result<Option<Session>> {
+    let data: Option<String> = conn
+        .query_row("SELECT data FROM sessions WHERE id = ?1", [id], |row| {
+            row.get(0)
+        })
+        .optional()?;
+    data.map(|data| serde_json::from_str(&data).map_err(AppError::from))
+        .transpose()
+}
+
+/// 按写入顺序读取所有会话
+///
+/// 无法解析的会话跳过并记录警告，单条损坏的记录不影响整个列表。
+pub fn list_sessions(conn: &Connection) -> Result<Vec<Session>> {
+    let mut stmt = conn.prepare("SELECT id, data FROM sessions ORDER BY rowid")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+
+    let mut sessions = Vec::new();
+    for row in rows {
+        let (id, data) = row?;
+        match serde_json::from_str(&data) {
+            Ok(session) => sessions.push(session),
+            Err(e) => tracing::warn!("跳过无法解析的会话记录 {}: {}", id, e),
+        }
+    }
+    Ok(sessions)
+}
+
+/// 插入或更新会话，已有会话保留原有的写入顺序
+pub fn upsert_session(conn: &Connection, session: &Session) -> Result<()> {
+    let data = serde_json::to_string(session)?;
+    conn.execute(
+        "INSERT INTO sessions (id, project_path, status, is_archived, created_at, last_active_at, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(id) DO UPDATE SET
+             project_path = excluded.project_path,
+             status = excluded.status,
+             is_archived = excluded.is_archived,
+             created_at = excluded.created_at,
+             last_active_at = excluded.last_active_at,
+             data = excluded.data",
+        params![
+            session.id,
+            session.project_path,
+            status_name(session.status),
+            session.is_archived,
+            session.created_at.to_rfc3339(),
+            session.last_active_at.to_rfc3339(),
+            data,
+        ],
+    )?;
+    Ok(())
+}
+
+/// 按写入顺序读取所有项目
+pub fn list_projects(conn: &Connection) -> Result<Vec<Project>> {
+    let mut stmt = conn.prepare(
+        "SELECT path, name, last_accessed_at, session_count, config FROM projects ORDER BY rowid",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, u32>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+
+    let mut projects = Vec::new();
+    for row in rows {
+        let (path, name, last_accessed_at, session_count, config) = row?;
+        projects.push(Project {
+            path,
+            name,
+            last_accessed_at: parse_time(&last_accessed_at)?,
+            session_count,
+            config: config
+                .map(|config| serde_json::from_str(&config))
+                .transpose()?,
+        });
+    }
+    Ok(projects)
+}
+
+/// 插入或更新项目
+pub fn upsert_project(conn: &Connection, project: &Project) -> Result<()> {
+    let config = project
+        .config
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+    conn.execute(
+        "INSERT INTO projects (path, name, last_accessed_at, session_count, config)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT(path) DO UPDATE SET
+             name = excluded.name,
+             last_accessed_at = excluded.last_accessed_at,
+             session_count = excluded.session_count,
+             config = excluded.config",
+        params![
+            project.path,
+            project.name,
+            project.last_accessed_at.to_rfc3339(),
+            project.session_count,
+            config,
+        ],
+    )?;
+    Ok(())
+}
+
+/// 按顺序读取会话的消息
+pub fn list_messages(conn: &Connection, session_id: &str) -> Result<Vec<Message>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, role, content, timestamp, metadata FROM messages
+         WHERE session_id = ?1 ORDER BY seq",
+    )?;
+    let rows = stmt.query_map([session_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, String>(3)?,
+            row.get::<_, Option<String>>(4)?,
+        ))
+    })?;
+
+    let mut messages = Vec::new();
+    for row in rows {
+        let (id, role, content, timestamp, metadata) = row?;
+        messages.push(Message {
+            id,
+            role: serde_json::from_value(serde_json::Value::String(role))?,
+            content,
+            timestamp: parse_time(&timestamp)?,
+            metadata: metadata
+                .map(|metadata| serde_json::from_str(&metadata))
+                .transpose()?,
+        });
+    }
+    Ok(messages)
+}
+
+/// 替换会话的全部消息
+pub fn replace_messages(conn: &Connection, session_id: &str, messages: &[Message]) -> Result<()> {
+    conn.execute("DELETE FROM messages WHERE session_id = ?1", [session_id])?;
+
+    let mut stmt = conn.prepare(
+        "INSERT INTO messages (session_id, seq, id, role, content, timestamp, metadata)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    for (seq, message) in messages.iter().enumerate() {
+        let role = serde_json::to_value(message.role)?;
+        let metadata = message
+            .metadata
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        stmt.execute(params![
+            session_id,
+            seq as i64,
+            message.id,
+            role.as_str().unwrap_or_default(),
+            message.content,
+            message.timestamp.to_rfc3339(),
+            metadata,
+        ])?;
+    }
+    Ok(())
+}
+
+/// 读取会话详情中的进程信息与统计
+pub fn get_detail(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<Option<(Option<ProcessInfo>, SessionStats)>> {
+    let row: Option<(Option<String>, String)> = conn
+        .query_row(
+            "SELECT process_info, stats FROM session_details WHERE session_id = ?1",
+            [session_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    row.map(|(process_info, stats)| -> Result<_> {
+        let process_info = process_info
+            .map(|info| serde_json::from_str(&info))
+            .transpose()?;
+        Ok((process_info, serde_json::from_str(&stats)?))
+    })
+    .transpose()
+}
+
+/// 插入或更新会话详情中的进程信息与统计
+pub fn upsert_detail(
+    conn: &Connection,
+    session_id: &str,
+    process_info: Option<&ProcessInfo>,
+    stats: &SessionStats,
+) -> Result<()> {
+    let process_info = process_info.map(serde_json::to_string).transpose()?;
+    conn.execute(
+        "INSERT INTO session_details (session_id, process_info, stats)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(session_id) DO UPDATE SET
+             process_info = excluded.process_info,
+             stats = excluded.stats",
+        params![session_id, process_info, serde_json::to_string(stats)?],
+    )?;
+    Ok(())
+}
+
+/// 记录一次状态变化
+pub fn insert_transition(conn: &Connection, transition: &StatusTransition) -> Result<()> {
+    conn.execute(
+        "INSERT INTO transitions (session_id, old_status, new_status, changed_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![
+            transition.session_id,
+            status_name(transition.old_status),
+            status_name(transition.new_status),
+            transition.changed_at.to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// 按时间顺序读取会话的状态变化
+pub fn list_transitions(conn: &Connection, session_id: &str) -> Result<Vec<StatusTransition>> {
+    let mut stmt = conn.prepare(
+        "SELECT old_status, new_status, changed_at FROM transitions
+         WHERE session_id = ?1 ORDER BY changed_at, id",
+    )?;
+    let rows = stmt.query_map([session_id], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+
+    let mut transitions = Vec::new();
+    for row in rows {
+        let (old_status, new_status, changed_at) = row?;
+        transitions.push(StatusTransition {
+            session_id: session_id.to_string(),
+            old_status: parse_status(&old_status),
+            new_status: parse_status(&new_status),
+            changed_at: parse_time(&changed_at)?,
+        });
+    }
+    Ok(transitions)
+}
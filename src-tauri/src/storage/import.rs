@@ -0,0 +1,116 @@
+//! 旧版 JSON 存储导入
+//!
+//! 首次打开数据库时把 sessions/index.json、sessions/<id>.json 与 projects.json
+//! 导入 SQLite。导入完成后在 meta 表中记录，原文件保留不动。
+
+use super::{atomic, db};
+use crate::error::Result;
+use crate::models::{Project, Session, SessionDetail};
+use rusqlite::Connection;
+use std::path::Path;
+
+/// 记录导入完成的元数据键
+const IMPORTED_KEY: &str = "legacy_json_imported_at";
+
+/// 从旧版 JSON 文件读取的数据
+#[derive(Debug, Default)]
+pub struct LegacyData {
+    pub sessions: Vec<Session>,
+    pub details: Vec<SessionDetail>,
+    pub projects: Vec<Project>,
+}
+
+/// 是否已完成导入
+pub fn is_imported(conn: &Connection) -> Result<bool> {
+    Ok(db::get_meta(conn, IMPORTED_KEY)?.is_some())
+}
+
+/// 读取数据目录下的旧版 JSON 文件
+///
+/// 无法解析的文件跳过并记录警告，不阻止应用启动。
+pub async fn read_legacy(data_dir: &Path) -> LegacyData {
+    let mut legacy = LegacyData::default();
+    let sessions_dir = data_dir.join("sessions");
+
+    if let Some(sessions) = read_file(&sessions_dir.join("index.json")).await {
+        legacy.sessions = sessions;
+    }
+    if let Some(projects) = read_file(&data_dir.join("projects.json")).await {
+        legacy.projects = projects;
+    }
+
+    let mut entries = match tokio::fs::read_dir(&sessions_dir).await {
+        Ok(entries) => entries,
+        Err(_) => return legacy,
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_detail = path.extension().map(|ext| ext == "json").unwrap_or(false)
+            && path
+                .file_stem()
+                .map(|stem| stem != "index")
+                .unwrap_or(false);
+        if is_detail {
+            if let Some(detail) = read_file(&path).await {
+                legacy.details.push(detail);
+            }
+        }
+    }
+    legacy
+}
+
+/// 把旧版数据写入数据库并记录导入完成
+///
+/// 数据库中已有的会话和项目不会被覆盖。
+pub fn import(conn: &mut Connection, legacy: &LegacyData) -> Result<()> {
+    let tx = db::begin_write(conn)?;
+
+    for session in &legacy.sessions {
+        if db::get_session(&tx, &session.id)?.is_none() {
+            db::upsert_session(&tx, session)?;
+        }
+    }
+
+    for detail in &legacy.details {
+        let id = &detail.session.id;
+        if db::get_session(&tx, id)?.is_none() {
+            db::upsert_session(&tx, &detail.session)?;
+        }
+        if db::get_detail(&tx, id)?.is_none() {
+            db::upsert_detail(&tx, id, detail.process_info.as_ref(), &detail.stats)?;
+            db::replace_messages(&tx, id, &detail.messages)?;
+        }
+    }
+
+    let existing: Vec<String> = db::list_projects(&tx)?
+        .into_iter()
+        .map(|project| project.path)
+        .collect();
+    for project in &legacy.projects {
+        if !existing.contains(&project.path) {
+            db::upsert_project(&tx, project)?;
+        }
+    }
+
+    db::set_meta(&tx, IMPORTED_KEY, &chrono::Utc::now().to_rfc3339())?;
+    tx.commit()?;
+
+    tracing::info!(
+        "已导入旧版 JSON 存储: {} 个会话, {} 个会话详情, {} 个项目",
+        legacy.sessions.len(),
+        legacy.details.len(),
+        legacy.projects.len()
+    );
+    Ok(())
+}
+
+/// 读取并解析 JSON 文件（损坏时尝试从备份恢复），文件不存在或无法恢复时返回 None
+async fn read_file<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    match atomic::read_json(path).await {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("跳过无法解析的旧版存储文件 {}: {}", path.display(), e);
+            None
+        }
+    }
+}
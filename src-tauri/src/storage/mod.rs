@@ -1,34 +1,70 @@
-//! 本地 JSON 文件存储模块
+//! 本地存储模块
 //!
-//! 提供会话、项目、配置的持久化存储功能
+//! 会话、项目、状态变化与消息保存在 SQLite 数据库中，其余数据保存为 JSON 文件
 //! 数据目录: ~/.codeagent/
 
 use crate::error::{AppError, Result};
-use crate::models::{Project, Session, SessionDetail};
+use crate::models::{Project, Session, SessionDetail, StatusTransition};
+use crate::queue::PromptQueues;
+use crate::workflow::Workflow;
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
 
+pub mod atomic;
 pub mod config;
+pub mod db;
+pub mod import;
+pub mod recorder;
 
+pub use atomic::FileLock;
 pub use config::ConfigStorage;
 
 /// 存储管理器
+///
+/// 数据库操作在阻塞线程池中执行，不占用异步运行时的工作线程；
+/// 读-改-写在同一事务内完成，多个命令或多个应用实例不会互相覆盖。
 #[derive(Debug, Clone)]
 pub struct Storage {
     /// 数据根目录
     data_dir: PathBuf,
+    /// 数据库连接
+    db: Arc<Mutex<Connection>>,
 }
 
 impl Storage {
     /// 创建存储管理器实例
     pub async fn new() -> Result<Self> {
-        let data_dir = Self::data_dir()?;
+        Self::open(Self::data_dir()?).await
+    }
+
+    /// 在指定目录创建存储管理器实例
+    ///
+    /// 首次打开时导入旧版 JSON 存储中的会话与项目。
+    pub async fn open(data_dir: PathBuf) -> Result<Self> {
         Self::ensure_dir(&data_dir).await?;
         Self::ensure_dir(&data_dir.join("sessions")).await?;
         Self::ensure_dir(&data_dir.join("cache")).await?;
 
-        Ok(Self { data_dir })
+        let db_path = data_dir.join(db::DB_FILE);
+        let conn = tokio::task::spawn_blocking(move || db::open(&db_path))
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))??;
+        let storage = Self {
+            data_dir,
+            db: Arc::new(Mutex::new(conn)),
+        };
+
+        if !storage.with_conn(|conn| import::is_imported(conn)).await? {
+            let legacy = import::read_legacy(&storage.data_dir).await;
+            storage
+                .with_conn(move |conn| import::import(conn, &legacy))
+                .await?;
+        }
+        Ok(storage)
     }
 
     /// 获取数据目录路径 (~/.codeagent/)
@@ -38,6 +74,11 @@ impl Storage {
             .ok_or_else(|| AppError::StorageError("无法获取用户主目录".to_string()))
     }
 
+    /// 日志压缩副本目录 (~/.codeagent/transcripts/)
+    pub fn transcripts_dir(&self) -> PathBuf {
+        self.data_dir.join("transcripts")
+    }
+
     /// 确保目录存在
     async fn ensure_dir(path: &Path) -> Result<()> {
         if !path.exists() {
@@ -48,92 +89,262 @@ impl Storage {
         Ok(())
     }
 
-    /// 保存会话列表索引
+    /// 在阻塞线程池中执行数据库操作
+    ///
+    /// rusqlite 调用是同步的，写锁冲突时还会按 busy_timeout 等待，不能占用异步运行时的工作线程。
+    async fn with_conn<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let db = self.db.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = db
+                .lock()
+                .map_err(|_| AppError::StorageError("数据库连接已损坏".to_string()))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| AppError::Internal(e.to_string()))?
+    }
+
+    /// 保存会话列表（替换全部会话）
     pub async fn save_session_index(&self, sessions: &[Session]) -> Result<()> {
-        let path = self.data_dir.join("sessions").join("index.json");
-        let sessions_vec: Vec<Session> = sessions.to_vec();
-        self.write_json(path, &sessions_vec).await
+        let sessions = sessions.to_vec();
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+            tx.execute("DELETE FROM sessions", [])?;
+            for session in &sessions {
+                db::upsert_session(&tx, session)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
     }
 
-    /// 读取会话列表索引
+    /// 读取会话列表
     pub async fn load_session_index(&self) -> Result<Vec<Session>> {
-        let path = self.data_dir.join("sessions").join("index.json");
-        if !path.exists() {
-            return Ok(vec![]);
-        }
-        self.read_json(path).await
+        self.with_conn(|conn| db::list_sessions(conn)).await
     }
 
     /// 保存会话详情
     pub async fn save_session_detail(&self, detail: &SessionDetail) -> Result<()> {
-        let filename = format!("{}.json", detail.session.id);
-        let path = self.data_dir.join("sessions").join(filename);
-        self.write_json(path, detail).await
+        let detail = detail.clone();
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+            let id = &detail.session.id;
+            db::upsert_session(&tx, &detail.session)?;
+            db::upsert_detail(&tx, id, detail.process_info.as_ref(), &detail.stats)?;
+            db::replace_messages(&tx, id, &detail.messages)?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
     }
 
     /// 读取会话详情
     pub async fn load_session_detail(&self, session_id: &str) -> Result<SessionDetail> {
-        let filename = format!("{}.json", session_id);
-        let path = self.data_dir.join("sessions").join(filename);
-        self.read_json(path).await
+        let session_id = session_id.to_string();
+        self.with_conn(move |conn| {
+            let session = db::get_session(conn, &session_id)?
+                .ok_or_else(|| AppError::SessionNotFound(session_id.clone()))?;
+            let (process_info, stats) = db::get_detail(conn, &session_id)?
+                .ok_or_else(|| AppError::StorageError("会话详情不存在".to_string()))?;
+            let messages = db::list_messages(conn, &session_id)?;
+
+            Ok(SessionDetail {
+                session,
+                messages,
+                process_info,
+                stats,
+            })
+        })
+        .await
     }
 
-    /// 删除会话详情
+    /// 删除会话详情（保留会话本身）
     pub async fn delete_session_detail(&self, session_id: &str) -> Result<()> {
-        let filename = format!("{}.json", session_id);
-        let path = self.data_dir.join("sessions").join(filename);
-        fs::remove_file(&path)
+        let session_id = session_id.to_string();
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+            let deleted = tx.execute(
+                "DELETE FROM session_details WHERE session_id = ?1",
+                [&session_id],
+            )?;
+            if deleted == 0 {
+                return Err(AppError::StorageError("会话详情不存在".to_string()));
+            }
+            tx.execute("DELETE FROM messages WHERE session_id = ?1", [&session_id])?;
+            tx.commit()?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// 只保留最近活跃的 `keep` 个会话的详情、消息与状态变化记录，会话本身保留
+    ///
+    /// 返回被清理了数据的会话 ID。
+    pub async fn prune_session_data(&self, keep: usize) -> Result<Vec<String>> {
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+
+            let expired: Vec<String> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id FROM sessions ORDER BY last_active_at DESC LIMIT -1 OFFSET ?1",
+                )?;
+                let rows = stmt.query_map([keep as i64], |row| row.get(0))?;
+                rows.collect::<rusqlite::Result<_>>()?
+            };
+
+            let mut pruned = Vec::new();
+            for id in expired {
+                let mut deleted = 0;
+                for table in ["session_details", "messages", "transitions"] {
+                    deleted += tx.execute(
+                        &format!("DELETE FROM {} WHERE session_id = ?1", table),
+                        [&id],
+                    )?;
+                }
+                if deleted > 0 {
+                    pruned.push(id);
+                }
+            }
+
+            tx.commit()?;
+            Ok(pruned)
+        })
+        .await
+    }
+
+    /// 记录会话状态变化
+    pub async fn record_transition(&self, transition: &StatusTransition) -> Result<()> {
+        let transition = transition.clone();
+        self.with_conn(move |conn| db::insert_transition(conn, &transition))
             .await
-            .map_err(|e| AppError::StorageError(format!("删除文件失败: {}", e)))?;
-        Ok(())
     }
 
-    /// 保存项目列表
+    /// 按时间顺序读取会话的状态变化
+    pub async fn load_transitions(&self, session_id: &str) -> Result<Vec<StatusTransition>> {
+        let session_id = session_id.to_string();
+        self.with_conn(move |conn| db::list_transitions(conn, &session_id))
+            .await
+    }
+
+    /// 保存项目列表（替换全部项目）
     pub async fn save_projects(&self, projects: &[Project]) -> Result<()> {
-        let path = self.data_dir.join("projects.json");
-        let projects_vec: Vec<Project> = projects.to_vec();
-        self.write_json(path, &projects_vec).await
+        let projects = projects.to_vec();
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+            tx.execute("DELETE FROM projects", [])?;
+            for project in &projects {
+                db::upsert_project(&tx, project)?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+        .await
     }
 
     /// 读取项目列表
     pub async fn load_projects(&self) -> Result<Vec<Project>> {
-        let path = self.data_dir.join("projects.json");
+        self.with_conn(|conn| db::list_projects(conn)).await
+    }
+
+    /// 按路径读取项目
+    pub async fn get_project(&self, path: &str) -> Result<Option<Project>> {
+        let projects = self.load_projects().await?;
+        Ok(projects.into_iter().find(|p| p.path == path))
+    }
+
+    /// 修改项目并保存，项目不存在时先创建
+    pub async fn update_project<F>(&self, path: &str, f: F) -> Result<Project>
+    where
+        F: FnOnce(&mut Project) + Send + 'static,
+    {
+        let path = path.to_string();
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+
+            let existing = db::list_projects(&tx)?.into_iter().find(|p| p.path == path);
+            let mut project = existing.unwrap_or_else(|| {
+                let name = Path::new(&path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone());
+                Project {
+                    path: path.clone(),
+                    name,
+                    last_accessed_at: Utc::now(),
+                    session_count: 0,
+                    config: None,
+                }
+            });
+            f(&mut project);
+
+            db::upsert_project(&tx, &project)?;
+            tx.commit()?;
+            Ok(project)
+        })
+        .await
+    }
+
+    /// 保存会话暂停提醒表
+    pub async fn save_snoozes(&self, snoozes: &HashMap<String, DateTime<Utc>>) -> Result<()> {
+        let path = self.data_dir.join("snoozes.json");
+        self.write_json(path, snoozes).await
+    }
+
+    /// 读取会话暂停提醒表
+    pub async fn load_snoozes(&self) -> Result<HashMap<String, DateTime<Utc>>> {
+        let path = self.data_dir.join("snoozes.json");
         if !path.exists() {
-            return Ok(vec![]);
+            return Ok(HashMap::new());
         }
         self.read_json(path).await
     }
 
-    /// 写入 JSON 文件
-    async fn write_json<T: serde::Serialize>(&self, path: PathBuf, data: &T) -> Result<()> {
-        let json = serde_json::to_string_pretty(data)
-            .map_err(|e| AppError::Serialization(e.to_string()))?;
-
-        let mut file = fs::File::create(&path)
-            .await
-            .map_err(|e| AppError::StorageError(format!("创建文件失败: {}", e)))?;
+    /// 保存提示队列
+    pub async fn save_prompt_queues(&self, queues: &PromptQueues) -> Result<()> {
+        let path = self.data_dir.join("queues.json");
+        self.write_json(path, queues).await
+    }
 
-        file.write_all(json.as_bytes())
-            .await
-            .map_err(|e| AppError::StorageError(format!("写入文件失败: {}", e)))?;
+    /// 读取提示队列
+    pub async fn load_prompt_queues(&self) -> Result<PromptQueues> {
+        let path = self.data_dir.join("queues.json");
+        if !path.exists() {
+            return Ok(PromptQueues::new());
+        }
+        self.read_json(path).await
+    }
 
-        Ok(())
+    /// 保存工作流定义
+    pub async fn save_workflows(&self, workflows: &[Workflow]) -> Result<()> {
+        let path = self.data_dir.join("workflows.json");
+        self.write_json(path, &workflows).await
     }
 
-    /// 读取 JSON 文件
-    async fn read_json<T: serde::de::DeserializeOwned>(&self, path: PathBuf) -> Result<T> {
+    /// 读取工作流定义
+    pub async fn load_workflows(&self) -> Result<Vec<Workflow>> {
+        let path = self.data_dir.join("workflows.json");
         if !path.exists() {
-            return Err(AppError::StorageError("文件不存在".to_string()));
+            return Ok(vec![]);
         }
+        self.read_json(path).await
+    }
 
-        let content = fs::read_to_string(&path)
-            .await
-            .map_err(|e| AppError::StorageError(format!("读取文件失败: {}", e)))?;
-
-        let data = serde_json::from_str(&content)
-            .map_err(|e| AppError::Serialization(e.to_string()))?;
+    /// 写入 JSON 文件（原子替换并保留上一版备份）
+    async fn write_json<T: serde::Serialize>(&self, path: PathBuf, data: &T) -> Result<()> {
+        let _lock = FileLock::acquire(&path).await?;
+        atomic::write_json(&path, data).await
+    }
 
-        Ok(data)
+    /// 读取 JSON 文件，文件损坏时从备份恢复
+    async fn read_json<T: serde::de::DeserializeOwned>(&self, path: PathBuf) -> Result<T> {
+        atomic::read_json(&path)
+            .await?
+            .ok_or_else(|| AppError::StorageError("文件不存在".to_string()))
     }
 
     /// 获取所有活跃会话（未归档）
@@ -148,39 +359,94 @@ impl Storage {
         Ok(sessions.into_iter().filter(|s| s.is_archived).collect())
     }
 
-    /// 更新会话
+    /// 更新会话，不存在时新增
     pub async fn update_session(&self, updated: &Session) -> Result<()> {
-        let mut sessions = self.load_session_index().await?;
-
-        if let Some(index) = sessions.iter().position(|s| s.id == updated.id) {
-            sessions[index] = updated.clone();
-        } else {
-            sessions.push(updated.clone());
-        }
-
-        self.save_session_index(&sessions).await
+        let updated = updated.clone();
+        self.with_conn(move |conn| db::upsert_session(conn, &updated))
+            .await
     }
 
     /// 根据 ID 获取会话
     pub async fn get_session(&self, id: &str) -> Result<Session> {
-        let sessions = self.load_session_index().await?;
-        sessions
-            .into_iter()
-            .find(|s| s.id == id)
-            .ok_or_else(|| AppError::SessionNotFound(id.to_string()))
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            db::get_session(conn, &id)?.ok_or_else(|| AppError::SessionNotFound(id.clone()))
+        })
+        .await
+    }
+
+    /// 修改存储中的会话并保存，返回修改后的会话
+    pub async fn modify_session<F>(&self, id: &str, f: F) -> Result<Session>
+    where
+        F: FnOnce(&mut Session) + Send + 'static,
+    {
+        self.modify_session_or(id, None, f).await
+    }
+
+    /// 修改存储中的会话并保存，存储中没有时以 `fallback` 为基础写入
+    pub async fn modify_session_or<F>(
+        &self,
+        id: &str,
+        fallback: Option<Session>,
+        f: F,
+    ) -> Result<Session>
+    where
+        F: FnOnce(&mut Session) + Send + 'static,
+    {
+        let id = id.to_string();
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+            let mut session = db::get_session(&tx, &id)?
+                .or(fallback)
+                .ok_or_else(|| AppError::SessionNotFound(id.clone()))?;
+            f(&mut session);
+
+            db::upsert_session(&tx, &session)?;
+            tx.commit()?;
+            Ok(session)
+        })
+        .await
+    }
+
+    /// 记录监控发现的会话
+    ///
+    /// 新会话加入存储；已有会话更新为最新状态，保留用户的修改（归档、标记完成、重命名）。
+    /// 会话没有变化时不写入。
+    pub async fn record_sessions(&self, live: &[Session]) -> Result<()> {
+        let live = live.to_vec();
+        self.with_conn(move |conn| {
+            let tx = db::begin_write(conn)?;
+
+            for session in &live {
+                let mut merged = session.clone();
+                match db::get_session(&tx, &session.id)? {
+                    Some(stored) => {
+                        merged.apply_overrides(&stored);
+                        merged.created_at = merged.created_at.min(stored.created_at);
+                        if stored != merged {
+                            db::upsert_session(&tx, &merged)?;
+                        }
+                    }
+                    None => db::upsert_session(&tx, &merged)?,
+                }
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::{ProjectConfig, SessionStatus};
     use tempfile::TempDir;
 
     async fn create_test_storage() -> (Storage, TempDir) {
         let temp_dir = TempDir::new().unwrap();
-        let storage = Storage {
-            data_dir: temp_dir.path().to_path_buf(),
-        };
+        let storage = Storage::open(temp_dir.path().to_path_buf()).await.unwrap();
         (storage, temp_dir)
     }
 
@@ -200,4 +466,141 @@ mod tests {
         assert_eq!(loaded[0].title, "测试会话1");
         assert_eq!(loaded[1].title, "测试会话2");
     }
+
+    #[tokio::test]
+    async fn test_update_project_creates_and_persists() {
+        let temp = TempDir::new().unwrap();
+        let storage = Storage::open(temp.path().to_path_buf()).await.unwrap();
+
+        assert!(storage.get_project("/work/app").await.unwrap().is_none());
+        let project = storage
+            .update_project("/work/app", |project| {
+                project.session_count += 1;
+                project.config = Some(ProjectConfig {
+                    default_profile: Some("review".to_string()),
+                    ..ProjectConfig::default()
+                });
+            })
+            .await
+            .unwrap();
+        assert_eq!(project.name, "app");
+
+        storage
+            .update_project("/work/app", |project| project.session_count += 1)
+            .await
+            .unwrap();
+        let loaded = storage.get_project("/work/app").await.unwrap().unwrap();
+        assert_eq!(loaded.session_count, 2);
+        assert_eq!(
+            loaded.config.unwrap().default_profile.as_deref(),
+            Some("review")
+        );
+        assert_eq!(storage.load_projects().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_imports_legacy_json_once() {
+        let temp = TempDir::new().unwrap();
+        let sessions_dir = temp.path().join("sessions");
+        std::fs::create_dir_all(&sessions_dir).unwrap();
+
+        let indexed = Session::new("旧会话", "app", "/work/app");
+        let detailed = Session::new("有详情的会话", "app", "/work/app");
+        let detail = SessionDetail {
+            session: detailed.clone(),
+            messages: vec![crate::models::Message {
+                id: "msg-1".to_string(),
+                role: crate::models::MessageRole::User,
+                content: "修复登录".to_string(),
+                timestamp: Utc::now(),
+                metadata: None,
+            }],
+            process_info: None,
+            stats: crate::models::SessionStats {
+                message_count: 1,
+                total_tokens: None,
+                duration_secs: 60,
+            },
+        };
+        std::fs::write(
+            sessions_dir.join("index.json"),
+            serde_json::to_string(&vec![indexed.clone()]).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(
+            sessions_dir.join(format!("{}.json", detailed.id)),
+            serde_json::to_string(&detail).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(sessions_dir.join("broken.json"), "{ not json").unwrap();
+
+        let storage = Storage::open(temp.path().to_path_buf()).await.unwrap();
+        let sessions = storage.load_session_index().await.unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(storage.get_session(&indexed.id).await.unwrap(), indexed);
+        let loaded = storage.load_session_detail(&detailed.id).await.unwrap();
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.messages[0].content, "修复登录");
+        assert_eq!(loaded.stats.duration_secs, 60);
+
+        // 导入只执行一次，之后的修改不会被旧文件覆盖
+        storage
+            .modify_session(&indexed.id, |session| session.is_archived = true)
+            .await
+            .unwrap();
+        drop(storage);
+        let reopened = Storage::open(temp.path().to_path_buf()).await.unwrap();
+        assert!(reopened.get_session(&indexed.id).await.unwrap().is_archived);
+        assert_eq!(reopened.load_session_index().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_migrations_and_transitions() {
+        let (storage, _temp) = create_test_storage().await;
+        assert_eq!(db::schema_version(&storage.db.lock().unwrap()).unwrap(), 2);
+
+        let now = Utc::now();
+        for (offset, (old_status, new_status)) in [
+            (SessionStatus::Running, SessionStatus::WaitingInput),
+            (SessionStatus::WaitingInput, SessionStatus::Running),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            storage
+                .record_transition(&StatusTransition {
+                    session_id: "sess_1".to_string(),
+                    old_status,
+                    new_status,
+                    changed_at: now + chrono::Duration::seconds(offset as i64),
+                })
+                .await
+                .unwrap();
+        }
+
+        let transitions = storage.load_transitions("sess_1").await.unwrap();
+        assert_eq!(transitions.len(), 2);
+        assert_eq!(transitions[0].new_status, SessionStatus::WaitingInput);
+        assert_eq!(transitions[1].new_status, SessionStatus::Running);
+        assert!(storage.load_transitions("sess_2").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_session_row_is_skipped() {
+        let (storage, _temp) = create_test_storage().await;
+        let session = Session::new("正常会话", "app", "/work/app");
+        storage.update_session(&session).await.unwrap();
+        storage
+            .db
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO sessions (id, project_path, status, is_archived, created_at, last_active_at, data)
+                 VALUES ('sess_bad', '/work/app', 'running', 0, '', '', '{ truncated')",
+                [],
+            )
+            .unwrap();
+
+        assert_eq!(storage.load_session_index().await.unwrap(), vec![session]);
+    }
 }
@@ -0,0 +1,151 @@
+//! 会话历史记录
+//!
+//! 订阅监控事件，把发现的会话、状态变化与结束时间写入存储。
+//! 日志过期后监控不再发现的会话仍保留在存储中，作为会话历史。
+
+use super::Storage;
+use crate::error::{AppError, Result};
+use crate::models::{SessionStatus, StatusTransition};
+use crate::monitor::event_bus::EventSubscription;
+use crate::monitor::MonitorEvent;
+use chrono::Utc;
+use std::sync::Arc;
+
+/// 把一个监控事件写入存储
+pub async fn record_event(storage: &Storage, event: &MonitorEvent) -> Result<()> {
+    match event {
+        MonitorEvent::Snapshot { sessions } => storage.record_sessions(sessions).await,
+        MonitorEvent::SessionDiscovered { session } => {
+            storage.record_sessions(std::slice::from_ref(session)).await
+        }
+        MonitorEvent::StatusChanged {
+            session_id,
+            old_status,
+            new_status,
+        } => {
+            storage
+                .record_transition(&StatusTransition {
+                    session_id: session_id.clone(),
+                    old_status: *old_status,
+                    new_status: *new_status,
+                    changed_at: Utc::now(),
+                })
+                .await?;
+            if *new_status == SessionStatus::Completed {
+                return Ok(());
+            }
+
+            // 用户标记完成后会话重新活跃，标记失效（快照随后带来最新状态）
+            let marked = match storage.get_session(session_id).await {
+                Ok(session) => session.marked_completed_at.is_some(),
+                Err(AppError::SessionNotFound(_)) => false,
+                Err(e) => return Err(e),
+            };
+            if marked {
+                let new_status = *new_status;
+                storage
+                    .modify_session(session_id, move |session| {
+                        session.marked_completed_at = None;
+                        session.status = new_status;
+                    })
+                    .await?;
+            }
+            Ok(())
+        }
+        MonitorEvent::SessionEnded { session_id } => {
+            let result = storage
+                .modify_session(session_id, |session| {
+                    session.status = SessionStatus::Completed;
+                    session.ended_at = Some(Utc::now());
+                })
+                .await;
+            match result {
+                Ok(_) | Err(AppError::SessionNotFound(_)) => Ok(()),
+                Err(e) => Err(e),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 启动会话历史记录任务
+pub fn spawn_recorder(storage: Arc<Storage>, mut events: EventSubscription) {
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("会话历史记录任务已启动");
+
+        while let Some(event) = events.recv().await {
+            if let Err(e) = record_event(&storage, &event).await {
+                tracing::warn!("记录会话历史失败 ({}): {}", event.event_type(), e);
+            }
+        }
+
+        tracing::info!("会话历史记录任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Session;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_live_sessions_persist_with_user_overrides() {
+        let temp = TempDir::new().unwrap();
+        let storage = Storage::open(temp.path().to_path_buf()).await.unwrap();
+
+        let mut live = Session::new("app | 修复登录", "app", "/work/app");
+        live.status = SessionStatus::WaitingInput;
+        let snapshot = MonitorEvent::Snapshot {
+            sessions: vec![live.clone()],
+        };
+        record_event(&storage, &snapshot).await.unwrap();
+        assert_eq!(storage.get_session(&live.id).await.unwrap(), live);
+
+        // 用户重命名并标记完成后，新的快照不覆盖这些修改
+        storage
+            .modify_session(&live.id, |session| {
+                session.custom_title = Some("登录修复".to_string());
+                session.status = SessionStatus::Completed;
+                session.marked_completed_at = Some(Utc::now());
+                session.is_archived = true;
+            })
+            .await
+            .unwrap();
+        record_event(&storage, &snapshot).await.unwrap();
+        let stored = storage.get_session(&live.id).await.unwrap();
+        assert_eq!(stored.title, "登录修复");
+        assert_eq!(stored.status, SessionStatus::Completed);
+        assert!(stored.is_archived);
+
+        // 会话重新活跃后完成标记失效
+        let resumed = MonitorEvent::StatusChanged {
+            session_id: live.id.clone(),
+            old_status: SessionStatus::WaitingInput,
+            new_status: SessionStatus::Running,
+        };
+        record_event(&storage, &resumed).await.unwrap();
+        let stored = storage.get_session(&live.id).await.unwrap();
+        assert_eq!(stored.status, SessionStatus::Running);
+        assert!(stored.marked_completed_at.is_none());
+        let transitions = storage.load_transitions(&live.id).await.unwrap();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(transitions[0].old_status, SessionStatus::WaitingInput);
+
+        let ended = MonitorEvent::SessionEnded {
+            session_id: live.id.clone(),
+        };
+        record_event(&storage, &ended).await.unwrap();
+        let stored = storage.get_session(&live.id).await.unwrap();
+        assert_eq!(stored.status, SessionStatus::Completed);
+        assert!(stored.ended_at.is_some());
+        assert_eq!(stored.title, "登录修复");
+
+        // 未记录过的会话结束时忽略
+        let unknown = MonitorEvent::SessionEnded {
+            session_id: "sess_missing".to_string(),
+        };
+        record_event(&storage, &unknown).await.unwrap();
+        assert_eq!(storage.load_session_index().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,330 @@
+//! 终端启动模块
+//!
+//! 在用户的终端中打开项目目录，可选执行 `claude --resume <id>`。
+//!
+//! 所有参数都直接传给终端程序，不经过 shell 拼接，路径中的引号、空格等字符不会破坏命令。
+//! 查找顺序：
+//! 1. 配置中的自定义命令模板
+//! 2. macOS 使用 Terminal.app（osascript 通过 argv 传参）
+//! 3. Linux 依次检测 gnome-terminal、konsole、kitty、alacritty、wezterm、xterm
+
+use crate::error::{AppError, Result};
+use crate::monitor::history;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// 自动检测的 Linux 终端（按优先级）
+const LINUX_TERMINALS: &[&str] = &[
+    "gnome-terminal",
+    "konsole",
+    "kitty",
+    "alacritty",
+    "wezterm",
+    "xterm",
+];
+
+/// 待执行的终端命令
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchCommand {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+/// 在终端中打开项目目录
+///
+/// `template` 为配置中的自定义命令模板，`resume` 为要恢复的 Claude Code 会话 ID。
+pub fn launch(project_path: &str, resume: Option<&str>, template: Option<&str>) -> Result<()> {
+    let cwd = PathBuf::from(project_path);
+    if !cwd.is_dir() {
+        return Err(AppError::ProjectNotFound(project_path.to_string()));
+    }
+
+    let command = claude_command(resume)?;
+    let launch = match template.filter(|t| !t.trim().is_empty()) {
+        Some(template) => from_template(template, &cwd, &command)?,
+        None => detect(&cwd, &command)?,
+    };
+
+    tracing::info!("打开终端: {} {:?}", launch.program, launch.args);
+
+    let mut process = Command::new(&launch.program);
+    process
+        .args(&launch.args)
+        .current_dir(&cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    // osascript 很快返回，等待它以便报告错误；终端程序可能一直运行，不等待
+    if launch.program == "osascript" {
+        let status = process
+            .status()
+            .map_err(|e| AppError::ProcessError(format!("执行 osascript 失败: {}", e)))?;
+        if !status.success() {
+            return Err(AppError::ProcessError("打开终端失败".to_string()));
+        }
+    } else {
+        process
+            .spawn()
+            .map_err(|e| AppError::ProcessError(format!("启动 {} 失败: {}", launch.program, e)))?;
+    }
+
+    Ok(())
+}
+
+/// 终端中要执行的命令（为空表示只打开 shell）
+fn claude_command(resume: Option<&str>) -> Result<Vec<String>> {
+    match resume {
+        Some(session_id) if !history::is_valid_session_id(session_id) => Err(
+            AppError::InvalidInput(format!("会话 ID 无效: {}", session_id)),
+        ),
+        Some(session_id) => Ok(vec![
+            "claude".to_string(),
+            "--resume".to_string(),
+            session_id.to_string(),
+        ]),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// 按自定义模板构建命令
+///
+/// 模板先按空白拆分（支持单双引号），再替换占位符，因此替换值不会被再次拆分。
+/// 占位符必须单独成词，嵌在词中（如 `-c 'cd {cwd}'`）会被拒绝，
+/// 以免路径被终端交给 shell 解释：
+/// - `{cwd}`: 项目目录，作为一个参数
+/// - `{command}`: 展开为多个参数
+pub fn from_template(template: &str, cwd: &Path, command: &[String]) -> Result<LaunchCommand> {
+    let mut args = Vec::new();
+    for word in split_words(template)? {
+        match word.as_str() {
+            "{cwd}" => args.push(cwd.to_string_lossy().to_string()),
+            "{command}" => args.extend(command.iter().cloned()),
+            _ if word.contains("{cwd}") || word.contains("{command}") => {
+                return Err(AppError::InvalidInput(format!(
+                    "终端命令模板中的占位符必须单独成词: {}",
+                    word
+                )));
+            }
+            _ => args.push(word),
+        }
+    }
+
+    if args.is_empty() {
+        return Err(AppError::InvalidInput("终端命令模板为空".to_string()));
+    }
+    let program = args.remove(0);
+    Ok(LaunchCommand { program, args })
+}
+
+/// 按当前平台检测可用终端
+fn detect(cwd: &Path, command: &[String]) -> Result<LaunchCommand> {
+    if cfg!(target_os = "macos") {
+        return Ok(macos_terminal(cwd, command));
+    }
+
+    LINUX_TERMINALS
+        .iter()
+        .find(|program| find_in_path(program).is_some())
+        .map(|program| linux_terminal(program, cwd, command))
+        .ok_or_else(|| {
+            AppError::ProcessError("未找到可用的终端，请在设置中配置终端命令模板".to_string())
+        })
+}
+
+/// macOS Terminal.app：路径与命令经 argv 传入脚本，由 `quoted form of` 负责转义
+fn macos_terminal(cwd: &Path, command: &[String]) -> LaunchCommand {
+    const SCRIPT: &str = r#"on run argv
+    set cmd to "cd " & quoted form of (item 1 of argv)
+    if (count of argv) > 1 then set cmd to cmd & " && " & (item 2 of argv)
+    tell application "Terminal"
+        activate
+        do script cmd
+    end tell
+end run"#;
+
+    let mut args = vec![
+        "-e".to_string(),
+        SCRIPT.to_string(),
+        cwd.to_string_lossy().to_string(),
+    ];
+    // 命令仅由 claude、--resume 与校验过的会话 ID 组成
+    if !command.is_empty() {
+        args.push(command.join(" "));
+    }
+
+    LaunchCommand {
+        program: "osascript".to_string(),
+        args,
+    }
+}
+
+/// 各 Linux 终端的工作目录与执行参数
+pub fn linux_terminal(program: &str, cwd: &Path, command: &[String]) -> LaunchCommand {
+    let cwd = cwd.to_string_lossy().to_string();
+    let mut args = Vec::new();
+
+    match program {
+        "gnome-terminal" => {
+            args.push(format!("--working-directory={}", cwd));
+            if !command.is_empty() {
+                args.push("--".to_string());
+            }
+        }
+        "konsole" => {
+            args.extend(["--workdir".to_string(), cwd]);
+            if !command.is_empty() {
+                args.push("-e".to_string());
+            }
+        }
+        "kitty" => args.extend(["--directory".to_string(), cwd]),
+        "alacritty" => {
+            args.extend(["--working-directory".to_string(), cwd]);
+            if !command.is_empty() {
+                args.push("-e".to_string());
+            }
+        }
+        "wezterm" => {
+            args.extend(["start".to_string(), "--cwd".to_string(), cwd]);
+            if !command.is_empty() {
+                args.push("--".to_string());
+            }
+        }
+        // xterm 没有工作目录参数，依赖子进程的当前目录
+        _ => {
+            if !command.is_empty() {
+                args.push("-e".to_string());
+            }
+        }
+    }
+    args.extend(command.iter().cloned());
+
+    LaunchCommand {
+        program: program.to_string(),
+        args,
+    }
+}
+
+/// 在 PATH 中查找可执行文件
+fn find_in_path(program: &str) -> Option<PathBuf> {
+    std::env::var_os("PATH").and_then(|paths| {
+        std::env::split_paths(&paths)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())
+    })
+}
+
+/// 按空白拆分模板，引号内的空白保留
+fn split_words(template: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in template.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err(AppError::InvalidInput(format!(
+            "终端命令模板引号未闭合: {}",
+            template
+        )));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resume_command() -> Vec<String> {
+        claude_command(Some("3f2a-01")).unwrap()
+    }
+
+    #[test]
+    fn test_template_keeps_path_as_single_argument() {
+        let cwd = Path::new("/work/it's \"my\" app; rm -rf ~");
+        let launch = from_template(
+            "wezterm start --cwd {cwd} -- {command}",
+            cwd,
+            &resume_command(),
+        )
+        .unwrap();
+
+        assert_eq!(launch.program, "wezterm");
+        assert_eq!(
+            launch.args,
+            vec![
+                "start",
+                "--cwd",
+                "/work/it's \"my\" app; rm -rf ~",
+                "--",
+                "claude",
+                "--resume",
+                "3f2a-01"
+            ]
+        );
+
+        // 引号内空白保留
+        let launch = from_template("'my term' --cwd {cwd}", cwd, &[]).unwrap();
+        assert_eq!(launch.program, "my term");
+        assert_eq!(
+            launch.args,
+            vec!["--cwd", "/work/it's \"my\" app; rm -rf ~"]
+        );
+
+        // 嵌在词中的占位符会交给 shell 解释，直接拒绝
+        assert!(from_template("alacritty -e sh -c 'cd {cwd}'", cwd, &[]).is_err());
+        assert!(from_template("foot --working-directory={cwd}", cwd, &[]).is_err());
+        assert!(from_template("xterm -e x{command}", cwd, &[]).is_err());
+
+        assert!(from_template("kitty 'unclosed", cwd, &[]).is_err());
+        assert!(from_template("   ", cwd, &[]).is_err());
+    }
+
+    #[test]
+    fn test_linux_terminal_arguments() {
+        let cwd = Path::new("/work/app");
+
+        let gnome = linux_terminal("gnome-terminal", cwd, &resume_command());
+        assert_eq!(
+            gnome.args,
+            vec![
+                "--working-directory=/work/app",
+                "--",
+                "claude",
+                "--resume",
+                "3f2a-01"
+            ]
+        );
+
+        let konsole = linux_terminal("konsole", cwd, &[]);
+        assert_eq!(konsole.args, vec!["--workdir", "/work/app"]);
+
+        let xterm = linux_terminal("xterm", cwd, &resume_command());
+        assert_eq!(xterm.args, vec!["-e", "claude", "--resume", "3f2a-01"]);
+
+        assert!(claude_command(Some("id; reboot")).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! tmux 集成
+//!
+//! 沿会话进程的祖先链查找所在的 tmux 窗格，用于聚焦窗格与通过 send-keys 发送输入。
+//! 默认连接 tmux 的默认服务器；记录窗格时同时记录服务器套接字路径，之后的操作都指向同一服务器。
+
+use crate::error::{AppError, Result};
+use crate::models::TmuxPane;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// 沿祖先链向上查找的最大层数
+const MAX_ANCESTOR_DEPTH: usize = 32;
+
+/// 发送文本时使用的 tmux 缓冲区名
+const PASTE_BUFFER: &str = "codecenter-input";
+
+/// 构建 tmux 命令，指定套接字时连接对应服务器
+fn tmux(socket: Option<&str>) -> Command {
+    let mut command = Command::new("tmux");
+    if let Some(socket) = socket {
+        command.args(["-S", socket]);
+    }
+    command
+}
+
+/// 执行 tmux 命令并检查退出状态
+fn run(mut command: Command) -> Result<String> {
+    let output = command
+        .output()
+        .map_err(|e| AppError::ProcessError(format!("执行 tmux 失败: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::ProcessError(format!(
+            "tmux 命令失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// 列出服务器上的所有窗格（窗格进程 PID -> 窗格）
+pub fn list_panes(socket: Option<&Path>) -> Result<HashMap<u32, TmuxPane>> {
+    let mut command = tmux(socket.and_then(|s| s.to_str()));
+    command.args([
+        "list-panes",
+        "-a",
+        "-F",
+        "#{pane_pid}\t#{pane_id}\t#{session_name}\t#{window_index}\t#{pane_index}\t#{socket_path}",
+    ]);
+
+    Ok(run(command)?.lines().filter_map(parse_pane_line).collect())
+}
+
+fn parse_pane_line(line: &str) -> Option<(u32, TmuxPane)> {
+    let mut fields = line.split('\t');
+    let pid = fields.next()?.parse().ok()?;
+    let pane = TmuxPane {
+        pane_id: fields.next()?.to_string(),
+        session_name: fields.next()?.to_string(),
+        window_index: fields.next()?.parse().ok()?,
+        pane_index: fields.next()?.parse().ok()?,
+        socket_path: fields.next().filter(|s| !s.is_empty()).map(String::from),
+    };
+    Some((pid, pane))
+}
+
+/// 查找进程所在的 tmux 窗格；没有 tmux 或进程不在 tmux 中时返回 None
+pub fn find_pane(socket: Option<&Path>, pid: u32) -> Option<TmuxPane> {
+    let mut panes = list_panes(socket).ok()?;
+
+    let mut current = pid;
+    for _ in 0..MAX_ANCESTOR_DEPTH {
+        if let Some(pane) = panes.remove(&current) {
+            return Some(pane);
+        }
+        match parent_pid(current) {
+            Some(parent) if parent > 1 && parent != current => current = parent,
+            _ => break,
+        }
+    }
+    None
+}
+
+/// 父进程 PID
+#[cfg(target_os = "linux")]
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // 进程名可能包含空格与括号，从最后一个右括号之后解析：状态 父进程 ...
+    let fields = &stat[stat.rfind(')')? + 1..];
+    fields.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// 父进程 PID
+#[cfg(not(target_os = "linux"))]
+fn parent_pid(pid: u32) -> Option<u32> {
+    let output = Command::new("ps")
+        .args(["-o", "ppid=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+/// 切换到窗格所在的窗口并选中窗格
+pub fn focus_pane(pane: &TmuxPane) -> Result<()> {
+    let socket = pane.socket_path.as_deref();
+
+    let mut select_window = tmux(socket);
+    select_window.args(["select-window", "-t", &pane.pane_id]);
+    run(select_window)?;
+
+    let mut select_pane = tmux(socket);
+    select_pane.args(["select-pane", "-t", &pane.pane_id]);
+    run(select_pane)?;
+
+    // 有已连接的客户端时切换过去；没有客户端时会失败，忽略即可
+    let mut switch_client = tmux(socket);
+    switch_client.args(["switch-client", "-t", &pane.pane_id]);
+    let _ = run(switch_client);
+
+    Ok(())
+}
+
+/// 向窗格发送文本并回车
+///
+/// 文本经缓冲区粘贴（应用开启括号粘贴时以括号粘贴发送），多行内容不会被逐行提交。
+pub fn send_keys(pane: &TmuxPane, text: &str) -> Result<()> {
+    let socket = pane.socket_path.as_deref();
+
+    let mut set_buffer = tmux(socket);
+    set_buffer.args(["set-buffer", "-b", PASTE_BUFFER, "--", text]);
+    run(set_buffer)?;
+
+    let mut paste = tmux(socket);
+    paste.args([
+        "paste-buffer",
+        "-p",
+        "-d",
+        "-b",
+        PASTE_BUFFER,
+        "-t",
+        &pane.pane_id,
+    ]);
+    run(paste)?;
+
+    let mut enter = tmux(socket);
+    enter.args(["send-keys", "-t", &pane.pane_id, "Enter"]);
+    run(enter)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    /// 测试专用的 tmux 服务器，结束时关闭
+    struct TestServer {
+        dir: TempDir,
+        socket: PathBuf,
+    }
+
+    impl TestServer {
+        fn start(session: &str, shell_command: &str) -> Self {
+            let dir = TempDir::new().unwrap();
+            let socket = dir.path().join("tmux.sock");
+            let status = Command::new("tmux")
+                .args(["-S", socket.to_str().unwrap(), "-f", "/dev/null"])
+                .args(["new-session", "-d", "-s", session, "-x", "80", "-y", "24"])
+                .arg(shell_command)
+                .current_dir(dir.path())
+                .status()
+                .expect("无法运行 tmux");
+            assert!(status.success(), "tmux 服务器启动失败");
+            Self { dir, socket }
+        }
+
+        fn command(&self) -> Command {
+            tmux(self.socket.to_str())
+        }
+    }
+
+    impl Drop for TestServer {
+        fn drop(&mut self) {
+            let mut command = self.command();
+            command.arg("kill-server");
+            let _ = command.output();
+        }
+    }
+
+    fn wait_for_file(path: &Path) -> String {
+        for _ in 0..100 {
+            if let Ok(content) = std::fs::read_to_string(path) {
+                if content.ends_with('\n') {
+                    return content;
+                }
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        panic!("等待文件超时: {}", path.display());
+    }
+
+    #[test]
+    fn test_parse_pane_line() {
+        let (pid, pane) = parse_pane_line("4242\t%3\twork\t1\t0\t/tmp/tmux-501/default").unwrap();
+        assert_eq!(pid, 4242);
+        assert_eq!(pane.pane_id, "%3");
+        assert_eq!(pane.session_name, "work");
+        assert_eq!(pane.window_index, 1);
+        assert_eq!(pane.socket_path.as_deref(), Some("/tmp/tmux-501/default"));
+        assert!(parse_pane_line("garbage").is_none());
+    }
+
+    #[test]
+    #[ignore = "需要本机安装 tmux，使用 cargo test -- --ignored 运行"]
+    fn test_find_focus_and_send_keys() {
+        // 窗格中的 shell 启动一个子进程，模拟运行在 shell 中的 agent
+        let server = TestServer::start(
+            "agents",
+            "sleep 60 & echo $! > child.pid; read line; echo \"$line\" > input.txt; wait",
+        );
+
+        let child_pid: u32 = wait_for_file(&server.dir.path().join("child.pid"))
+            .trim()
+            .parse()
+            .unwrap();
+        let pane = find_pane(Some(&server.socket), child_pid).expect("未找到窗格");
+        assert_eq!(pane.session_name, "agents");
+        assert_eq!(pane.socket_path.as_deref(), server.socket.to_str());
+        assert!(find_pane(Some(&server.socket), std::process::id()).is_none());
+
+        // 新建窗口后聚焦回会话所在窗口
+        let mut new_window = server.command();
+        new_window.args(["new-window", "-d", "sleep 60"]);
+        run(new_window).unwrap();
+        let mut select_new = server.command();
+        select_new.args(["select-window", "-t", "agents:1"]);
+        run(select_new).unwrap();
+
+        focus_pane(&pane).unwrap();
+        let mut active = server.command();
+        active.args(["display-message", "-p", "-t", "agents", "#{window_index}"]);
+        assert_eq!(run(active).unwrap().trim(), pane.window_index.to_string());
+
+        send_keys(&pane, "hello from codecenter").unwrap();
+        assert_eq!(
+            wait_for_file(&server.dir.path().join("input.txt")),
+            "hello from codecenter\n"
+        );
+    }
+}
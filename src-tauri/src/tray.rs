@@ -0,0 +1,383 @@
+//! 系统托盘模块
+//!
+//! 托盘提示显示各状态会话数量，菜单列出等待输入和执行阻塞的会话，
+//! 并提供打开会话、打开终端、归档等操作。
+//! 托盘内容随监控事件总线的会话快照更新，内容不变时不重建菜单。
+
+use crate::commands;
+use crate::models::{Session, SessionStatus};
+use crate::monitor::event_bus::EventSubscription;
+use crate::monitor::MonitorEvent;
+use crate::notifications::FOCUS_EVENT;
+use crate::state::AppState;
+use std::collections::HashSet;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{App, AppHandle, Emitter, Manager};
+
+/// 托盘图标 ID
+const TRAY_ID: &str = "main";
+
+/// 菜单中最多列出的会话数
+const MAX_LISTED_SESSIONS: usize = 10;
+
+/// 托盘统计的状态（按显示顺序）
+const COUNTED_STATUSES: [SessionStatus; 4] = [
+    SessionStatus::Running,
+    SessionStatus::WaitingInput,
+    SessionStatus::Blocked,
+    SessionStatus::Completed,
+];
+
+/// 托盘内容摘要
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TraySummary {
+    /// 各状态会话数量（顺序同 COUNTED_STATUSES）
+    pub counts: Vec<(SessionStatus, usize)>,
+    /// 需要关注的会话（等待输入、执行阻塞）
+    pub attention: Vec<TrayEntry>,
+}
+
+/// 菜单中的一条会话
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayEntry {
+    pub session_id: String,
+    pub label: String,
+    pub project_path: String,
+}
+
+impl TraySummary {
+    /// 从会话快照生成摘要，已归档的会话不计入
+    pub fn from_sessions(sessions: &[Session], archived: &HashSet<String>) -> Self {
+        let visible: Vec<&Session> = sessions
+            .iter()
+            .filter(|s| !s.is_archived && !archived.contains(&s.id))
+            .collect();
+
+        // 初始化中的会话按运行中统计，与前端展示一致
+        let counts = COUNTED_STATUSES
+            .iter()
+            .map(|status| {
+                let count = visible
+                    .iter()
+                    .filter(|s| {
+                        s.status == *status
+                            || (*status == SessionStatus::Running
+                                && s.status == SessionStatus::Initializing)
+                    })
+                    .count();
+                (*status, count)
+            })
+            .collect();
+
+        let mut attention: Vec<&Session> = visible
+            .into_iter()
+            .filter(|s| {
+                matches!(
+                    s.status,
+                    SessionStatus::WaitingInput | SessionStatus::Blocked
+                )
+            })
+            .collect();
+        // 阻塞优先，其次按最近活动排序
+        attention.sort_by(|a, b| {
+            (b.status == SessionStatus::Blocked)
+                .cmp(&(a.status == SessionStatus::Blocked))
+                .then(b.last_active_at.cmp(&a.last_active_at))
+        });
+
+        let attention = attention
+            .into_iter()
+            .take(MAX_LISTED_SESSIONS)
+            .map(|s| TrayEntry {
+                session_id: s.id.clone(),
+                label: format!(
+                    "[{}] {} · {}",
+                    s.status.display_name(),
+                    s.project_name,
+                    s.title
+                ),
+                project_path: s.project_path.clone(),
+            })
+            .collect();
+
+        Self { counts, attention }
+    }
+
+    /// 托盘提示文本
+    pub fn tooltip(&self) -> String {
+        let parts: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(status, count)| format!("{} {}", status.display_name(), count))
+            .collect();
+        format!("CodeCenter\n{}", parts.join(" · "))
+    }
+}
+
+/// 托盘菜单操作
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrayAction {
+    /// 显示主窗口
+    ShowWindow,
+    /// 退出应用
+    Quit,
+    /// 在主窗口中打开会话
+    OpenSession(String),
+    /// 打开会话所在项目的终端
+    OpenTerminal(String),
+    /// 归档会话
+    Archive(String),
+}
+
+impl TrayAction {
+    /// 生成菜单项 ID
+    pub fn menu_id(&self) -> String {
+        match self {
+            TrayAction::ShowWindow => "show".to_string(),
+            TrayAction::Quit => "quit".to_string(),
+            TrayAction::OpenSession(id) => format!("open:{}", id),
+            TrayAction::OpenTerminal(id) => format!("terminal:{}", id),
+            TrayAction::Archive(id) => format!("archive:{}", id),
+        }
+    }
+
+    /// 解析菜单项 ID
+    pub fn parse(menu_id: &str) -> Option<Self> {
+        match menu_id {
+            "show" => return Some(TrayAction::ShowWindow),
+            "quit" => return Some(TrayAction::Quit),
+            _ => {}
+        }
+
+        let (kind, session_id) = menu_id.split_once(':')?;
+        let session_id = session_id.to_string();
+        match kind {
+            "open" => Some(TrayAction::OpenSession(session_id)),
+            "terminal" => Some(TrayAction::OpenTerminal(session_id)),
+            "archive" => Some(TrayAction::Archive(session_id)),
+            _ => None,
+        }
+    }
+}
+
+/// 创建托盘图标并启动更新任务
+pub fn setup_tray(app: &App, events: EventSubscription) -> tauri::Result<()> {
+    let handle = app.handle().clone();
+    let menu = build_menu(&handle, &TraySummary::default())?;
+
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip("CodeCenter")
+        .show_menu_on_left_click(true)
+        .on_menu_event(|app, event| {
+            if let Some(action) = TrayAction::parse(event.id().as_ref()) {
+                handle_action(app.clone(), action);
+            }
+        });
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+
+    spawn_tray_updater(handle, events);
+    Ok(())
+}
+
+/// 订阅会话快照，内容变化时更新托盘
+fn spawn_tray_updater(app: AppHandle, mut events: EventSubscription) {
+    tauri::async_runtime::spawn(async move {
+        let mut current = TraySummary::default();
+
+        while let Some(event) = events.recv().await {
+            let MonitorEvent::Snapshot { sessions } = event else {
+                continue;
+            };
+
+            let archived = archived_ids(&app).await;
+            let summary = TraySummary::from_sessions(&sessions, &archived);
+            if summary == current {
+                continue;
+            }
+
+            if let Err(e) = apply_summary(&app, &summary) {
+                tracing::warn!("更新托盘失败: {}", e);
+                continue;
+            }
+            current = summary;
+        }
+    });
+}
+
+/// 已归档会话 ID（归档状态保存在存储中）
+async fn archived_ids(app: &AppHandle) -> HashSet<String> {
+    let Some(state) = app.try_state::<AppState>() else {
+        return HashSet::new();
+    };
+
+    match state.storage().get_archived_sessions().await {
+        Ok(sessions) => sessions.into_iter().map(|s| s.id).collect(),
+        Err(e) => {
+            tracing::debug!("读取归档会话失败: {}", e);
+            HashSet::new()
+        }
+    }
+}
+
+/// 将摘要应用到托盘提示和菜单
+fn apply_summary(app: &AppHandle, summary: &TraySummary) -> tauri::Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+
+    tray.set_tooltip(Some(summary.tooltip()))?;
+    tray.set_menu(Some(build_menu(app, summary)?))
+}
+
+/// 构建托盘菜单
+fn build_menu(app: &AppHandle, summary: &TraySummary) -> tauri::Result<Menu<tauri::Wry>> {
+    let menu = Menu::new(app)?;
+
+    for (status, count) in &summary.counts {
+        let text = format!("{}: {}", status.display_name(), count);
+        menu.append(&MenuItem::new(app, text, false, None::<&str>)?)?;
+    }
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+
+    if summary.attention.is_empty() {
+        menu.append(&MenuItem::new(
+            app,
+            "没有需要处理的会话",
+            false,
+            None::<&str>,
+        )?)?;
+    }
+    for entry in &summary.attention {
+        let id = &entry.session_id;
+        let open = menu_item(app, TrayAction::OpenSession(id.clone()), "打开会话")?;
+        let terminal = menu_item(app, TrayAction::OpenTerminal(id.clone()), "打开终端")?;
+        let archive = menu_item(app, TrayAction::Archive(id.clone()), "归档")?;
+        let submenu = Submenu::with_items(app, &entry.label, true, &[&open, &terminal, &archive])?;
+        menu.append(&submenu)?;
+    }
+
+    menu.append(&PredefinedMenuItem::separator(app)?)?;
+    menu.append(&menu_item(app, TrayAction::ShowWindow, "显示主窗口")?)?;
+    menu.append(&menu_item(app, TrayAction::Quit, "退出")?)?;
+    Ok(menu)
+}
+
+/// 创建带操作 ID 的菜单项
+fn menu_item(
+    app: &AppHandle,
+    action: TrayAction,
+    text: &str,
+) -> tauri::Result<MenuItem<tauri::Wry>> {
+    MenuItem::with_id(app, action.menu_id(), text, true, None::<&str>)
+}
+
+/// 执行托盘菜单操作
+fn handle_action(app: AppHandle, action: TrayAction) {
+    match action {
+        TrayAction::ShowWindow => show_main_window(&app),
+        TrayAction::Quit => app.exit(0),
+        TrayAction::OpenSession(session_id) => {
+            show_main_window(&app);
+            let payload = serde_json::json!({ "sessionId": session_id });
+            if let Err(e) = app.emit(FOCUS_EVENT, payload) {
+                tracing::warn!("推送会话聚焦事件失败: {}", e);
+            }
+        }
+        TrayAction::OpenTerminal(session_id) => {
+            tauri::async_runtime::spawn(async move {
+                let Some(state) = app.try_state::<AppState>() else {
+                    return;
+                };
+                let Some(session) = state.monitor.get_session(&session_id) else {
+                    tracing::warn!("会话不存在: {}", session_id);
+                    return;
+                };
+                if let Err(e) = commands::launch_terminal(&state, &session.project_path, None).await
+                {
+                    tracing::warn!("打开终端失败: {}", e);
+                }
+            });
+        }
+        TrayAction::Archive(session_id) => {
+            tauri::async_runtime::spawn(async move {
+                let Some(state) = app.try_state::<AppState>() else {
+                    return;
+                };
+                if let Err(e) = commands::set_session_archived(&state, &session_id, true).await {
+                    tracing::warn!("归档会话失败 {}: {}", session_id, e);
+                    return;
+                }
+                // 触发一次对账以发布新快照，托盘与前端随之更新
+                if let Err(e) = state.monitor.refresh().await {
+                    tracing::warn!("刷新会话失败: {}", e);
+                }
+            });
+        }
+    }
+}
+
+/// 显示并聚焦主窗口
+fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, status: SessionStatus) -> Session {
+        let mut session = Session::new(format!("会话 {}", id), "demo", "/path/to/demo");
+        session.id = id.to_string();
+        session.status = status;
+        session
+    }
+
+    #[test]
+    fn test_summary_counts_and_attention() {
+        let sessions = vec![
+            session("a", SessionStatus::Running),
+            session("b", SessionStatus::Initializing),
+            session("c", SessionStatus::WaitingInput),
+            session("d", SessionStatus::Blocked),
+            session("e", SessionStatus::WaitingInput),
+        ];
+        let archived: HashSet<String> = ["e".to_string()].into_iter().collect();
+
+        let summary = TraySummary::from_sessions(&sessions, &archived);
+
+        assert_eq!(summary.counts[0], (SessionStatus::Running, 2));
+        assert_eq!(summary.counts[1], (SessionStatus::WaitingInput, 1));
+        assert_eq!(summary.counts[2], (SessionStatus::Blocked, 1));
+        let ids: Vec<&str> = summary
+            .attention
+            .iter()
+            .map(|e| e.session_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["d", "c"]);
+        assert!(summary.tooltip().contains("等待输入 1"));
+    }
+
+    #[test]
+    fn test_menu_id_roundtrip() {
+        let actions = [
+            TrayAction::ShowWindow,
+            TrayAction::Quit,
+            TrayAction::OpenSession("sess_1".to_string()),
+            TrayAction::OpenTerminal("sess_1".to_string()),
+            TrayAction::Archive("sess_1".to_string()),
+        ];
+        for action in actions {
+            assert_eq!(TrayAction::parse(&action.menu_id()), Some(action));
+        }
+        assert_eq!(TrayAction::parse("unknown:x"), None);
+    }
+}
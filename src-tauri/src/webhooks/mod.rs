@@ -0,0 +1,396 @@
+//! 外发 Webhook 模块
+//!
+//! 订阅监控事件总线，将匹配的事件按配置的模板 POST 到外部 HTTP 地址。
+//!
+//! - `template`: 请求体模板渲染
+//!
+//! 投递失败（连接失败、超时、5xx、408、429）时按指数退避重试，
+//! 请求本身无效（如请求头不合法）时不重试；每次投递的最终结果记录在内存中的投递日志里。
+
+pub mod template;
+
+use crate::models::{AppConfig, Session, WebhookConfig};
+use crate::monitor::event_bus::EventSubscription;
+use crate::monitor::MonitorEvent;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// 投递日志保留条数
+const LOG_CAPACITY: usize = 200;
+
+/// 单次请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 退避等待上限
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// 一次 Webhook 投递记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event_type: String,
+    pub session_id: Option<String>,
+    /// 实际请求次数（含重试）
+    pub attempts: u32,
+    pub status_code: Option<u16>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub delivered_at: DateTime<Utc>,
+}
+
+/// Webhook 投递器
+///
+/// 可克隆共享，克隆体共用同一个 HTTP 客户端和投递日志。
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    log: Arc<Mutex<VecDeque<WebhookDelivery>>>,
+}
+
+impl WebhookDispatcher {
+    /// 创建投递器
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            log: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// 获取投递日志（最新的在前），可按 Webhook ID 过滤
+    pub fn deliveries(&self, webhook_id: Option<&str>) -> Vec<WebhookDelivery> {
+        let Ok(log) = self.log.lock() else {
+            return Vec::new();
+        };
+
+        log.iter()
+            .rev()
+            .filter(|d| webhook_id.is_none() || webhook_id == Some(d.webhook_id.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// 将事件分发给所有订阅了该事件的 Webhook（每个目标独立后台投递）
+    pub fn dispatch(
+        &self,
+        webhooks: &[WebhookConfig],
+        event: &MonitorEvent,
+        session: Option<&Session>,
+    ) {
+        for webhook in webhooks
+            .iter()
+            .filter(|w| w.enabled && w.accepts(event.event_type()))
+        {
+            let dispatcher = self.clone();
+            let webhook = webhook.clone();
+            let event = event.clone();
+            let session = session.cloned();
+
+            tokio::spawn(async move {
+                dispatcher.deliver(&webhook, &event, session.as_ref()).await;
+            });
+        }
+    }
+
+    /// 投递单个事件到指定 Webhook，失败时按退避策略重试
+    pub async fn deliver(
+        &self,
+        webhook: &WebhookConfig,
+        event: &MonitorEvent,
+        session: Option<&Session>,
+    ) -> WebhookDelivery {
+        let mut delivery = WebhookDelivery {
+            id: uuid::Uuid::new_v4().to_string(),
+            webhook_id: webhook.id.clone(),
+            event_type: event.event_type().to_string(),
+            session_id: event.session_id().map(str::to_string),
+            attempts: 0,
+            status_code: None,
+            success: false,
+            error: None,
+            delivered_at: Utc::now(),
+        };
+
+        match template::render(webhook.body_template.as_deref(), event, session) {
+            Ok(body) => self.send_with_retry(webhook, &body, &mut delivery).await,
+            Err(e) => delivery.error = Some(e.to_string()),
+        }
+
+        delivery.delivered_at = Utc::now();
+        if !delivery.success {
+            tracing::warn!(
+                "Webhook 投递失败 {} ({}): {}",
+                webhook.id,
+                delivery.event_type,
+                delivery.error.as_deref().unwrap_or("未知错误")
+            );
+        }
+        self.record(delivery.clone());
+        delivery
+    }
+
+    /// 发送请求，按指数退避重试
+    async fn send_with_retry(
+        &self,
+        webhook: &WebhookConfig,
+        body: &serde_json::Value,
+        delivery: &mut WebhookDelivery,
+    ) {
+        let mut backoff = Duration::from_millis(webhook.retry_backoff_ms);
+
+        loop {
+            delivery.attempts += 1;
+
+            let mut request = self
+                .client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json");
+            for (name, value) in &webhook.headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+
+            let retryable = match request.body(body.to_string()).send().await {
+                Ok(response) => {
+                    let status = response.status();
+                    delivery.status_code = Some(status.as_u16());
+                    if status.is_success() {
+                        delivery.success = true;
+                        delivery.error = None;
+                        return;
+                    }
+                    delivery.error = Some(format!("HTTP {}", status));
+                    status.is_server_error()
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => {
+                    delivery.status_code = None;
+                    delivery.error = Some(e.to_string());
+                    e.is_connect() || e.is_timeout()
+                }
+            };
+
+            if !retryable || delivery.attempts > webhook.max_retries {
+                return;
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+
+    /// 写入投递日志
+    fn record(&self, delivery: WebhookDelivery) {
+        if let Ok(mut log) = self.log.lock() {
+            if log.len() >= LOG_CAPACITY {
+                log.pop_front();
+            }
+            log.push_back(delivery);
+        }
+    }
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 启动 Webhook 分发任务
+pub fn spawn_dispatcher(
+    dispatcher: WebhookDispatcher,
+    mut events: EventSubscription,
+    config: Arc<RwLock<AppConfig>>,
+) {
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("Webhook 分发任务已启动");
+
+        // 模板需要的会话信息：结束事件先于移除该会话的快照发布，投递时仍可取到
+        let mut known: HashMap<String, Session> = HashMap::new();
+
+        while let Some(event) = events.recv().await {
+            match &event {
+                MonitorEvent::Snapshot { sessions } => {
+                    known = sessions
+                        .iter()
+                        .map(|session| (session.id.clone(), session.clone()))
+                        .collect();
+                }
+                MonitorEvent::SessionDiscovered { session } => {
+                    known.insert(session.id.clone(), session.as_ref().clone());
+                }
+                _ => {}
+            }
+
+            let webhooks = config.read().await.webhooks.clone();
+            if !webhooks.is_empty() {
+                let session = event.session_id().and_then(|id| known.get(id));
+                dispatcher.dispatch(&webhooks, &event, session);
+            }
+
+            if let MonitorEvent::SessionEnded { session_id } = &event {
+                known.remove(session_id);
+            }
+        }
+
+        tracing::info!("Webhook 分发任务已停止");
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionStatus;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 本地 HTTP 替身：按顺序返回给定状态码，并记录收到的请求体
+    async fn spawn_stand_in(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(Vec::new()));
+        let received = bodies.clone();
+
+        tokio::spawn(async move {
+            for status in statuses {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 1024];
+                let body = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    if n == 0 {
+                        break String::new();
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+
+                    let text = String::from_utf8_lossy(&buf).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|l| {
+                                l.to_ascii_lowercase()
+                                    .strip_prefix("content-length:")
+                                    .map(|v| v.trim().parse::<usize>().unwrap())
+                            })
+                            .unwrap_or(0);
+                        if buf.len() >= end + 4 + length {
+                            break text[end + 4..].to_string();
+                        }
+                    }
+                };
+
+                received.lock().unwrap().push(body);
+                let response = format!(
+                    "HTTP/1.1 {} Stand-In\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, bodies)
+    }
+
+    fn webhook(url: &str) -> WebhookConfig {
+        WebhookConfig {
+            id: "team-chat".to_string(),
+            url: url.to_string(),
+            enabled: true,
+            events: vec!["statusChanged".to_string()],
+            body_template: Some(r#"{"text": "{{sessionId}} -> {{newStatus}}"}"#.to_string()),
+            headers: HashMap::new(),
+            max_retries: 2,
+            retry_backoff_ms: 10,
+        }
+    }
+
+    fn status_changed() -> MonitorEvent {
+        MonitorEvent::StatusChanged {
+            session_id: "sess_1".to_string(),
+            old_status: SessionStatus::Running,
+            new_status: SessionStatus::Blocked,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors_then_succeeds() {
+        let (url, bodies) = spawn_stand_in(vec![500, 503, 200]).await;
+        let dispatcher = WebhookDispatcher::new();
+
+        let delivery = dispatcher
+            .deliver(&webhook(&url), &status_changed(), None)
+            .await;
+
+        assert!(delivery.success);
+        assert_eq!(delivery.attempts, 3);
+        assert_eq!(delivery.status_code, Some(200));
+
+        let bodies = bodies.lock().unwrap();
+        assert_eq!(bodies.len(), 3);
+        let body: serde_json::Value = serde_json::from_str(&bodies[0]).unwrap();
+        assert_eq!(body["text"], "sess_1 -> blocked");
+
+        assert_eq!(dispatcher.deliveries(Some("team-chat")).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_error_is_not_retried() {
+        let (url, bodies) = spawn_stand_in(vec![400, 200]).await;
+        let dispatcher = WebhookDispatcher::new();
+
+        let delivery = dispatcher
+            .deliver(&webhook(&url), &status_changed(), None)
+            .await;
+
+        assert!(!delivery.success);
+        assert_eq!(delivery.attempts, 1);
+        assert_eq!(delivery.status_code, Some(400));
+        assert_eq!(bodies.lock().unwrap().len(), 1);
+        assert!(dispatcher.deliveries(Some("other")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_request_is_not_retried() {
+        let (url, bodies) = spawn_stand_in(vec![200]).await;
+        let dispatcher = WebhookDispatcher::new();
+        let mut invalid = webhook(&url);
+        invalid
+            .headers
+            .insert("bad header".to_string(), "value".to_string());
+
+        let delivery = dispatcher.deliver(&invalid, &status_changed(), None).await;
+
+        assert!(!delivery.success);
+        assert_eq!(delivery.attempts, 1);
+        assert!(delivery.error.is_some());
+        assert!(bodies.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_connection_errors_are_retried() {
+        // 绑定后立即释放端口，连接被拒绝
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        drop(listener);
+
+        let delivery = WebhookDispatcher::new()
+            .deliver(&webhook(&url), &status_changed(), None)
+            .await;
+
+        assert!(!delivery.success);
+        assert_eq!(delivery.attempts, 3);
+    }
+}
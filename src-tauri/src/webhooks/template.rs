@@ -0,0 +1,160 @@
+//! Webhook 请求体模板
+//!
+//! 模板本身是 JSON 文本，`{{name}}` 占位符替换为转义后的字符串内容
+//! （需写在引号内），`{{payload}}` 与 `{{session}}` 替换为原始 JSON（写在引号外）。
+
+use crate::error::{AppError, Result};
+use crate::models::Session;
+use crate::monitor::MonitorEvent;
+use chrono::Utc;
+use serde_json::{json, Value};
+
+/// 渲染请求体
+///
+/// 未配置模板时使用默认结构：`{ event, timestamp, session, data }`。
+pub fn render(
+    template: Option<&str>,
+    event: &MonitorEvent,
+    session: Option<&Session>,
+) -> Result<Value> {
+    let payload = serde_json::to_value(event)?;
+    let session_json = serde_json::to_value(session)?;
+    let timestamp = Utc::now().to_rfc3339();
+
+    let Some(template) = template.filter(|t| !t.trim().is_empty()) else {
+        return Ok(json!({
+            "event": event.event_type(),
+            "timestamp": timestamp,
+            "session": session_json,
+            "data": payload,
+        }));
+    };
+
+    let field = |name: &str| {
+        payload
+            .get(name)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    };
+    let message = payload
+        .get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+
+    let payload_json = payload.to_string();
+    let session_json = session_json.to_string();
+    let vars = [
+        ("event", event.event_type()),
+        ("timestamp", timestamp.as_str()),
+        ("sessionId", event.session_id().unwrap_or_default()),
+        (
+            "projectName",
+            session.map(|s| s.project_name.as_str()).unwrap_or_default(),
+        ),
+        (
+            "projectPath",
+            session.map(|s| s.project_path.as_str()).unwrap_or_default(),
+        ),
+        (
+            "title",
+            session.map(|s| s.title.as_str()).unwrap_or_default(),
+        ),
+        ("oldStatus", field("oldStatus")),
+        ("newStatus", field("newStatus")),
+        ("message", message),
+    ];
+
+    // 单次扫描原始模板，替换进来的内容不会再被当作占位符展开
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        rendered.push_str(&rest[..start]);
+        match name {
+            "payload" => rendered.push_str(&payload_json),
+            "session" => rendered.push_str(&session_json),
+            _ => match vars.iter().find(|(var, _)| *var == name) {
+                Some((_, value)) => rendered.push_str(&escape(value)),
+                None => rendered.push_str(&rest[start..start + 4 + len]),
+            },
+        }
+        rest = &rest[start + 4 + len..];
+    }
+    rendered.push_str(rest);
+
+    serde_json::from_str(&rendered)
+        .map_err(|e| AppError::InvalidInput(format!("Webhook 模板渲染结果不是合法 JSON: {}", e)))
+}
+
+/// 转义为可嵌入 JSON 字符串字面量的内容（不含两侧引号）
+fn escape(value: &str) -> String {
+    let quoted = Value::String(value.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionStatus;
+
+    #[test]
+    fn test_render_template_with_placeholders() {
+        let mut session = Session::new("修复 \"登录\" 问题", "demo", "/path/to/demo");
+        session.id = "sess_1".to_string();
+        let event = MonitorEvent::StatusChanged {
+            session_id: "sess_1".to_string(),
+            old_status: SessionStatus::Running,
+            new_status: SessionStatus::WaitingInput,
+        };
+
+        let body = render(
+            Some(r#"{"text": "{{projectName}}: {{title}} -> {{newStatus}}", "raw": {{payload}}}"#),
+            &event,
+            Some(&session),
+        )
+        .unwrap();
+
+        assert_eq!(body["text"], "demo: 修复 \"登录\" 问题 -> waiting_input");
+        assert_eq!(body["raw"]["sessionId"], "sess_1");
+        assert_eq!(body["raw"]["type"], "statusChanged");
+    }
+
+    #[test]
+    fn test_substituted_values_are_not_expanded_again() {
+        let mut session = Session::new("{{projectName}} {{message}}", "demo", "/path/to/demo");
+        session.id = "sess_3".to_string();
+        let event = MonitorEvent::StatusChanged {
+            session_id: "sess_3".to_string(),
+            old_status: SessionStatus::Running,
+            new_status: SessionStatus::WaitingInput,
+        };
+
+        let body = render(
+            Some(r#"{"text": "{{title}} {{unknown}}", "session": {{session}}}"#),
+            &event,
+            Some(&session),
+        )
+        .unwrap();
+
+        assert_eq!(body["text"], "{{projectName}} {{message}} {{unknown}}");
+        assert_eq!(body["session"]["title"], "{{projectName}} {{message}}");
+    }
+
+    #[test]
+    fn test_render_default_and_invalid_template() {
+        let event = MonitorEvent::SessionEnded {
+            session_id: "sess_2".to_string(),
+        };
+
+        let body = render(None, &event, None).unwrap();
+        assert_eq!(body["event"], "sessionEnded");
+        assert_eq!(body["data"]["sessionId"], "sess_2");
+        assert!(body["session"].is_null());
+
+        assert!(render(Some("{\"text\": {{sessionId}}}"), &event, None).is_err());
+    }
+}
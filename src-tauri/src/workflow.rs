@@ -0,0 +1,752 @@
+//! 多会话工作流
+//!
+//! 工作流由一个触发器和一个动作组成，定义持久化于存储中：
+//! - 触发器：某会话进入指定状态，或其输出（应用启动的会话的终端输出、日志中的助手消息）匹配正则
+//! - 动作：向另一个会话发送提示、启动新会话，或弹出桌面通知
+//!
+//! 引擎订阅监控事件与包装器事件进行评估，每次执行结果记录在内存中的运行日志里。
+//! 同一工作流在冷却时间内不会重复触发，避免终端重绘或相互触发造成循环。
+
+use crate::commands::deliver_message;
+use crate::error::{AppError, Result};
+use crate::models::{MessageRole, SessionStatus};
+use crate::monitor::MonitorEvent;
+use crate::state::AppState;
+use crate::storage::Storage;
+use crate::wrapper::events::WrapperEvent;
+use crate::wrapper::permission::strip_ansi;
+use crate::wrapper::StartOptions;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tokio::sync::{broadcast, RwLock};
+
+/// 运行日志保留条数
+const LOG_CAPACITY: usize = 200;
+
+/// 同一工作流两次触发的最短间隔
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+/// 单行输出参与匹配的最大字符数（未换行的超长输出按此截断）
+const MAX_LINE_CHARS: usize = 4096;
+
+/// 工作流定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workflow {
+    /// 为空时保存时生成
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    pub trigger: WorkflowTrigger,
+    pub action: WorkflowAction,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 触发器
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WorkflowTrigger {
+    /// 会话进入指定状态
+    StatusReached {
+        session_id: String,
+        status: SessionStatus,
+    },
+    /// 会话输出中有一行匹配正则
+    OutputMatches { session_id: String, pattern: String },
+}
+
+/// 动作
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WorkflowAction {
+    /// 向会话发送提示；会话未在等待输入时加入其提示队列
+    SendPrompt { session_id: String, prompt: String },
+    /// 在伪终端中启动新会话
+    LaunchSession {
+        project_path: String,
+        prompt: Option<String>,
+        #[serde(default)]
+        options: Box<StartOptions>,
+    },
+    /// 弹出桌面通知
+    Notify {
+        title: Option<String>,
+        message: String,
+    },
+}
+
+impl WorkflowAction {
+    /// 动作类型名
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WorkflowAction::SendPrompt { .. } => "sendPrompt",
+            WorkflowAction::LaunchSession { .. } => "launchSession",
+            WorkflowAction::Notify { .. } => "notify",
+        }
+    }
+}
+
+impl Workflow {
+    /// 校验定义：名称、会话 ID 非空，正则可编译
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(AppError::InvalidInput("工作流名称为空".to_string()));
+        }
+
+        match &self.trigger {
+            WorkflowTrigger::StatusReached { session_id, .. } if session_id.is_empty() => {
+                return Err(AppError::InvalidInput("触发会话为空".to_string()));
+            }
+            WorkflowTrigger::OutputMatches {
+                session_id,
+                pattern,
+            } => {
+                if session_id.is_empty() {
+                    return Err(AppError::InvalidInput("触发会话为空".to_string()));
+                }
+                Regex::new(pattern)
+                    .map_err(|e| AppError::InvalidInput(format!("正则表达式无效: {}", e)))?;
+            }
+            _ => {}
+        }
+
+        match &self.action {
+            WorkflowAction::SendPrompt { session_id, prompt } => {
+                if session_id.is_empty() || prompt.trim().is_empty() {
+                    return Err(AppError::InvalidInput("目标会话或提示为空".to_string()));
+                }
+            }
+            WorkflowAction::LaunchSession { project_path, .. } => {
+                if project_path.is_empty() {
+                    return Err(AppError::InvalidInput("项目路径为空".to_string()));
+                }
+            }
+            WorkflowAction::Notify { message, .. } => {
+                if message.trim().is_empty() {
+                    return Err(AppError::InvalidInput("通知内容为空".to_string()));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 参与评估的观察
+#[derive(Debug, Clone)]
+pub enum Observation {
+    /// 会话状态变更
+    Status {
+        session_id: String,
+        status: SessionStatus,
+    },
+    /// 会话输出的一行文本
+    Output { session_id: String, line: String },
+}
+
+/// 已编译的正则缓存（模式 -> 正则）
+pub type PatternCache = HashMap<String, Regex>;
+
+impl WorkflowTrigger {
+    /// 观察是否命中触发器，命中时返回触发说明
+    pub fn evaluate(
+        &self,
+        observation: &Observation,
+        patterns: &mut PatternCache,
+    ) -> Option<String> {
+        match (self, observation) {
+            (
+                WorkflowTrigger::StatusReached { session_id, status },
+                Observation::Status {
+                    session_id: observed,
+                    status: new_status,
+                },
+            ) if session_id == observed && status == new_status => Some(format!(
+                "会话 {} 状态变为{}",
+                observed,
+                new_status.display_name()
+            )),
+            (
+                WorkflowTrigger::OutputMatches {
+                    session_id,
+                    pattern,
+                },
+                Observation::Output {
+                    session_id: observed,
+                    line,
+                },
+            ) if session_id == observed => {
+                if !patterns.contains_key(pattern) {
+                    patterns.insert(pattern.clone(), Regex::new(pattern).ok()?);
+                }
+                let matched = patterns.get(pattern)?.find(line)?;
+                Some(format!("会话 {} 输出匹配: {}", observed, matched.as_str()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 将终端输出切分为去除转义序列的完整行
+#[derive(Debug, Default)]
+pub struct LineSplitter {
+    partial: HashMap<String, String>,
+}
+
+impl LineSplitter {
+    /// 追加会话输出，返回新完成的非空行
+    pub fn push(&mut self, session_id: &str, data: &str) -> Vec<String> {
+        let partial = self.partial.entry(session_id.to_string()).or_default();
+        partial.push_str(data);
+
+        let mut lines = Vec::new();
+        while let Some(pos) = partial.find('\n') {
+            let line: String = partial.drain(..=pos).collect();
+            lines.push(line);
+        }
+        if partial.chars().count() > MAX_LINE_CHARS {
+            lines.push(std::mem::take(partial));
+        }
+
+        lines
+            .iter()
+            .flat_map(|line| {
+                strip_ansi(line)
+                    .lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty())
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// 会话结束后丢弃未完成的行
+    pub fn forget(&mut self, session_id: &str) {
+        self.partial.remove(session_id);
+    }
+}
+
+/// 一次工作流执行记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowRun {
+    pub id: String,
+    pub workflow_id: String,
+    pub workflow_name: String,
+    /// 触发说明
+    pub trigger: String,
+    /// 动作类型
+    pub action: String,
+    pub success: bool,
+    /// 执行结果说明
+    pub detail: Option<String>,
+    pub error: Option<String>,
+    pub ran_at: DateTime<Utc>,
+}
+
+/// 工作流引擎
+///
+/// 可克隆共享，克隆体共用同一组工作流与运行日志。
+#[derive(Clone)]
+pub struct WorkflowEngine {
+    workflows: Arc<RwLock<Vec<Workflow>>>,
+    storage: Arc<Storage>,
+    runs: Arc<Mutex<VecDeque<WorkflowRun>>>,
+    /// 各工作流最近一次触发时间
+    last_fired: Arc<Mutex<HashMap<String, Instant>>>,
+}
+
+impl WorkflowEngine {
+    pub fn new(workflows: Vec<Workflow>, storage: Arc<Storage>) -> Self {
+        Self {
+            workflows: Arc::new(RwLock::new(workflows)),
+            storage,
+            runs: Arc::new(Mutex::new(VecDeque::new())),
+            last_fired: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// 全部工作流
+    pub async fn list(&self) -> Vec<Workflow> {
+        self.workflows.read().await.clone()
+    }
+
+    /// 新建或更新工作流并保存
+    pub async fn save(&self, mut workflow: Workflow) -> Result<Workflow> {
+        workflow.validate()?;
+        if workflow.id.is_empty() {
+            workflow.id = uuid::Uuid::new_v4().to_string();
+        }
+
+        let mut workflows = self.workflows.write().await;
+        match workflows.iter_mut().find(|w| w.id == workflow.id) {
+            Some(existing) => *existing = workflow.clone(),
+            None => workflows.push(workflow.clone()),
+        }
+        self.storage.save_workflows(&workflows).await?;
+        Ok(workflow)
+    }
+
+    /// 删除工作流
+    pub async fn delete(&self, workflow_id: &str) -> Result<()> {
+        let mut workflows = self.workflows.write().await;
+        let before = workflows.len();
+        workflows.retain(|w| w.id != workflow_id);
+        if workflows.len() == before {
+            return Err(AppError::InvalidInput(format!(
+                "工作流不存在: {}",
+                workflow_id
+            )));
+        }
+        self.storage.save_workflows(&workflows).await
+    }
+
+    /// 运行日志（最新的在前），可按工作流 ID 过滤
+    pub fn runs(&self, workflow_id: Option<&str>) -> Vec<WorkflowRun> {
+        let Ok(runs) = self.runs.lock() else {
+            return Vec::new();
+        };
+
+        runs.iter()
+            .rev()
+            .filter(|r| workflow_id.is_none() || workflow_id == Some(r.workflow_id.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// 记录一次执行
+    fn record(&self, run: WorkflowRun) {
+        if let Ok(mut runs) = self.runs.lock() {
+            runs.push_back(run);
+            while runs.len() > LOG_CAPACITY {
+                runs.pop_front();
+            }
+        }
+    }
+
+    /// 评估观察，返回需要执行的工作流与触发说明（冷却中的工作流不返回）
+    pub async fn matching(
+        &self,
+        observation: &Observation,
+        patterns: &mut PatternCache,
+        now: Instant,
+    ) -> Vec<(Workflow, String)> {
+        let workflows = self.workflows.read().await;
+        let Ok(mut last_fired) = self.last_fired.lock() else {
+            return Vec::new();
+        };
+
+        let mut fired = Vec::new();
+        for workflow in workflows.iter().filter(|w| w.enabled) {
+            let Some(reason) = workflow.trigger.evaluate(observation, patterns) else {
+                continue;
+            };
+            if let Some(last) = last_fired.get(&workflow.id) {
+                if now.duration_since(*last) < COOLDOWN {
+                    tracing::debug!("工作流冷却中，忽略触发: {}", workflow.name);
+                    continue;
+                }
+            }
+            last_fired.insert(workflow.id.clone(), now);
+            fired.push((workflow.clone(), reason));
+        }
+        fired
+    }
+}
+
+/// 把监控事件转换为观察：状态变更，以及日志中助手消息的每一行
+pub fn observe(event: MonitorEvent) -> Vec<Observation> {
+    match event {
+        MonitorEvent::StatusChanged {
+            session_id,
+            new_status,
+            ..
+        } => vec![Observation::Status {
+            session_id,
+            status: new_status,
+        }],
+        MonitorEvent::NewMessage {
+            session_id,
+            message,
+        } if message.role == MessageRole::Assistant => message
+            .content
+            .lines()
+            .map(|line| Observation::Output {
+                session_id: session_id.clone(),
+                line: line.to_string(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 启动工作流引擎任务
+pub fn spawn_engine(app: AppHandle, state: AppState) {
+    let mut monitor_events = state.monitor.subscribe();
+    let mut wrapper_events = state.process_wrapper.subscribe();
+
+    tauri::async_runtime::spawn(async move {
+        tracing::info!("工作流引擎已启动");
+
+        let mut patterns = PatternCache::new();
+        let mut splitter = LineSplitter::default();
+
+        loop {
+            // 每个事件先转换为若干条观察
+            let observations: Vec<Observation> = tokio::select! {
+                event = monitor_events.recv() => match event {
+                    Some(event) => observe(event),
+                    None => break,
+                },
+                event = wrapper_events.recv() => match event {
+                    Ok(WrapperEvent::Output { session_id, data, .. }) => splitter
+                        .push(&session_id, &data)
+                        .into_iter()
+                        .map(|line| Observation::Output {
+                            session_id: session_id.clone(),
+                            line,
+                        })
+                        .collect(),
+                    Ok(WrapperEvent::Exited { session_id, .. }) => {
+                        splitter.forget(&session_id);
+                        continue;
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+            };
+
+            for observation in &observations {
+                for (workflow, reason) in state
+                    .workflows
+                    .matching(observation, &mut patterns, Instant::now())
+                    .await
+                {
+                    let app = app.clone();
+                    let state = state.clone();
+                    tokio::spawn(async move {
+                        run_workflow(&app, &state, workflow, reason).await;
+                    });
+                }
+            }
+        }
+
+        tracing::info!("工作流引擎已停止");
+    });
+}
+
+/// 执行工作流动作并记录结果
+async fn run_workflow(app: &AppHandle, state: &AppState, workflow: Workflow, reason: String) {
+    tracing::info!("工作流触发: {} ({})", workflow.name, reason);
+    let result = execute(app, state, &workflow.action).await;
+
+    if let Err(e) = &result {
+        tracing::warn!("工作流执行失败 {}: {}", workflow.name, e);
+    }
+    let (success, detail, error) = match result {
+        Ok(detail) => (true, Some(detail), None),
+        Err(e) => (false, None, Some(e)),
+    };
+    state.workflows.record(WorkflowRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        workflow_id: workflow.id,
+        workflow_name: workflow.name,
+        trigger: reason,
+        action: workflow.action.kind().to_string(),
+        success,
+        detail,
+        error,
+        ran_at: Utc::now(),
+    });
+}
+
+/// 执行动作，返回结果说明
+async fn execute(
+    app: &AppHandle,
+    state: &AppState,
+    action: &WorkflowAction,
+) -> std::result::Result<String, String> {
+    match action {
+        WorkflowAction::SendPrompt { session_id, prompt } => {
+            let waiting = state
+                .monitor
+                .get_session(session_id)
+                .map(|s| s.status == SessionStatus::WaitingInput)
+                .unwrap_or(false);
+            if waiting {
+                deliver_message(state, session_id, prompt).await?;
+                return Ok(format!("已向会话 {} 发送提示", session_id));
+            }
+
+            // 应用启动的会话忙碌时排队，进入等待输入后自动发送
+            if state.process_wrapper.can_send_input(session_id).await {
+                state
+                    .prompt_queue
+                    .update(session_id, |queue| queue.enqueue(prompt))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                return Ok(format!("会话 {} 忙碌，提示已加入队列", session_id));
+            }
+            Err(format!(
+                "会话 {} 未在等待输入，且不是由应用启动",
+                session_id
+            ))
+        }
+        WorkflowAction::LaunchSession {
+            project_path,
+            prompt,
+            options,
+        } => {
+            let path = std::path::Path::new(project_path);
+            if !path.is_dir() {
+                return Err(AppError::ProjectNotFound(project_path.clone()).to_string());
+            }
+            let session = state
+                .process_wrapper
+                .start(path, prompt.as_deref(), options.as_ref().clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(format!("已启动会话 {}", session.id))
+        }
+        WorkflowAction::Notify { title, message } => {
+            app.notification()
+                .builder()
+                .title(title.clone().unwrap_or_else(|| "工作流".to_string()))
+                .body(message.clone())
+                .show()
+                .map_err(|e| format!("发送桌面通知失败: {}", e))?;
+            Ok("已发送通知".to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn chain_workflow() -> Workflow {
+        Workflow {
+            id: String::new(),
+            name: "后端完成后更新前端".to_string(),
+            enabled: true,
+            trigger: WorkflowTrigger::StatusReached {
+                session_id: "backend".to_string(),
+                status: SessionStatus::WaitingInput,
+            },
+            action: WorkflowAction::SendPrompt {
+                session_id: "frontend".to_string(),
+                prompt: "更新 API 客户端".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_trigger_evaluation() {
+        let mut patterns = PatternCache::new();
+        let status = chain_workflow().trigger;
+        assert!(status
+            .evaluate(
+                &Observation::Status {
+                    session_id: "backend".to_string(),
+                    status: SessionStatus::WaitingInput,
+                },
+                &mut patterns,
+            )
+            .is_some());
+        assert!(status
+            .evaluate(
+                &Observation::Status {
+                    session_id: "frontend".to_string(),
+                    status: SessionStatus::WaitingInput,
+                },
+                &mut patterns,
+            )
+            .is_none());
+
+        let output = WorkflowTrigger::OutputMatches {
+            session_id: "backend".to_string(),
+            pattern: r"\d+ tests? passed".to_string(),
+        };
+        let reason = output
+            .evaluate(
+                &Observation::Output {
+                    session_id: "backend".to_string(),
+                    line: "✓ 12 tests passed".to_string(),
+                },
+                &mut patterns,
+            )
+            .unwrap();
+        assert!(reason.ends_with("12 tests passed"));
+        assert!(output
+            .evaluate(
+                &Observation::Status {
+                    session_id: "backend".to_string(),
+                    status: SessionStatus::WaitingInput,
+                },
+                &mut patterns,
+            )
+            .is_none());
+    }
+
+    #[test]
+    fn test_line_splitter_strips_ansi_and_joins_chunks() {
+        let mut splitter = LineSplitter::default();
+        assert!(splitter.push("a", "\x1b[32m12 tests ").is_empty());
+        assert_eq!(
+            splitter.push("a", "passed\x1b[0m\r\n\r\nnext"),
+            vec!["12 tests passed"]
+        );
+        assert!(splitter.push("b", "other\n") == vec!["other"]);
+        splitter.forget("a");
+        assert!(splitter.push("a", "\n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_save_validate_and_cooldown() {
+        let dir = TempDir::new().unwrap();
+        let storage = Arc::new(Storage::open(dir.path().to_path_buf()).await.unwrap());
+        let engine = WorkflowEngine::new(Vec::new(), storage.clone());
+
+        let mut invalid = chain_workflow();
+        invalid.trigger = WorkflowTrigger::OutputMatches {
+            session_id: "backend".to_string(),
+            pattern: "(".to_string(),
+        };
+        assert!(engine.save(invalid).await.is_err());
+
+        let saved = engine.save(chain_workflow()).await.unwrap();
+        assert!(!saved.id.is_empty());
+        assert_eq!(storage.load_workflows().await.unwrap(), vec![saved.clone()]);
+
+        let mut patterns = PatternCache::new();
+        let observation = Observation::Status {
+            session_id: "backend".to_string(),
+            status: SessionStatus::WaitingInput,
+        };
+        let now = Instant::now();
+        assert_eq!(
+            engine
+                .matching(&observation, &mut patterns, now)
+                .await
+                .len(),
+            1
+        );
+        // 冷却时间内不重复触发
+        assert!(engine
+            .matching(&observation, &mut patterns, now + Duration::from_secs(1))
+            .await
+            .is_empty());
+        assert_eq!(
+            engine
+                .matching(&observation, &mut patterns, now + COOLDOWN)
+                .await
+                .len(),
+            1
+        );
+
+        engine.delete(&saved.id).await.unwrap();
+        assert!(engine.delete(&saved.id).await.is_err());
+        assert!(storage.load_workflows().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_log_output_triggers_through_monitor_subscription() {
+        use crate::monitor::discovery::{self, DiscoveredSession};
+        use crate::monitor::{MonitorConfig, SessionMonitor};
+        use std::io::Write;
+
+        let root = TempDir::new().unwrap();
+        let project = root.path().join("work").join("backend");
+        let log_dir = root
+            .path()
+            .join("projects")
+            .join(discovery::encode_project_path(&project));
+        std::fs::create_dir_all(root.path().join("ide")).unwrap();
+        std::fs::create_dir_all(&log_dir).unwrap();
+        let log_path = log_dir.join("aaa.jsonl");
+        std::fs::write(&log_path, "").unwrap();
+
+        let monitor = SessionMonitor::new(MonitorConfig {
+            refresh_interval: Duration::from_secs(60),
+            roots: vec![root.path().to_path_buf()],
+        })
+        .unwrap()
+        .spawn();
+        let mut events = monitor.subscribe();
+        // 不在伪终端中运行的会话，只能通过日志观察输出
+        let session = monitor
+            .register(DiscoveredSession {
+                pid: std::process::id(),
+                project_path: project.clone(),
+                project_name: "backend".to_string(),
+                log_path: None,
+                start_time: None,
+            })
+            .await
+            .unwrap();
+
+        let storage = Arc::new(Storage::open(root.path().join("data")).await.unwrap());
+        let engine = WorkflowEngine::new(Vec::new(), storage);
+        let mut workflow = chain_workflow();
+        workflow.trigger = WorkflowTrigger::OutputMatches {
+            session_id: session.id.clone(),
+            pattern: r"\d+ tests? passed".to_string(),
+        };
+        engine.save(workflow).await.unwrap();
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&log_path)
+            .unwrap();
+        writeln!(
+            file,
+            r#"{{"type":"assistant","timestamp":"{}","message":{{"role":"assistant","content":[{{"type":"text","text":"全部完成\n✓ 12 tests passed"}}]}}}}"#,
+            Utc::now().to_rfc3339()
+        )
+        .unwrap();
+        drop(file);
+
+        let mut patterns = PatternCache::new();
+        let fired = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let event = events.recv().await.unwrap();
+                for observation in observe(event) {
+                    let fired = engine
+                        .matching(&observation, &mut patterns, Instant::now())
+                        .await;
+                    if !fired.is_empty() {
+                        return fired;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("日志中的助手消息应触发工作流");
+
+        assert_eq!(fired.len(), 1);
+        assert!(fired[0].1.ends_with("12 tests passed"));
+        monitor.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+//! Git worktree
+//!
+//! 并行运行多个 Agent 时，为每个会话在新分支上创建独立的 worktree，避免在同一检出中互相冲突。
+//! worktree 放在仓库旁的 `<仓库名>-worktrees/` 目录下；会话结束并合并或归档后清理 worktree 与分支。
+
+use crate::error::{AppError, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 未指定分支名时新分支的前缀
+const BRANCH_PREFIX: &str = "codecenter/";
+
+/// 已创建的 worktree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Worktree {
+    pub path: PathBuf,
+    pub branch: String,
+    /// 所属的主仓库
+    pub parent_repo: PathBuf,
+}
+
+/// 清理结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeCleanup {
+    pub path: PathBuf,
+    pub branch: Option<String>,
+    /// 分支是否已合并到主仓库当前检出的分支
+    pub merged: bool,
+    pub branch_deleted: bool,
+}
+
+/// 构建在指定目录执行的 git 命令
+fn git(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir);
+    command
+}
+
+/// 执行 git 命令并检查退出状态
+fn run(mut command: Command) -> Result<String> {
+    let output = command
+        .output()
+        .map_err(|e| AppError::ProcessError(format!("执行 git 失败: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::ProcessError(format!(
+            "git 命令失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// 路径所在仓库（或 worktree）的根目录
+pub fn repo_root(path: &Path) -> Result<PathBuf> {
+    let mut command = git(path);
+    command.args(["rev-parse", "--show-toplevel"]);
+    run(command)
+        .map(PathBuf::from)
+        .map_err(|_| AppError::InvalidInput(format!("不是 git 仓库: {}", path.display())))
+}
+
+/// 路径是 worktree 根目录时返回其所属的主仓库
+///
+/// 只读取 `.git` 文件与 `commondir`，不调用 git，可在监控发现会话时使用。
+pub fn parent_repo(path: &Path) -> Option<PathBuf> {
+    // 主仓库的 .git 是目录，读取失败
+    let content = std::fs::read_to_string(path.join(".git")).ok()?;
+    let gitdir = path.join(content.strip_prefix("gitdir:")?.trim());
+    // 子模块的 .git 文件同样指向别处，但没有 commondir
+    let commondir = std::fs::read_to_string(gitdir.join("commondir")).ok()?;
+    let common = gitdir.join(commondir.trim()).canonicalize().ok()?;
+
+    // 裸仓库没有工作区
+    if common.file_name()? != ".git" {
+        return None;
+    }
+    common.parent().map(Path::to_path_buf)
+}
+
+/// 在新分支上为项目创建 worktree
+///
+/// 未指定分支名时生成 `codecenter/<随机串>`；分支基于项目当前检出的提交。
+pub fn create(project_path: &Path, branch: Option<&str>) -> Result<Worktree> {
+    let root = repo_root(project_path)?;
+    let parent_repo = parent_repo(&root).unwrap_or(root);
+
+    let branch = match branch.map(str::trim).filter(|b| !b.is_empty()) {
+        Some(branch) => branch.to_string(),
+        None => format!(
+            "{}{}",
+            BRANCH_PREFIX,
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        ),
+    };
+    let mut check = git(&parent_repo);
+    check.args(["check-ref-format", "--branch", &branch]);
+    run(check).map_err(|_| AppError::InvalidInput(format!("分支名无效: {}", branch)))?;
+
+    let repo_name = parent_repo
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            AppError::InvalidInput(format!("无效的仓库路径: {}", parent_repo.display()))
+        })?;
+    let container = parent_repo
+        .parent()
+        .unwrap_or(&parent_repo)
+        .join(format!("{}-worktrees", repo_name));
+    let path = container.join(branch.replace('/', "-"));
+    if path.exists() {
+        return Err(AppError::InvalidInput(format!(
+            "worktree 目录已存在: {}",
+            path.display()
+        )));
+    }
+    std::fs::create_dir_all(&container)?;
+
+    let mut add = git(project_path);
+    add.args(["worktree", "add", "-b", &branch]).arg(&path);
+    run(add)?;
+
+    tracing::info!("已创建 worktree {} (分支 {})", path.display(), branch);
+    Ok(Worktree {
+        path,
+        branch,
+        parent_repo,
+    })
+}
+
+/// 分支是否已合并到主仓库当前检出的提交
+fn is_merged(parent_repo: &Path, branch: &str) -> Result<bool> {
+    let output = git(parent_repo)
+        .args(["merge-base", "--is-ancestor", branch, "HEAD"])
+        .output()
+        .map_err(|e| AppError::ProcessError(format!("执行 git 失败: {}", e)))?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => Err(AppError::ProcessError(format!(
+            "git 命令失败: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+/// 移除 worktree 并删除其分支
+///
+/// 分支已合并时直接清理；未合并（如会话已归档、改动不再需要）时须 `force`，
+/// 此时同时丢弃 worktree 中未提交的改动并强制删除分支。
+pub fn cleanup(worktree_path: &Path, force: bool) -> Result<WorktreeCleanup> {
+    let parent_repo = parent_repo(worktree_path).ok_or_else(|| {
+        AppError::InvalidInput(format!("不是 worktree: {}", worktree_path.display()))
+    })?;
+
+    // 分离 HEAD 时没有分支
+    let mut head = git(worktree_path);
+    head.args(["symbolic-ref", "--quiet", "--short", "HEAD"]);
+    let branch = run(head).ok().filter(|b| !b.is_empty());
+
+    let merged = match &branch {
+        Some(branch) => is_merged(&parent_repo, branch)?,
+        None => false,
+    };
+    if !merged && !force {
+        return Err(AppError::InvalidInput(format!(
+            "分支 {} 尚未合并，确认放弃改动后强制清理",
+            branch.as_deref().unwrap_or("HEAD")
+        )));
+    }
+
+    let mut remove = git(&parent_repo);
+    remove.args(["worktree", "remove"]);
+    if force {
+        remove.arg("--force");
+    }
+    remove.arg(worktree_path);
+    run(remove)?;
+
+    let mut branch_deleted = false;
+    if let Some(branch) = &branch {
+        let mut delete = git(&parent_repo);
+        delete.args(["branch", if merged { "-d" } else { "-D" }, branch]);
+        match run(delete) {
+            Ok(_) => branch_deleted = true,
+            Err(e) => tracing::warn!("删除分支 {} 失败: {}", branch, e),
+        }
+    }
+
+    tracing::info!("已清理 worktree {}", worktree_path.display());
+    Ok(WorktreeCleanup {
+        path: worktree_path.to_path_buf(),
+        branch,
+        merged,
+        branch_deleted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn git_ok(dir: &Path, args: &[&str]) -> String {
+        let mut command = git(dir);
+        command.args(["-c", "user.name=test", "-c", "user.email=test@example.com"]);
+        command.args(args);
+        run(command).unwrap()
+    }
+
+    fn init_repo(dir: &TempDir) -> PathBuf {
+        let repo = dir.path().join("app");
+        std::fs::create_dir(&repo).unwrap();
+        git_ok(&repo, &["init", "-q"]);
+        git_ok(&repo, &["commit", "-q", "--allow-empty", "-m", "init"]);
+        repo.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_create_and_cleanup_merged_worktree() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(&dir);
+        assert!(parent_repo(&repo).is_none());
+        assert!(create(&repo, Some("bad..name")).is_err());
+
+        let worktree = create(&repo, Some("feature/login")).unwrap();
+        assert_eq!(worktree.parent_repo, repo);
+        assert_eq!(
+            worktree.path,
+            repo.parent().unwrap().join("app-worktrees/feature-login")
+        );
+        assert_eq!(parent_repo(&worktree.path), Some(repo.clone()));
+        assert!(create(&repo, Some("feature/login")).is_err());
+
+        // 未合并的提交需要强制清理
+        std::fs::write(worktree.path.join("login.rs"), "fn login() {}").unwrap();
+        git_ok(&worktree.path, &["add", "login.rs"]);
+        git_ok(&worktree.path, &["commit", "-q", "-m", "login"]);
+        assert!(cleanup(&worktree.path, false).is_err());
+
+        git_ok(&repo, &["merge", "-q", "--ff-only", "feature/login"]);
+        let report = cleanup(&worktree.path, false).unwrap();
+        assert!(report.merged && report.branch_deleted);
+        assert_eq!(report.branch.as_deref(), Some("feature/login"));
+        assert!(!worktree.path.exists());
+        assert!(git_ok(&repo, &["branch", "--list", "feature/login"]).is_empty());
+    }
+
+    #[test]
+    fn test_force_cleanup_discards_unmerged_worktree() {
+        let dir = TempDir::new().unwrap();
+        let repo = init_repo(&dir);
+
+        // 从子目录创建也归属同一仓库，默认生成分支名
+        std::fs::create_dir(repo.join("src")).unwrap();
+        let worktree = create(&repo.join("src"), None).unwrap();
+        assert!(worktree.branch.starts_with(BRANCH_PREFIX));
+        assert_eq!(worktree.parent_repo, repo);
+
+        std::fs::write(worktree.path.join("wip.txt"), "未提交").unwrap();
+        git_ok(
+            &worktree.path,
+            &["commit", "-q", "--allow-empty", "-m", "wip"],
+        );
+
+        let report = cleanup(&worktree.path, true).unwrap();
+        assert!(!report.merged && report.branch_deleted);
+        assert!(!worktree.path.exists());
+        assert!(cleanup(&worktree.path, true).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+//! 终端输出环形缓冲
+//!
+//! 保留最近一段原始终端输出（含 ANSI 转义序列），供前端附加时回放。
+//! 每段输出带有在整个输出流中的字节偏移，前端据此丢弃与回放内容重复的事件。
+
+use serde::{Deserialize, Serialize};
+
+/// 默认缓冲容量（字节）
+pub const DEFAULT_CAPACITY: usize = 256 * 1024;
+
+/// 缓冲内容快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalSnapshot {
+    /// 缓冲中的输出
+    pub data: String,
+    /// 快照末尾在输出流中的字节偏移
+    pub offset: u64,
+}
+
+/// 有界输出缓冲
+#[derive(Debug)]
+pub struct OutputBuffer {
+    data: String,
+    capacity: usize,
+    /// 已写入的总字节数
+    total: u64,
+}
+
+impl Default for OutputBuffer {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+impl OutputBuffer {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            data: String::new(),
+            capacity,
+            total: 0,
+        }
+    }
+
+    /// 追加输出，返回这段输出在输出流中的起始偏移
+    pub fn push(&mut self, chunk: &str) -> u64 {
+        let offset = self.total;
+        self.total += chunk.len() as u64;
+        self.data.push_str(chunk);
+
+        if self.data.len() > self.capacity {
+            let mut cut = self.data.len() - self.capacity;
+            while !self.data.is_char_boundary(cut) {
+                cut += 1;
+            }
+            // 附近有换行时从整行处截断，避免回放从半行或半个转义序列开始
+            if let Some(pos) = self.data[cut..].find('\n') {
+                if pos < self.capacity / 4 {
+                    cut += pos + 1;
+                }
+            }
+            self.data.drain(..cut);
+        }
+
+        offset
+    }
+
+    /// 已写入的总字节数（输出流末尾偏移）
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// 流偏移 `since` 之后的输出，最多取末尾 `max_bytes` 字节
+    pub fn tail(&self, since: u64, max_bytes: usize) -> &str {
+        let start = self.total - self.data.len() as u64;
+        let skip = since.saturating_sub(start).min(self.data.len() as u64) as usize;
+        let mut from = skip.max(self.data.len().saturating_sub(max_bytes));
+        while !self.data.is_char_boundary(from) {
+            from += 1;
+        }
+        &self.data[from..]
+    }
+
+    /// 当前缓冲内容
+    pub fn snapshot(&self) -> TerminalSnapshot {
+        TerminalSnapshot {
+            data: self.data.clone(),
+            offset: self.total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trims_to_whole_lines_and_tracks_offset() {
+        let mut buffer = OutputBuffer::with_capacity(8);
+
+        assert_eq!(buffer.push("abc\n"), 0);
+        assert_eq!(buffer.push("def\n"), 4);
+        assert_eq!(buffer.push("gh\n"), 8);
+
+        let snapshot = buffer.snapshot();
+        assert_eq!(snapshot.data, "def\ngh\n");
+        assert_eq!(snapshot.offset, 11);
+        assert_eq!(buffer.tail(8, 100), "gh\n");
+        assert_eq!(buffer.tail(0, 3), "gh\n");
+
+        // 附近没有换行时按字符边界截断
+        buffer.push("四五六");
+        assert_eq!(buffer.snapshot().data, "五六");
+        assert_eq!(buffer.snapshot().offset, 20);
+    }
+}
@@ -0,0 +1,58 @@
+//! 包装器事件定义
+
+use super::permission::PermissionPrompt;
+use serde::Serialize;
+
+/// 应用启动的 PTY 会话事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WrapperEvent {
+    /// 终端输出（保留 ANSI 转义序列），offset 为该段在输出流中的起始字节偏移
+    Output {
+        session_id: String,
+        data: String,
+        offset: u64,
+    },
+    /// 进程退出
+    Exited {
+        session_id: String,
+        exit_code: Option<u32>,
+    },
+    /// 读写错误
+    Error { session_id: String, message: String },
+    /// 会话停在权限确认界面
+    PermissionRequested {
+        session_id: String,
+        prompt: PermissionPrompt,
+    },
+    /// 权限确认界面已消失（已处理或被取消）
+    PermissionResolved { session_id: String },
+}
+
+impl WrapperEvent {
+    /// 推送给前端的事件名
+    pub fn ui_event_name(&self) -> &'static str {
+        match self {
+            WrapperEvent::Output { .. } => "wrapper:output",
+            WrapperEvent::Exited { .. } => "wrapper:exited",
+            WrapperEvent::Error { .. } => "wrapper:error",
+            WrapperEvent::PermissionRequested { .. } => "wrapper:permission-requested",
+            WrapperEvent::PermissionResolved { .. } => "wrapper:permission-resolved",
+        }
+    }
+
+    /// 事件所属会话 ID
+    pub fn session_id(&self) -> &str {
+        match self {
+            WrapperEvent::Output { session_id, .. }
+            | WrapperEvent::Exited { session_id, .. }
+            | WrapperEvent::Error { session_id, .. }
+            | WrapperEvent::PermissionRequested { session_id, .. }
+            | WrapperEvent::PermissionResolved { session_id } => session_id,
+        }
+    }
+}
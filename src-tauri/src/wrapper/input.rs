@@ -0,0 +1,136 @@
+//! 输入确认
+//!
+//! 向终端写入消息后，以 jsonl 日志中出现对应的 `user` 事件作为送达确认。
+
+use crate::error::{AppError, Result};
+use crate::monitor::handle::MonitorHandle;
+use serde_json::Value;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 等待日志确认消息送达的超时时间
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 轮询日志的间隔
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 发送前的日志位置（日志路径与长度）
+pub type LogBaseline = Option<(PathBuf, u64)>;
+
+/// 记录发送前的日志位置，之后只在新写入的部分中查找确认
+pub async fn log_baseline(monitor: &MonitorHandle, project_path: &str) -> Result<LogBaseline> {
+    Ok(monitor.find_latest_log(project_path).await?.map(|path| {
+        let len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        (path, len)
+    }))
+}
+
+/// 等待日志中出现对应的 `user` 事件
+///
+/// `is_alive` 返回 false 时提前结束等待。
+pub async fn confirm_delivery<F>(
+    monitor: &MonitorHandle,
+    project_path: &str,
+    baseline: LogBaseline,
+    content: &str,
+    is_alive: F,
+) -> Result<()>
+where
+    F: Fn() -> bool,
+{
+    let deadline = tokio::time::Instant::now() + CONFIRM_TIMEOUT;
+    loop {
+        // 首条消息会创建新的日志文件，此时从头查找
+        if let Some(log_path) = monitor.find_latest_log(project_path).await? {
+            let offset = match &baseline {
+                Some((path, len)) if *path == log_path => *len,
+                _ => 0,
+            };
+            if log_has_user_message(&log_path, offset, content)? {
+                return Ok(());
+            }
+        }
+
+        if !is_alive() {
+            return Err(AppError::ProcessError("会话进程已退出".to_string()));
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(AppError::ProcessError(
+                "消息已写入终端，但未在日志中确认送达".to_string(),
+            ));
+        }
+        tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+    }
+}
+
+/// 将消息编码为终端输入：多行内容使用括号粘贴，避免换行被当作提交
+pub fn encode_message(content: &str) -> String {
+    if content.contains('\n') {
+        format!("\x1b[200~{}\x1b[201~\r", content)
+    } else {
+        format!("{}\r", content)
+    }
+}
+
+/// 日志从指定偏移起是否包含内容匹配的 `user` 事件
+pub fn log_has_user_message(log_path: &Path, offset: u64, content: &str) -> Result<bool> {
+    let mut file = std::fs::File::open(log_path)?;
+    let offset = offset.min(file.metadata()?.len());
+    file.seek(SeekFrom::Start(offset))?;
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    let text = String::from_utf8_lossy(&buf);
+
+    Ok(text.lines().any(|line| is_user_message(line, content)))
+}
+
+/// 单行日志是否为内容匹配的 `user` 事件（写入到一半的行解析失败即视为不匹配）
+pub fn is_user_message(line: &str, content: &str) -> bool {
+    let Ok(event) = serde_json::from_str::<Value>(line) else {
+        return false;
+    };
+    if event.get("type").and_then(Value::as_str) != Some("user") {
+        return false;
+    }
+
+    let text = match event.pointer("/message/content") {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(blocks)) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return false,
+    };
+
+    text.trim() == content.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_user_events_only() {
+        let plain = r#"{"type":"user","message":{"role":"user","content":"运行测试"}}"#;
+        let blocks = r#"{"type":"user","message":{"role":"user","content":[{"type":"text","text":"运行测试\n"}]}}"#;
+        let assistant =
+            r#"{"type":"assistant","message":{"role":"assistant","content":"运行测试"}}"#;
+        let tool_result = r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","content":"运行测试"}]}}"#;
+
+        assert!(is_user_message(plain, "运行测试"));
+        assert!(is_user_message(blocks, "运行测试"));
+        assert!(!is_user_message(assistant, "运行测试"));
+        assert!(!is_user_message(tool_result, "运行测试"));
+        assert!(!is_user_message(r#"{"type":"user","mess"#, "运行测试"));
+    }
+
+    #[test]
+    fn test_multiline_uses_bracketed_paste() {
+        assert_eq!(encode_message("hi"), "hi\r");
+        assert_eq!(encode_message("a\nb"), "\x1b[200~a\nb\x1b[201~\r");
+    }
+}
@@ -0,0 +1,1042 @@
+//! Claude Code 进程包装器
+//!
+//! 在应用持有的伪终端中启动 `claude`，使应用能够读写会话终端。
+//!
+//! # 模块结构
+//!
+//! - `buffer`: 终端输出环形缓冲
+//! - `events`: 包装器事件定义
+//! - `input`: 消息编码与送达确认
+//! - `permission`: 权限确认提示识别与应答
+//! - `pty`: 伪终端创建、读写与大小调整
+//!
+//! 启动的会话会立即携带 PID 登记到 `SessionMonitor`，
+//! 进程退出后自动注销并发布会话结束事件。
+
+pub mod buffer;
+pub mod events;
+pub mod input;
+pub mod permission;
+pub mod pty;
+
+use crate::error::{AppError, Result};
+use crate::models::{LaunchProfile, Session};
+use crate::monitor::discovery::DiscoveredSession;
+use crate::monitor::handle::MonitorHandle;
+use crate::monitor::session_id_for_path;
+use buffer::{OutputBuffer, TerminalSnapshot};
+use chrono::{DateTime, Utc};
+use events::WrapperEvent;
+use permission::{PermissionDecision, PermissionPrompt, PromptOption};
+use portable_pty::{ChildKiller, CommandBuilder, MasterPty};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, RwLock};
+
+/// 默认启动的程序
+const DEFAULT_PROGRAM: &str = "claude";
+
+/// 默认终端大小
+const DEFAULT_ROWS: u16 = 40;
+const DEFAULT_COLS: u16 = 120;
+
+/// 事件通道容量
+const EVENT_CAPACITY: usize = 1024;
+
+/// 启动选项
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StartOptions {
+    /// 恢复的 Claude Code 会话 ID（--resume）
+    pub resume: Option<String>,
+    /// 模型（--model）
+    pub model: Option<String>,
+    /// 权限模式（--permission-mode）
+    pub permission_mode: Option<String>,
+    /// 允许的工具（--allowedTools）
+    pub allowed_tools: Vec<String>,
+    /// 禁用的工具（--disallowedTools）
+    pub disallowed_tools: Vec<String>,
+    /// 追加的系统提示（--append-system-prompt）
+    pub append_system_prompt: Option<String>,
+    /// 额外命令行参数
+    pub extra_args: Vec<String>,
+    /// 额外环境变量
+    pub env: HashMap<String, String>,
+    /// 终端行数
+    pub rows: Option<u16>,
+    /// 终端列数
+    pub cols: Option<u16>,
+}
+
+impl StartOptions {
+    /// 由项目的启动配置生成启动选项，启动配置中的环境变量覆盖项目环境变量
+    pub fn from_profile(profile: &LaunchProfile, project_env: &HashMap<String, String>) -> Self {
+        let mut env = project_env.clone();
+        env.extend(profile.env_vars.clone());
+
+        Self {
+            model: profile.model.clone(),
+            permission_mode: profile.permission_mode.clone(),
+            allowed_tools: profile.allowed_tools.clone(),
+            disallowed_tools: profile.disallowed_tools.clone(),
+            append_system_prompt: profile.append_system_prompt.clone(),
+            env,
+            ..Self::default()
+        }
+    }
+
+    /// 以 `defaults` 补全未设置的选项；环境变量合并，本选项中的同名变量优先
+    pub fn with_defaults(self, defaults: StartOptions) -> Self {
+        fn or_vec(value: Vec<String>, default: Vec<String>) -> Vec<String> {
+            if value.is_empty() {
+                default
+            } else {
+                value
+            }
+        }
+
+        let mut env = defaults.env;
+        env.extend(self.env);
+
+        Self {
+            resume: self.resume.or(defaults.resume),
+            model: self.model.or(defaults.model),
+            permission_mode: self.permission_mode.or(defaults.permission_mode),
+            allowed_tools: or_vec(self.allowed_tools, defaults.allowed_tools),
+            disallowed_tools: or_vec(self.disallowed_tools, defaults.disallowed_tools),
+            append_system_prompt: self.append_system_prompt.or(defaults.append_system_prompt),
+            extra_args: or_vec(self.extra_args, defaults.extra_args),
+            env,
+            rows: self.rows.or(defaults.rows),
+            cols: self.cols.or(defaults.cols),
+        }
+    }
+
+    /// 生成命令行参数，初始提示作为最后一个位置参数
+    pub fn to_args(&self, initial_prompt: Option<&str>) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(session_id) = &self.resume {
+            args.push("--resume".to_string());
+            args.push(session_id.clone());
+        }
+
+        if let Some(model) = &self.model {
+            args.push("--model".to_string());
+            args.push(model.clone());
+        }
+        if let Some(mode) = &self.permission_mode {
+            args.push("--permission-mode".to_string());
+            args.push(mode.clone());
+        }
+        // 工具列表合为一个参数，避免可变长参数吞掉后面的初始提示
+        if !self.allowed_tools.is_empty() {
+            args.push("--allowedTools".to_string());
+            args.push(self.allowed_tools.join(","));
+        }
+        if !self.disallowed_tools.is_empty() {
+            args.push("--disallowedTools".to_string());
+            args.push(self.disallowed_tools.join(","));
+        }
+        if let Some(prompt) = self
+            .append_system_prompt
+            .as_ref()
+            .filter(|p| !p.trim().is_empty())
+        {
+            args.push("--append-system-prompt".to_string());
+            args.push(prompt.clone());
+        }
+        args.extend(self.extra_args.iter().cloned());
+
+        if let Some(prompt) = initial_prompt.filter(|p| !p.trim().is_empty()) {
+            args.push(prompt.to_string());
+        }
+
+        args
+    }
+}
+
+/// 运行在伪终端中的 Claude Code 进程
+pub struct ClaudeProcessWrapper {
+    session_id: String,
+    pid: u32,
+    project_path: PathBuf,
+    started_at: DateTime<Utc>,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    killer: Mutex<Box<dyn ChildKiller + Send + Sync>>,
+    running: Arc<AtomicBool>,
+    /// 最近的终端输出
+    output: Arc<Mutex<OutputBuffer>>,
+    /// 已附加的前端视图数
+    subscribers: AtomicUsize,
+    /// 最近一次应答权限提示时的输出流偏移，之前的输出不再识别提示
+    prompt_answered_at: AtomicU64,
+    /// 当前权限提示是否已发布事件
+    prompt_reported: AtomicBool,
+}
+
+impl ClaudeProcessWrapper {
+    /// 会话 ID
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// 进程 PID
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// 项目路径
+    pub fn project_path(&self) -> &Path {
+        &self.project_path
+    }
+
+    /// 启动时间
+    pub fn started_at(&self) -> DateTime<Utc> {
+        self.started_at
+    }
+
+    /// 进程是否仍在运行
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// 向终端写入原始输入
+    pub fn send_input(&self, content: &str) -> Result<()> {
+        if !self.is_running() {
+            return Err(AppError::ProcessError("会话进程已退出".to_string()));
+        }
+
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| AppError::Internal("终端写入锁已损坏".to_string()))?;
+        writer.write_all(content.as_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// 中断当前操作（等同在终端中按 Esc）
+    pub fn interrupt(&self) -> Result<()> {
+        self.send_input("\x1b")
+    }
+
+    /// 调整终端大小
+    pub fn resize(&self, rows: u16, cols: u16) -> Result<()> {
+        let master = self
+            .master
+            .lock()
+            .map_err(|_| AppError::Internal("终端锁已损坏".to_string()))?;
+        pty::resize_pty(master.as_ref(), rows, cols)
+    }
+
+    /// 附加终端视图：开始推送输出，返回缓冲中的输出供回放
+    pub fn attach(&self) -> Result<TerminalSnapshot> {
+        // 持有缓冲锁期间登记，保证回放与之后推送的输出首尾相接
+        let output = self
+            .output
+            .lock()
+            .map_err(|_| AppError::Internal("终端输出锁已损坏".to_string()))?;
+        self.subscribers.fetch_add(1, Ordering::SeqCst);
+        Ok(output.snapshot())
+    }
+
+    /// 脱离终端视图，最后一个视图脱离后停止推送输出
+    pub fn detach(&self) {
+        let _ = self
+            .subscribers
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+    }
+
+    /// 是否有已附加的终端视图
+    pub fn is_attached(&self) -> bool {
+        self.subscribers.load(Ordering::SeqCst) > 0
+    }
+
+    /// 终端画面底部的权限提示（问句与选项）
+    pub fn permission_screen(&self) -> Option<(String, Vec<PromptOption>)> {
+        let screen = {
+            let output = self.output.lock().ok()?;
+            output
+                .tail(
+                    self.prompt_answered_at.load(Ordering::SeqCst),
+                    permission::SCREEN_BYTES,
+                )
+                .to_string()
+        };
+        permission::parse_screen(&screen)
+    }
+
+    /// 应答权限提示：发送按键，并忽略此前输出中的提示
+    pub fn answer_permission(&self, keys: &str) -> Result<()> {
+        let total = self
+            .output
+            .lock()
+            .map_err(|_| AppError::Internal("终端输出锁已损坏".to_string()))?
+            .total();
+        self.send_input(keys)?;
+        self.prompt_answered_at.store(total, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// 强制结束进程
+    pub fn terminate(&self) -> Result<()> {
+        let mut killer = self
+            .killer
+            .lock()
+            .map_err(|_| AppError::Internal("进程锁已损坏".to_string()))?;
+        killer
+            .kill()
+            .map_err(|e| AppError::ProcessError(format!("结束进程失败: {}", e)))
+    }
+}
+
+/// 进程包装器管理器
+///
+/// 可在各个命令间自由克隆共享，克隆体共用同一组会话。
+#[derive(Clone)]
+pub struct ProcessWrapperManager {
+    /// 应用启动的会话（会话 ID -> 进程）
+    sessions: Arc<RwLock<HashMap<String, Arc<ClaudeProcessWrapper>>>>,
+    /// 包装器事件发送器
+    events: broadcast::Sender<WrapperEvent>,
+    /// 监控句柄（用于登记与注销会话）
+    monitor: MonitorHandle,
+    /// 启动的程序
+    program: String,
+}
+
+impl ProcessWrapperManager {
+    /// 创建管理器，启动 `claude`
+    pub fn new(monitor: MonitorHandle) -> Self {
+        Self::with_program(monitor, DEFAULT_PROGRAM)
+    }
+
+    /// 创建管理器，启动指定程序（测试时可替换为假的 agent 脚本）
+    pub fn with_program(monitor: MonitorHandle, program: impl Into<String>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        let manager = Self {
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            events,
+            monitor,
+            program: program.into(),
+        };
+        manager.spawn_reaper();
+        manager.spawn_permission_watcher();
+        manager
+    }
+
+    /// 订阅包装器事件
+    pub fn subscribe(&self) -> broadcast::Receiver<WrapperEvent> {
+        self.events.subscribe()
+    }
+
+    /// 获取应用启动的会话
+    pub async fn get(&self, session_id: &str) -> Option<Arc<ClaudeProcessWrapper>> {
+        self.sessions.read().await.get(session_id).cloned()
+    }
+
+    /// 所有应用启动的会话
+    pub async fn list(&self) -> Vec<Arc<ClaudeProcessWrapper>> {
+        self.sessions.read().await.values().cloned().collect()
+    }
+
+    /// 附加会话终端视图，返回缓冲中的输出
+    pub async fn attach(&self, session_id: &str) -> Result<TerminalSnapshot> {
+        self.get(session_id)
+            .await
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?
+            .attach()
+    }
+
+    /// 脱离会话终端视图
+    pub async fn detach(&self, session_id: &str) {
+        if let Some(wrapper) = self.get(session_id).await {
+            wrapper.detach();
+        }
+    }
+
+    /// 调整会话终端大小
+    pub async fn resize(&self, session_id: &str, rows: u16, cols: u16) -> Result<()> {
+        if rows == 0 || cols == 0 {
+            return Err(AppError::InvalidInput("终端大小无效".to_string()));
+        }
+        self.get(session_id)
+            .await
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?
+            .resize(rows, cols)
+    }
+
+    /// 事件是否需要推送给前端：输出只推送给已附加的会话，其余事件总是推送
+    pub async fn should_forward(&self, event: &WrapperEvent) -> bool {
+        match event {
+            WrapperEvent::Output { session_id, .. } => self
+                .get(session_id)
+                .await
+                .map(|wrapper| wrapper.is_attached())
+                .unwrap_or(false),
+            _ => true,
+        }
+    }
+
+    /// 会话能否接收输入（由应用启动且进程仍在运行）
+    pub async fn can_send_input(&self, session_id: &str) -> bool {
+        self.get(session_id)
+            .await
+            .map(|wrapper| wrapper.is_running())
+            .unwrap_or(false)
+    }
+
+    /// 向会话发送消息，等待日志中出现对应的 `user` 事件后返回
+    pub async fn send_message(&self, session_id: &str, content: &str) -> Result<()> {
+        let content = content.trim();
+        if content.is_empty() {
+            return Err(AppError::InvalidInput("消息内容为空".to_string()));
+        }
+
+        let wrapper = self
+            .get(session_id)
+            .await
+            .filter(|wrapper| wrapper.is_running())
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!("会话不是由应用启动或已退出: {}", session_id))
+            })?;
+        let project_path = wrapper.project_path().to_string_lossy().to_string();
+
+        let baseline = input::log_baseline(&self.monitor, &project_path).await?;
+        wrapper.send_input(&input::encode_message(content))?;
+        input::confirm_delivery(&self.monitor, &project_path, baseline, content, || {
+            wrapper.is_running()
+        })
+        .await
+    }
+
+    /// 会话当前的权限提示，没有停在权限确认界面时返回 None
+    pub async fn permission_prompt(&self, session_id: &str) -> Result<Option<PermissionPrompt>> {
+        let wrapper = self
+            .get(session_id)
+            .await
+            .ok_or_else(|| AppError::SessionNotFound(session_id.to_string()))?;
+        let Some((question, options)) = wrapper.permission_screen() else {
+            return Ok(None);
+        };
+
+        let project_path = wrapper.project_path().to_string_lossy().to_string();
+        let tool = match self.monitor.find_latest_log(&project_path).await? {
+            Some(log_path) => {
+                tokio::task::spawn_blocking(move || permission::pending_tool_use(&log_path))
+                    .await
+                    .map_err(|e| AppError::Internal(format!("读取日志任务失败: {}", e)))?
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("读取待确认的工具调用失败: {}", e);
+                        None
+                    })
+            }
+            None => None,
+        };
+
+        Ok(Some(PermissionPrompt {
+            session_id: session_id.to_string(),
+            question,
+            options,
+            tool,
+        }))
+    }
+
+    /// 应答会话的权限提示
+    pub async fn respond_permission(
+        &self,
+        session_id: &str,
+        decision: PermissionDecision,
+    ) -> Result<()> {
+        let wrapper = self
+            .get(session_id)
+            .await
+            .filter(|wrapper| wrapper.is_running())
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!("会话不是由应用启动或已退出: {}", session_id))
+            })?;
+        let (_, options) = wrapper.permission_screen().ok_or_else(|| {
+            AppError::InvalidInput(format!("会话当前没有待确认的权限请求: {}", session_id))
+        })?;
+        let keys = permission::keystroke(&options, decision)
+            .ok_or_else(|| AppError::InvalidInput("该权限请求不支持此操作".to_string()))?;
+
+        tracing::info!("应答权限请求 {}: {:?}", session_id, decision);
+        wrapper.answer_permission(&keys)
+    }
+
+    /// 在伪终端中启动会话，并立即登记到监控
+    pub async fn start(
+        &self,
+        project_path: &Path,
+        initial_prompt: Option<&str>,
+        options: StartOptions,
+    ) -> Result<Session> {
+        let session_id = session_id_for_path(project_path);
+        // 会话 ID 由项目路径决定：检查与登记在同一把写锁内完成，
+        // 同一项目的并发启动只有一个成功，其余被拒绝
+        let mut sessions = self.sessions.write().await;
+        if let Some(existing) = sessions.get(&session_id) {
+            if existing.is_running() {
+                return Err(AppError::InvalidInput(format!(
+                    "项目已有运行中的会话: {}",
+                    project_path.display()
+                )));
+            }
+        }
+
+        let mut command = CommandBuilder::new(&self.program);
+        command.args(options.to_args(initial_prompt));
+        command.cwd(project_path);
+        command.env("TERM", "xterm-256color");
+        for (key, value) in &options.env {
+            command.env(key, value);
+        }
+
+        let process = pty::spawn_pty_command(
+            command,
+            options.rows.unwrap_or(DEFAULT_ROWS),
+            options.cols.unwrap_or(DEFAULT_COLS),
+        )?;
+        let mut child = process.child;
+        let pid = child
+            .process_id()
+            .ok_or_else(|| AppError::ProcessError("无法获取进程 PID".to_string()))?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let wrapper = Arc::new(ClaudeProcessWrapper {
+            session_id: session_id.clone(),
+            pid,
+            project_path: project_path.to_path_buf(),
+            started_at: Utc::now(),
+            master: Mutex::new(process.master),
+            writer: Mutex::new(process.writer),
+            killer: Mutex::new(child.clone_killer()),
+            running: running.clone(),
+            output: Arc::new(Mutex::new(OutputBuffer::default())),
+            subscribers: AtomicUsize::new(0),
+            prompt_answered_at: AtomicU64::new(0),
+            prompt_reported: AtomicBool::new(false),
+        });
+        sessions.insert(session_id.clone(), wrapper.clone());
+        drop(sessions);
+
+        // 读取线程：转发输出，读到 EOF 后回收进程并发布退出事件
+        let events = self.events.clone();
+        let reader_id = session_id.clone();
+        let output = wrapper.output.clone();
+        let mut reader = process.reader;
+        std::thread::spawn(move || {
+            let mut decoder = pty::Utf8Decoder::default();
+            let mut buf = [0u8; 8192];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        let data = decoder.push(&buf[..n]);
+                        if data.is_empty() {
+                            continue;
+                        }
+                        // 写入缓冲与发布事件在同一把锁内完成，与附加操作互斥
+                        let Ok(mut output) = output.lock() else {
+                            break;
+                        };
+                        let offset = output.push(&data);
+                        let _ = events.send(WrapperEvent::Output {
+                            session_id: reader_id.clone(),
+                            data,
+                            offset,
+                        });
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    // Linux 上子进程退出后读主端返回 EIO，视为结束
+                    Err(_) => break,
+                }
+            }
+
+            let exit_code = child.wait().ok().map(|status| status.exit_code());
+            running.store(false, Ordering::SeqCst);
+            let _ = events.send(WrapperEvent::Exited {
+                session_id: reader_id,
+                exit_code,
+            });
+        });
+
+        tracing::info!(
+            "已启动会话 {} (pid={}) 于 {}",
+            session_id,
+            pid,
+            project_path.display()
+        );
+
+        let project_name = project_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| project_path.to_string_lossy().to_string());
+        let discovered = DiscoveredSession {
+            pid,
+            project_path: project_path.to_path_buf(),
+            project_name,
+            log_path: None,
+            start_time: Some(wrapper.started_at),
+        };
+
+        match self.monitor.register(discovered).await {
+            Ok(session) => Ok(session),
+            Err(e) => {
+                let _ = wrapper.terminate();
+                self.sessions.write().await.remove(&session_id);
+                Err(e)
+            }
+        }
+    }
+
+    /// 进程退出后移除会话并从监控注销
+    fn spawn_reaper(&self) {
+        let mut events = self.subscribe();
+        let sessions = self.sessions.clone();
+        let monitor = self.monitor.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let session_id = match events.recv().await {
+                    Ok(WrapperEvent::Exited { session_id, .. }) => session_id,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                // 同一项目可能已重新启动，只移除已退出的进程
+                let removed = {
+                    let mut sessions = sessions.write().await;
+                    match sessions.get(&session_id) {
+                        Some(wrapper) if !wrapper.is_running() => {
+                            sessions.remove(&session_id);
+                            true
+                        }
+                        _ => false,
+                    }
+                };
+
+                if removed {
+                    tracing::info!("会话进程已退出: {}", session_id);
+                    if let Err(e) = monitor.unregister(&session_id).await {
+                        tracing::warn!("注销会话失败 {}: {}", session_id, e);
+                    }
+                }
+            }
+        });
+    }
+
+    /// 随终端输出识别权限提示，出现与消失时发布事件
+    fn spawn_permission_watcher(&self) {
+        let mut events = self.subscribe();
+        let manager = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let session_id = match events.recv().await {
+                    Ok(WrapperEvent::Output { session_id, .. }) => session_id,
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(wrapper) = manager.get(&session_id).await else {
+                    continue;
+                };
+
+                let visible = wrapper.permission_screen().is_some();
+                let reported = wrapper.prompt_reported.load(Ordering::SeqCst);
+                if visible && !reported {
+                    match manager.permission_prompt(&session_id).await {
+                        Ok(Some(prompt)) => {
+                            wrapper.prompt_reported.store(true, Ordering::SeqCst);
+                            let _ = manager
+                                .events
+                                .send(WrapperEvent::PermissionRequested { session_id, prompt });
+                        }
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("识别权限提示失败 {}: {}", session_id, e),
+                    }
+                } else if !visible && reported {
+                    wrapper.prompt_reported.store(false, Ordering::SeqCst);
+                    let _ = manager
+                        .events
+                        .send(WrapperEvent::PermissionResolved { session_id });
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SessionStatus;
+    use crate::monitor::discovery::SessionDiscovery;
+    use crate::monitor::{MonitorConfig, SessionMonitor};
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    /// 假的 agent 脚本：打印参数后等待一行输入
+    fn fake_agent(dir: &Path) -> PathBuf {
+        let path = dir.join("fake-claude.sh");
+        std::fs::write(
+            &path,
+            "#!/bin/sh\necho \"ready: $*\"\nread line\necho \"got: $line\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// 模拟 Claude Code 的 agent 脚本：每读到一行输入就追加一条 `user` 日志事件
+    fn fake_logging_agent(dir: &Path) -> PathBuf {
+        let path = dir.join("fake-claude-log.sh");
+        std::fs::write(
+            &path,
+            concat!(
+                "#!/bin/sh\n",
+                "mkdir -p \"$(dirname \"$FAKE_LOG\")\"\n",
+                "while IFS= read -r line; do\n",
+                "  printf '{\"type\":\"user\",\"message\":{\"role\":\"user\",\"content\":\"%s\"}}\\n' \"$line\" >> \"$FAKE_LOG\"\n",
+                "done\n",
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// 模拟权限确认界面的 agent 脚本：写入待确认的工具调用，读取一个按键
+    fn fake_permission_agent(dir: &Path) -> PathBuf {
+        let path = dir.join("fake-claude-permission.sh");
+        std::fs::write(
+            &path,
+            concat!(
+                "#!/bin/sh\n",
+                "mkdir -p \"$(dirname \"$FAKE_LOG\")\"\n",
+                "printf '{\"type\":\"assistant\",\"message\":{\"content\":[{\"type\":\"tool_use\",\"id\":\"t1\",\"name\":\"Bash\",\"input\":{\"command\":\"npm test\"}}]}}\\n' >> \"$FAKE_LOG\"\n",
+                "stty -icanon -echo\n",
+                "printf 'Bash command\\n\\n  npm test\\n\\nDo you want to proceed?\\n> 1. Yes\\n  2. Yes, always allow npm test\\n  3. No\\n'\n",
+                "key=$(dd bs=1 count=1 2>/dev/null)\n",
+                "echo \"answer: $key\"\n",
+                "sleep 30\n",
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    async fn next_event<F>(
+        rx: &mut broadcast::Receiver<WrapperEvent>,
+        mut matches: F,
+    ) -> WrapperEvent
+    where
+        F: FnMut(&WrapperEvent) -> bool,
+    {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let event = rx.recv().await.unwrap();
+                if matches(&event) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .expect("等待包装器事件超时")
+    }
+
+    #[test]
+    fn test_start_options_args() {
+        let options = StartOptions {
+            model: Some("sonnet".to_string()),
+            extra_args: vec!["--verbose".to_string()],
+            ..StartOptions::default()
+        };
+
+        assert_eq!(
+            options.to_args(Some("修复测试")),
+            vec!["--model", "sonnet", "--verbose", "修复测试"]
+        );
+        assert_eq!(options.to_args(Some("  ")).len(), 3);
+
+        let resume = StartOptions {
+            resume: Some("3f2a".to_string()),
+            ..StartOptions::default()
+        };
+        assert_eq!(resume.to_args(None), vec!["--resume", "3f2a"]);
+    }
+
+    #[test]
+    fn test_profile_options_with_overrides() {
+        let profile = LaunchProfile {
+            name: "review".to_string(),
+            model: Some("opus".to_string()),
+            permission_mode: Some("plan".to_string()),
+            allowed_tools: vec!["Read".to_string(), "Bash(git diff:*)".to_string()],
+            append_system_prompt: Some("只做代码审查".to_string()),
+            env_vars: HashMap::from([("LOG_LEVEL".to_string(), "debug".to_string())]),
+            ..LaunchProfile::default()
+        };
+        let project_env = HashMap::from([
+            ("LOG_LEVEL".to_string(), "info".to_string()),
+            ("API_URL".to_string(), "http://localhost".to_string()),
+        ]);
+
+        let from_profile = StartOptions::from_profile(&profile, &project_env);
+        assert_eq!(from_profile.env["LOG_LEVEL"], "debug");
+        assert_eq!(from_profile.env["API_URL"], "http://localhost");
+
+        // 显式选项优先于启动配置
+        let options = StartOptions {
+            model: Some("sonnet".to_string()),
+            ..StartOptions::default()
+        }
+        .with_defaults(from_profile);
+        assert_eq!(
+            options.to_args(Some("审查最近的提交")),
+            vec![
+                "--model",
+                "sonnet",
+                "--permission-mode",
+                "plan",
+                "--allowedTools",
+                "Read,Bash(git diff:*)",
+                "--append-system-prompt",
+                "只做代码审查",
+                "审查最近的提交"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_session_registers_with_pid() {
+        let claude_root = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let monitor = SessionMonitor::new(MonitorConfig {
+            refresh_interval: Duration::from_secs(60),
+            roots: vec![claude_root.path().to_path_buf()],
+        })
+        .unwrap()
+        .spawn();
+        let manager = ProcessWrapperManager::with_program(
+            monitor.clone(),
+            fake_agent(project.path()).to_string_lossy(),
+        );
+        let mut events = manager.subscribe();
+
+        let session = manager
+            .start(project.path(), Some("hello"), StartOptions::default())
+            .await
+            .unwrap();
+
+        // 登记后立即出现在快照中
+        let snapshot = monitor.get_session(&session.id).unwrap();
+        assert_eq!(snapshot.status, SessionStatus::Initializing);
+        let wrapper = manager.get(&session.id).await.unwrap();
+        assert!(SessionDiscovery::process_exists(wrapper.pid()));
+
+        let output = next_event(
+            &mut events,
+            |e| matches!(e, WrapperEvent::Output { data, .. } if data.contains("ready: hello")),
+        )
+        .await;
+        assert_eq!(output.session_id(), session.id);
+
+        // 未附加时不推送输出；附加后回放缓冲并开始推送
+        assert!(!manager.should_forward(&output).await);
+        let snapshot = manager.attach(&session.id).await.unwrap();
+        assert!(snapshot.data.contains("ready: hello"));
+        assert_eq!(snapshot.offset, snapshot.data.len() as u64);
+        assert!(manager.should_forward(&output).await);
+        manager.resize(&session.id, 30, 100).await.unwrap();
+        assert!(manager.resize(&session.id, 0, 100).await.is_err());
+        manager.detach(&session.id).await;
+        manager.detach(&session.id).await;
+        assert!(!manager.should_forward(&output).await);
+
+        // 再次启动同一项目会被拒绝
+        assert!(manager
+            .start(project.path(), None, StartOptions::default())
+            .await
+            .is_err());
+
+        // 进程退出后自动注销
+        wrapper.send_input("bye\n").unwrap();
+        next_event(&mut events, |e| matches!(e, WrapperEvent::Exited { .. })).await;
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while monitor.get_session(&session.id).is_some() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("会话未被注销");
+        assert!(manager.get(&session.id).await.is_none());
+
+        monitor.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_start_same_project_rejected() {
+        let claude_root = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let monitor = SessionMonitor::new(MonitorConfig {
+            refresh_interval: Duration::from_secs(60),
+            roots: vec![claude_root.path().to_path_buf()],
+        })
+        .unwrap()
+        .spawn();
+        let manager = ProcessWrapperManager::with_program(
+            monitor.clone(),
+            fake_agent(project.path()).to_string_lossy(),
+        );
+
+        let (first, second) = tokio::join!(
+            manager.start(project.path(), None, StartOptions::default()),
+            manager.start(project.path(), None, StartOptions::default()),
+        );
+        let started: Vec<_> = [first, second].into_iter().filter_map(|r| r.ok()).collect();
+        assert_eq!(started.len(), 1);
+        assert_eq!(manager.list().await.len(), 1);
+
+        let wrapper = manager.get(&started[0].id).await.unwrap();
+        wrapper.terminate().unwrap();
+        monitor.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_message_confirmed_by_log() {
+        let claude_root = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let monitor = SessionMonitor::new(MonitorConfig {
+            refresh_interval: Duration::from_secs(60),
+            roots: vec![claude_root.path().to_path_buf()],
+        })
+        .unwrap()
+        .spawn();
+        let manager = ProcessWrapperManager::with_program(
+            monitor.clone(),
+            fake_logging_agent(project.path()).to_string_lossy(),
+        );
+
+        let encoded = project.path().to_string_lossy().replace('/', "-");
+        let log_path = claude_root
+            .path()
+            .join("projects")
+            .join(encoded)
+            .join("session.jsonl");
+        let options = StartOptions {
+            env: HashMap::from([(
+                "FAKE_LOG".to_string(),
+                log_path.to_string_lossy().to_string(),
+            )]),
+            ..StartOptions::default()
+        };
+
+        assert!(!manager.can_send_input("unknown").await);
+        assert!(manager.send_message("unknown", "hi").await.is_err());
+
+        let session = manager.start(project.path(), None, options).await.unwrap();
+        assert!(manager.can_send_input(&session.id).await);
+
+        // 首条消息创建日志文件，第二条消息只在新增内容中确认
+        manager.send_message(&session.id, "第一条").await.unwrap();
+        manager.send_message(&session.id, "second").await.unwrap();
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        assert_eq!(log.lines().count(), 2);
+
+        manager.get(&session.id).await.unwrap().terminate().unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while manager.can_send_input(&session.id).await {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("进程未退出");
+        assert!(manager.send_message(&session.id, "late").await.is_err());
+
+        monitor.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_permission_prompt_detected_and_answered() {
+        let claude_root = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let monitor = SessionMonitor::new(MonitorConfig {
+            refresh_interval: Duration::from_secs(60),
+            roots: vec![claude_root.path().to_path_buf()],
+        })
+        .unwrap()
+        .spawn();
+        let manager = ProcessWrapperManager::with_program(
+            monitor.clone(),
+            fake_permission_agent(project.path()).to_string_lossy(),
+        );
+        let mut events = manager.subscribe();
+
+        let encoded = project.path().to_string_lossy().replace('/', "-");
+        let log_path = claude_root
+            .path()
+            .join("projects")
+            .join(encoded)
+            .join("session.jsonl");
+        let options = StartOptions {
+            env: HashMap::from([(
+                "FAKE_LOG".to_string(),
+                log_path.to_string_lossy().to_string(),
+            )]),
+            ..StartOptions::default()
+        };
+        let session = manager.start(project.path(), None, options).await.unwrap();
+
+        let WrapperEvent::PermissionRequested { prompt, .. } = next_event(&mut events, |e| {
+            matches!(e, WrapperEvent::PermissionRequested { .. })
+        })
+        .await
+        else {
+            unreachable!()
+        };
+        assert_eq!(prompt.question, "Do you want to proceed?");
+        assert_eq!(prompt.options.len(), 3);
+        let tool = prompt.tool.unwrap();
+        assert_eq!(tool.name, "Bash");
+        assert_eq!(tool.input["command"], "npm test");
+
+        manager
+            .respond_permission(&session.id, PermissionDecision::ApproveAlways)
+            .await
+            .unwrap();
+        next_event(&mut events, |e| {
+            matches!(e, WrapperEvent::PermissionResolved { .. })
+        })
+        .await;
+        assert!(manager
+            .attach(&session.id)
+            .await
+            .unwrap()
+            .data
+            .contains("answer: 2"));
+        assert!(manager
+            .permission_prompt(&session.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(manager
+            .respond_permission(&session.id, PermissionDecision::Deny)
+            .await
+            .is_err());
+
+        manager.get(&session.id).await.unwrap().terminate().unwrap();
+        monitor.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,311 @@
+//! 权限确认
+//!
+//! Claude Code 在执行工具前停在权限确认界面时，从终端画面识别提示与选项，
+//! 从 jsonl 日志中取出尚未返回结果的 `tool_use`，供前端展示工具名与参数，
+//! 并把批准、始终批准、拒绝换算为对应选项的按键。
+
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// 识别权限提示时检查的终端输出末尾字节数
+pub const SCREEN_BYTES: usize = 8 * 1024;
+
+/// 读取日志末尾的字节数（待确认的工具调用总在最近的事件中）
+const LOG_TAIL_BYTES: u64 = 512 * 1024;
+
+/// 最后一个选项之后允许的非空行数（操作提示等），超出说明提示已不在画面底部
+const MAX_TRAILING_LINES: usize = 3;
+
+/// 权限提示的问句
+const QUESTION_MARKERS: &[&str] = &["Do you want to", "Do you want"];
+
+/// 对权限提示的处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionDecision {
+    /// 批准本次调用
+    Approve,
+    /// 批准并不再询问同类调用
+    ApproveAlways,
+    /// 拒绝
+    Deny,
+}
+
+/// 提示中的选项
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptOption {
+    pub number: u32,
+    pub label: String,
+}
+
+/// 等待确认的工具调用
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingToolUse {
+    pub id: String,
+    pub name: String,
+    pub input: Value,
+}
+
+/// 会话当前的权限提示
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionPrompt {
+    pub session_id: String,
+    /// 提示问句
+    pub question: String,
+    pub options: Vec<PromptOption>,
+    /// 日志中待确认的工具调用（日志尚未写入时为空）
+    pub tool: Option<PendingToolUse>,
+}
+
+/// 去除 ANSI 转义序列，回车视为换行
+pub fn strip_ansi(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI：参数与中间字节后跟一个结束字节
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC：以 BEL 或 ESC \ 结束
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\r' => {
+                if chars.peek() != Some(&'\n') {
+                    out.push('\n');
+                }
+            }
+            c if c.is_control() && c != '\n' && c != '\t' => {}
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// 从终端输出中识别位于画面底部的权限提示，返回问句与选项
+pub fn parse_screen(raw: &str) -> Option<(String, Vec<PromptOption>)> {
+    let text = strip_ansi(raw);
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    let question_index = lines
+        .iter()
+        .rposition(|line| QUESTION_MARKERS.iter().any(|m| line.contains(m)))?;
+
+    let mut options: Vec<PromptOption> = Vec::new();
+    let mut last_option_index = question_index;
+    for (index, line) in lines.iter().enumerate().skip(question_index + 1) {
+        let Some(option) = parse_option(line) else {
+            continue;
+        };
+        // 同一提示重绘时选项会重复出现，只保留最后一次
+        if option.number == 1 {
+            options.clear();
+        }
+        if option.number as usize == options.len() + 1 {
+            options.push(option);
+            last_option_index = index;
+        }
+    }
+
+    if options.len() < 2 || lines.len() - 1 - last_option_index > MAX_TRAILING_LINES {
+        return None;
+    }
+    Some((lines[question_index].to_string(), options))
+}
+
+/// 解析选项行，如 `❯ 1. Yes`、`2. Yes, and don't ask again`
+fn parse_option(line: &str) -> Option<PromptOption> {
+    let line = line.trim_start_matches(['❯', '>', '›', ' ']);
+    let (number, label) = line.split_once(". ")?;
+    let number = number.parse().ok()?;
+    let label = label.trim();
+    (!label.is_empty()).then(|| PromptOption {
+        number,
+        label: label.to_string(),
+    })
+}
+
+/// 处理方式对应的按键；提示中没有对应选项时返回 None
+pub fn keystroke(options: &[PromptOption], decision: PermissionDecision) -> Option<String> {
+    let is_always = |label: &str| label.contains("don't ask again") || label.contains("always");
+
+    let option = options.iter().find(|option| {
+        let label = option.label.to_lowercase();
+        match decision {
+            PermissionDecision::Approve => label.starts_with("yes") && !is_always(&label),
+            PermissionDecision::ApproveAlways => label.starts_with("yes") && is_always(&label),
+            PermissionDecision::Deny => label.starts_with("no"),
+        }
+    });
+
+    match (option, decision) {
+        (Some(option), _) => Some(option.number.to_string()),
+        // 没有拒绝选项时按 Esc 取消
+        (None, PermissionDecision::Deny) => Some("\x1b".to_string()),
+        (None, _) => None,
+    }
+}
+
+/// 日志中最后一个尚未返回结果的 `tool_use`
+pub fn pending_tool_use(log_path: &Path) -> Result<Option<PendingToolUse>> {
+    let mut file = std::fs::File::open(log_path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(LOG_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)?;
+    let text = String::from_utf8_lossy(&buf);
+
+    let mut pending: Vec<PendingToolUse> = Vec::new();
+    // 从文件中间开始读时第一行不完整，解析失败会被跳过
+    for line in text.lines() {
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        let Some(Value::Array(blocks)) = event.pointer("/message/content") else {
+            continue;
+        };
+
+        match event.get("type").and_then(Value::as_str) {
+            Some("assistant") => {
+                pending.extend(
+                    blocks
+                        .iter()
+                        .filter(|b| b.get("type").and_then(Value::as_str) == Some("tool_use"))
+                        .filter_map(|b| {
+                            Some(PendingToolUse {
+                                id: b.get("id")?.as_str()?.to_string(),
+                                name: b.get("name")?.as_str()?.to_string(),
+                                input: b.get("input").cloned().unwrap_or(Value::Null),
+                            })
+                        }),
+                );
+            }
+            Some("user") => {
+                for id in blocks
+                    .iter()
+                    .filter(|b| b.get("type").and_then(Value::as_str) == Some("tool_result"))
+                    .filter_map(|b| b.get("tool_use_id").and_then(Value::as_str))
+                {
+                    pending.retain(|tool| tool.id != id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(pending.pop())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const PROMPT: &str = concat!(
+        "\x1b[2K\x1b[1A\x1b[38;5;174mBash command\x1b[39m\r\n",
+        "\r\n",
+        "  npm test\r\n",
+        "\r\n",
+        "\x1b[1mDo you want to proceed?\x1b[22m\r\n",
+        "\x1b[36m❯ 1. Yes\x1b[39m\r\n",
+        "  2. Yes, and don't ask again for npm test commands in /work/app\r\n",
+        "  3. No, and tell Claude what to do differently (esc)\r\n",
+    );
+
+    #[test]
+    fn test_parse_prompt_at_bottom_of_screen() {
+        assert_eq!(strip_ansi("\x1b]0;title\x07a\x1b[31mb\x1b[0m\rc"), "ab\nc");
+
+        let (question, options) = parse_screen(PROMPT).unwrap();
+        assert_eq!(question, "Do you want to proceed?");
+        assert_eq!(options.len(), 3);
+        assert_eq!(options[0].label, "Yes");
+
+        // 提示重绘后仍只识别一组选项
+        let redrawn = format!("{}{}", PROMPT, PROMPT);
+        assert_eq!(parse_screen(&redrawn).unwrap().1.len(), 3);
+
+        // 提示之后有更多输出时说明已处理
+        let later = format!("{}⏺ Running tests\n✓ 12 passed\nok\ndone\n", PROMPT);
+        assert!(parse_screen(&later).is_none());
+        assert!(parse_screen("Do you want pizza?\n").is_none());
+    }
+
+    #[test]
+    fn test_keystrokes_follow_option_labels() {
+        let (_, options) = parse_screen(PROMPT).unwrap();
+        assert_eq!(
+            keystroke(&options, PermissionDecision::Approve).unwrap(),
+            "1"
+        );
+        assert_eq!(
+            keystroke(&options, PermissionDecision::ApproveAlways).unwrap(),
+            "2"
+        );
+        assert_eq!(keystroke(&options, PermissionDecision::Deny).unwrap(), "3");
+
+        // 两个选项的提示中拒绝是第 2 项，且没有始终批准
+        let (_, options) = parse_screen("Do you want to make this edit?\n1. Yes\n2. No\n").unwrap();
+        assert_eq!(keystroke(&options, PermissionDecision::Deny).unwrap(), "2");
+        assert!(keystroke(&options, PermissionDecision::ApproveAlways).is_none());
+    }
+
+    #[test]
+    fn test_pending_tool_use_from_log() {
+        let dir = TempDir::new().unwrap();
+        let log = dir.path().join("session.jsonl");
+        std::fs::write(
+            &log,
+            concat!(
+                r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"t1","name":"Read","input":{"file_path":"a.rs"}}]}}"#,
+                "\n",
+                r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t1","content":"..."}]}}"#,
+                "\n",
+                r#"{"type":"assistant","message":{"content":[{"type":"text","text":"运行测试"},{"type":"tool_use","id":"t2","name":"Bash","input":{"command":"npm test"}}]}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        let tool = pending_tool_use(&log).unwrap().unwrap();
+        assert_eq!(tool.name, "Bash");
+        assert_eq!(tool.input["command"], "npm test");
+
+        let mut content = std::fs::read_to_string(&log).unwrap();
+        content.push_str(
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","tool_use_id":"t2","content":"ok"}]}}"#,
+        );
+        std::fs::write(&log, content).unwrap();
+        assert!(pending_tool_use(&log).unwrap().is_none());
+    }
+}
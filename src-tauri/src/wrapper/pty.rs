@@ -0,0 +1,105 @@
+//! PTY 工具函数
+//!
+//! 基于 portable-pty 创建伪终端并启动命令。读取在独立线程中阻塞进行，
+//! 输出按 UTF-8 解码，跨读取边界的不完整字符留待下次拼接。
+
+use crate::error::{AppError, Result};
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use std::io::{Read, Write};
+
+/// 已启动的 PTY 进程
+pub struct PtyProcess {
+    pub master: Box<dyn MasterPty + Send>,
+    pub child: Box<dyn Child + Send + Sync>,
+    pub reader: Box<dyn Read + Send>,
+    pub writer: Box<dyn Write + Send>,
+}
+
+/// 在新的伪终端中启动命令
+pub fn spawn_pty_command(command: CommandBuilder, rows: u16, cols: u16) -> Result<PtyProcess> {
+    let pair = native_pty_system()
+        .openpty(pty_size(rows, cols))
+        .map_err(|e| AppError::ProcessError(format!("创建伪终端失败: {}", e)))?;
+
+    let child = pair
+        .slave
+        .spawn_command(command)
+        .map_err(|e| AppError::ProcessError(format!("启动进程失败: {}", e)))?;
+    // 子进程已持有从端，父进程及时关闭以便子进程退出时读端收到 EOF
+    drop(pair.slave);
+
+    let reader = pair
+        .master
+        .try_clone_reader()
+        .map_err(|e| AppError::ProcessError(format!("获取终端读取端失败: {}", e)))?;
+    let writer = pair
+        .master
+        .take_writer()
+        .map_err(|e| AppError::ProcessError(format!("获取终端写入端失败: {}", e)))?;
+
+    Ok(PtyProcess {
+        master: pair.master,
+        child,
+        reader,
+        writer,
+    })
+}
+
+/// 调整终端大小
+pub fn resize_pty(master: &dyn MasterPty, rows: u16, cols: u16) -> Result<()> {
+    master
+        .resize(pty_size(rows, cols))
+        .map_err(|e| AppError::ProcessError(format!("调整终端大小失败: {}", e)))
+}
+
+fn pty_size(rows: u16, cols: u16) -> PtySize {
+    PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+/// 流式 UTF-8 解码器
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    /// 追加字节并返回可完整解码的文本；无效字节替换为 U+FFFD
+    pub fn push(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+
+        let valid_up_to = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // 结尾是不完整的字符时保留，其余部分（含无效字节）照常输出
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.pending.len(),
+        };
+
+        let rest = self.pending.split_off(valid_up_to);
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending = rest;
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_joins_split_characters() {
+        let mut decoder = Utf8Decoder::default();
+        let bytes = "\x1b[32m完成\x1b[0m".as_bytes();
+
+        // 在“完”字中间切开
+        let first = decoder.push(&bytes[..6]);
+        let second = decoder.push(&bytes[6..]);
+
+        assert_eq!(first, "\x1b[32m");
+        assert_eq!(format!("{}{}", first, second), "\x1b[32m完成\x1b[0m");
+    }
+}